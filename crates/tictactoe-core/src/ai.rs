@@ -0,0 +1,805 @@
+use crate::mcts::{self, Budget};
+use crate::position::{CellState, Move, Position};
+use crate::rng::Rng;
+use crate::rules::Rule;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// How long the AI may think per move unless the player picks something else.
+pub const DEFAULT_THINK_TIME: Duration = Duration::from_millis(500);
+
+// Big enough that no variant's static score comes near it
+const WIN_SCORE: f32 = 1_000_000.0;
+
+/// Scores beyond this either way are proven wins or losses rather than
+/// `evaluate` guesses.
+pub const PROVEN_SCORE: f32 = WIN_SCORE / 2.0;
+
+// Checking the clock on every node is measurable, so only do it this often
+const NODES_PER_TIME_CHECK: u64 = 1024;
+
+// Fixed seed for the Zobrist keys, so hashes are the same on every run
+const ZOBRIST_SEED: u64 = 0x5EED_2B0B_715A_C7E5;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+    Perfect,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 4] = [
+        Difficulty::Easy,
+        Difficulty::Medium,
+        Difficulty::Hard,
+        Difficulty::Perfect,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "Easy",
+            Difficulty::Medium => "Medium",
+            Difficulty::Hard => "Hard",
+            Difficulty::Perfect => "Perfect",
+        }
+    }
+}
+
+/// The search behind every difficulty above Easy.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Engine {
+    /// Alpha-beta search. Exact on small boards.
+    Minimax,
+    /// Monte Carlo Tree Search. Better on big boards, where minimax can't
+    /// see far enough ahead.
+    Mcts,
+}
+
+impl Engine {
+    pub const ALL: [Engine; 2] = [Engine::Minimax, Engine::Mcts];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Engine::Minimax => "Minimax",
+            Engine::Mcts => "MCTS",
+        }
+    }
+}
+
+// Medium only looks at its own move and the reply to it
+const MEDIUM_DEPTH: usize = 2;
+
+// MCTS has no depth to limit, so Medium gets a small, fixed number of games
+const MEDIUM_PLAYOUTS: u32 = 200;
+
+// How often Hard deliberately plays something other than its best move
+const HARD_MISTAKE_CHANCE: f32 = 0.2;
+
+/// Picks a move for `ai_symbol` with `engine`, searching for at most
+/// `think_time`. All randomness comes from `rng`; given the same seed,
+/// position and settings the move only differs if the clock stops the search
+/// at a different point.
+pub fn find_best_move(
+    position: &Position,
+    ai_symbol: CellState,
+    difficulty: Difficulty,
+    engine: Engine,
+    think_time: Duration,
+    rng: &mut Rng,
+) -> Option<Move> {
+    let moves = position.empty_cells();
+    if moves.is_empty() {
+        return None;
+    }
+    if difficulty == Difficulty::Easy {
+        return Some(moves[rng.gen_index(moves.len())]);
+    }
+
+    // Higher is better for both engines: a minimax score or an MCTS visit count
+    let scored = match (engine, difficulty) {
+        (Engine::Minimax, Difficulty::Medium) => search_scores(
+            &mut Scratch::new(position, ai_symbol),
+            MEDIUM_DEPTH,
+            think_time,
+        ),
+        (Engine::Minimax, _) => search_scores(
+            &mut Scratch::new(position, ai_symbol),
+            usize::MAX,
+            think_time,
+        ),
+        (Engine::Mcts, _) => {
+            let budget = if difficulty == Difficulty::Medium {
+                Budget::Playouts(MEDIUM_PLAYOUTS)
+            } else {
+                Budget::Time(think_time)
+            };
+            mcts::visit_counts(position, ai_symbol, budget, rng)
+                .into_iter()
+                .map(|(mv, visits)| (mv, visits as f32))
+                .collect()
+        }
+    };
+
+    let (best, best_score) = best_of(&scored);
+    if difficulty == Difficulty::Hard {
+        let mistakes: Vec<Move> = scored
+            .iter()
+            .filter(|(_, score)| *score < best_score)
+            .map(|(mv, _)| *mv)
+            .collect();
+
+        if !mistakes.is_empty() && rng.gen_f32() < HARD_MISTAKE_CHANCE {
+            return Some(mistakes[rng.gen_index(mistakes.len())]);
+        }
+    }
+    Some(best)
+}
+
+/// What a move leads to if both sides play on perfectly.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Evaluation {
+    /// The mover wins, the move itself counting as its first of `N` moves.
+    Win(usize),
+    Draw,
+    /// The opponent wins with its `N`th move from here. `Loss(0)` is a move
+    /// that loses on the spot, like completing a line in misère.
+    Loss(usize),
+    /// The search ran out of time before settling it.
+    Unknown,
+}
+
+/// Evaluates every empty cell as `side`'s next move, searching for at most
+/// `think_time`. Small boards are solved outright; on big ones moves the
+/// search can't settle in time come back `Unknown`.
+pub fn evaluate_moves(
+    position: &Position,
+    side: CellState,
+    think_time: Duration,
+) -> Vec<(Move, Evaluation)> {
+    if position.winner().is_some() {
+        return Vec::new();
+    }
+
+    let (scored, complete) = search_exact(&mut Scratch::new(position, side), think_time);
+    scored
+        .into_iter()
+        .map(|(mv, score)| {
+            // Proven scores count down from `WIN_SCORE` by the ply the game
+            // ends on, this move being ply 1
+            let plies = (WIN_SCORE - score.abs()).round() as usize;
+            let evaluation = if score > PROVEN_SCORE {
+                Evaluation::Win(plies.div_ceil(2))
+            } else if score < -PROVEN_SCORE {
+                Evaluation::Loss(plies / 2)
+            } else if complete {
+                Evaluation::Draw
+            } else {
+                Evaluation::Unknown
+            };
+            (mv, evaluation)
+        })
+        .collect()
+}
+
+/// First move with the highest score, along with that score.
+fn best_of(scored: &[(Move, f32)]) -> (Move, f32) {
+    let mut best = scored[0];
+    for &(mv, score) in scored {
+        if score > best.1 {
+            best = (mv, score);
+        }
+    }
+    best
+}
+
+/// How a game has ended, or is sure to end, for the side to move, counted
+/// in plies from here: `Loss(0)` is a game the opponent just won.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Ending {
+    Win(usize),
+    Draw,
+    Loss(usize),
+}
+
+impl Ending {
+    /// The search score for this ending `ply` plies below the root. Sooner
+    /// wins and later losses score higher.
+    fn score(self, ply: usize) -> f32 {
+        match self {
+            Ending::Win(plies) => WIN_SCORE - (ply + plies) as f32,
+            Ending::Draw => 0.0,
+            Ending::Loss(plies) => -(WIN_SCORE - (ply + plies) as f32),
+        }
+    }
+}
+
+/// A game the shared alpha-beta search can play, through `search_best_move`
+/// and friends. Moves are played and taken back in place, and every score
+/// is for the side to move.
+pub trait Searchable {
+    type Move: Copy + PartialEq;
+
+    /// How the game ended, or `None` while it's still on. A game may also
+    /// report an ending it can already see coming, such as a win the side
+    /// to move can't be stopped from taking.
+    fn outcome(&mut self) -> Option<Ending>;
+
+    /// Moves for the side to move, the most promising first: the search
+    /// prunes best when good moves come early.
+    fn legal_moves(&mut self) -> Vec<Self::Move>;
+
+    fn play(&mut self, mv: Self::Move);
+
+    /// Takes back `mv`, which was the last move played.
+    fn undo(&mut self, mv: Self::Move);
+
+    /// Static score for the side to move where the search stops. Anything
+    /// beyond `PROVEN_SCORE` either way is cut down to it.
+    fn evaluate(&mut self) -> f32;
+
+    /// The only sensible move, when there is one, like blocking the one line
+    /// the opponent is about to finish. It's searched without using up
+    /// depth.
+    fn forced_move(&mut self) -> Option<Self::Move> {
+        None
+    }
+
+    /// A hash of the position and side to move, for games worth a
+    /// transposition table. `None` searches without one.
+    fn hash(&self) -> Option<u64> {
+        None
+    }
+}
+
+/// Picks a move for the side to move by iterative deepening, up to
+/// `max_depth` plies or until `think_time` runs out, whichever comes first.
+/// Ties go to a random one of the best, so games don't repeat.
+pub fn search_best_move<G: Searchable>(
+    game: &mut G,
+    max_depth: usize,
+    think_time: Duration,
+    rng: &mut Rng,
+) -> Option<G::Move> {
+    let scored = search_scores(game, max_depth, think_time);
+    let best_score = scored.first()?.1;
+    let top = scored
+        .iter()
+        .take_while(|(_, score)| *score == best_score)
+        .count();
+    Some(scored[rng.gen_index(top)].0)
+}
+
+/// Scores every move for the side to move, best first, going one ply deeper
+/// each round until `max_depth`, a proven result, a round that saw the whole
+/// game out, or the deadline. Moves tied with the best are exact; the others
+/// may be upper bounds, which are still below the best move's score.
+pub fn search_scores<G: Searchable>(
+    game: &mut G,
+    max_depth: usize,
+    think_time: Duration,
+) -> Vec<(G::Move, f32)> {
+    let mut search = Search::new(game, think_time);
+    let mut order = search.game.legal_moves();
+    let mut scored = Vec::new();
+
+    for depth in 1..=max_depth {
+        search.cut_off = false;
+        let mut alpha = f32::NEG_INFINITY;
+        let mut current = Vec::with_capacity(order.len());
+        for &mv in &order {
+            search.game.play(mv);
+            let score = -search.negamax(depth - 1, 1, f32::NEG_INFINITY, -below(alpha));
+            search.game.undo(mv);
+            if search.clock.out_of_time {
+                break;
+            }
+            alpha = alpha.max(score);
+            current.push((mv, score));
+        }
+        // A round cut short is thrown away in favour of the last full one
+        if search.clock.out_of_time {
+            break;
+        }
+
+        // Best moves first, so the next round gets its cutoffs early
+        current.sort_by(|a, b| b.1.total_cmp(&a.1));
+        order = current.iter().map(|&(mv, _)| mv).collect();
+        let best_score = current.first().map_or(0.0, |&(_, score)| score);
+        scored = current;
+
+        // The first round always finishes so there is a move to play
+        search.clock.can_stop = true;
+        if best_score.abs() > PROVEN_SCORE || !search.cut_off {
+            break;
+        }
+    }
+
+    scored
+}
+
+/// Like `search_scores`, but every move gets an exact score rather than just
+/// the best one. Deepens until every move is a proven win or loss, or until
+/// a round sees the whole game out, which the second value reports; it's
+/// false if the deadline came first.
+pub fn search_exact<G: Searchable>(
+    game: &mut G,
+    think_time: Duration,
+) -> (Vec<(G::Move, f32)>, bool) {
+    let mut search = Search::new(game, think_time);
+    let order = search.game.legal_moves();
+    let mut scored = Vec::new();
+    let mut complete = false;
+
+    for depth in 1.. {
+        search.cut_off = false;
+        let mut current = Vec::with_capacity(order.len());
+        for &mv in &order {
+            search.game.play(mv);
+            let score = -search.negamax(depth - 1, 1, f32::NEG_INFINITY, f32::INFINITY);
+            search.game.undo(mv);
+            if search.clock.out_of_time {
+                break;
+            }
+            current.push((mv, score));
+        }
+        if search.clock.out_of_time {
+            break;
+        }
+
+        scored = current;
+        search.clock.can_stop = true;
+        complete = !search.cut_off;
+        if complete || scored.iter().all(|(_, score)| score.abs() > PROVEN_SCORE) {
+            break;
+        }
+    }
+
+    (scored, complete)
+}
+
+// Root moves are searched against the best score so far lowered by this
+// share of it, so one that ties the best comes back exact rather than as a
+// bound that merely can't beat it, and the random pick among ties only sees
+// real ties. A fixed margin would vanish into the rounding of scores near
+// `WIN_SCORE`
+const TIE_MARGIN: f32 = 1e-6;
+
+fn below(alpha: f32) -> f32 {
+    alpha - TIE_MARGIN * alpha.abs().max(1.0)
+}
+
+/// The deadline for a search, checked every `NODES_PER_TIME_CHECK` nodes.
+/// Searches with rounds let the first one finish before it can stop them.
+pub(crate) struct Clock {
+    deadline: Instant,
+    can_stop: bool,
+    out_of_time: bool,
+    nodes: u64,
+}
+
+impl Clock {
+    pub fn new(think_time: Duration) -> Self {
+        Self {
+            deadline: Instant::now() + think_time,
+            can_stop: true,
+            out_of_time: false,
+            nodes: 0,
+        }
+    }
+
+    /// Counts a node and reports whether time is up.
+    pub fn tick(&mut self) -> bool {
+        self.nodes += 1;
+        if self.can_stop
+            && self.nodes.is_multiple_of(NODES_PER_TIME_CHECK)
+            && Instant::now() >= self.deadline
+        {
+            self.out_of_time = true;
+        }
+        self.out_of_time
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Bound {
+    Exact,
+    Lower, // the real score is at least this (the search failed high)
+    Upper, // the real score is at most this (the search failed low)
+}
+
+#[derive(Clone, Copy)]
+struct Entry<M> {
+    depth: usize,
+    score: f32,
+    bound: Bound,
+    best: Option<M>,
+}
+
+/// One iterative-deepening alpha-beta search over a `Searchable` game.
+struct Search<'a, G: Searchable> {
+    game: &'a mut G,
+    table: HashMap<u64, Entry<G::Move>>,
+    clock: Clock,
+    cut_off: bool, // some line ended at the depth limit this round
+}
+
+impl<'a, G: Searchable> Search<'a, G> {
+    fn new(game: &'a mut G, think_time: Duration) -> Self {
+        let mut clock = Clock::new(think_time);
+        clock.can_stop = false;
+        Self {
+            game,
+            table: HashMap::new(),
+            clock,
+            cut_off: false,
+        }
+    }
+
+    /// Score for the side to move, `ply` plies below the root.
+    fn negamax(&mut self, depth: usize, ply: usize, mut alpha: f32, beta: f32) -> f32 {
+        if let Some(ending) = self.game.outcome() {
+            return ending.score(ply);
+        }
+        if self.clock.tick() {
+            return 0.0;
+        }
+
+        if let Some(mv) = self.game.forced_move() {
+            self.game.play(mv);
+            let score = -self.negamax(depth, ply + 1, -beta, -alpha);
+            self.game.undo(mv);
+            return score;
+        }
+        if depth == 0 {
+            self.cut_off = true;
+            return self.game.evaluate().clamp(-PROVEN_SCORE, PROVEN_SCORE);
+        }
+
+        let hash = self.game.hash();
+        let original_alpha = alpha;
+        let mut tt_move = None;
+        if let Some(entry) = hash.and_then(|hash| self.table.get(&hash)) {
+            tt_move = entry.best;
+            if entry.depth >= depth {
+                let score = score_from_table(entry.score, ply);
+                match entry.bound {
+                    Bound::Exact => return score,
+                    Bound::Lower if score >= beta => return score,
+                    Bound::Upper if score <= alpha => return score,
+                    _ => {}
+                }
+            }
+        }
+
+        // The table's best move from an earlier round goes first
+        let mut moves = self.game.legal_moves();
+        if let Some(index) = moves.iter().position(|&mv| Some(mv) == tt_move) {
+            moves[..=index].rotate_right(1);
+        }
+
+        let mut best_score = f32::NEG_INFINITY;
+        let mut best_move = None;
+        for mv in moves {
+            self.game.play(mv);
+            let score = -self.negamax(depth - 1, ply + 1, -beta, -alpha);
+            self.game.undo(mv);
+            if self.clock.out_of_time {
+                return 0.0;
+            }
+
+            if score > best_score {
+                best_score = score;
+                best_move = Some(mv);
+            }
+            alpha = alpha.max(score);
+            if alpha >= beta {
+                break;
+            }
+        }
+
+        if let Some(hash) = hash {
+            let bound = if best_score <= original_alpha {
+                Bound::Upper
+            } else if best_score >= beta {
+                Bound::Lower
+            } else {
+                Bound::Exact
+            };
+            self.table.insert(
+                hash,
+                Entry {
+                    depth,
+                    score: score_to_table(best_score, ply),
+                    bound,
+                    best: best_move,
+                },
+            );
+        }
+
+        best_score
+    }
+}
+
+/// A scratch copy of a plain board for the search to play on, hashed for
+/// the transposition table.
+struct Scratch {
+    position: Position,
+    side: CellState, // to move
+    played: Vec<Move>,
+    empty: usize,
+    hash: u64,
+    zobrist: Vec<[u64; 2]>, // per cell: key for X, key for O
+    cells_by_center: Vec<Move>,
+}
+
+impl Scratch {
+    fn new(position: &Position, side: CellState) -> Self {
+        let (rows, cols) = (position.rows(), position.cols());
+        let mut rng = Rng::new(ZOBRIST_SEED);
+        let zobrist: Vec<[u64; 2]> = (0..rows * cols)
+            .map(|_| [rng.next_u64(), rng.next_u64()])
+            .collect();
+
+        let mut hash = 0;
+        for row in 0..rows {
+            for col in 0..cols {
+                if let Some(side) = side_index(position.get(row, col)) {
+                    hash ^= zobrist[row * cols + col][side];
+                }
+            }
+        }
+
+        // Central cells take part in the most lines, so they are tried first
+        let center_row = (rows as f32 - 1.0) / 2.0;
+        let center_col = (cols as f32 - 1.0) / 2.0;
+        let mut cells_by_center: Vec<Move> = (0..rows)
+            .flat_map(|row| (0..cols).map(move |col| (row, col)))
+            .collect();
+        cells_by_center.sort_by(|a, b| {
+            let distance = |(row, col): Move| {
+                (row as f32 - center_row).abs() + (col as f32 - center_col).abs()
+            };
+            distance(*a).total_cmp(&distance(*b))
+        });
+
+        Self {
+            position: position.clone(),
+            side,
+            played: Vec::new(),
+            empty: position.empty_cells().len(),
+            hash,
+            zobrist,
+            cells_by_center,
+        }
+    }
+
+    fn has_neighbour(&self, row: usize, col: usize) -> bool {
+        (row.saturating_sub(1)..(row + 2).min(self.position.rows())).any(|r| {
+            (col.saturating_sub(1)..(col + 2).min(self.position.cols()))
+                .any(|c| self.position.get(r, c) != CellState::Empty)
+        })
+    }
+
+    fn toggle_hash(&mut self, row: usize, col: usize, side: CellState) {
+        if let Some(index) = side_index(side) {
+            self.hash ^= self.zobrist[row * self.position.cols() + col][index];
+        }
+    }
+}
+
+impl Searchable for Scratch {
+    type Move = Move;
+
+    /// Only the opponent's move that led here can have ended the game.
+    fn outcome(&mut self) -> Option<Ending> {
+        if let Some(&(row, col)) = self.played.last()
+            && self.position.completes_line(row, col)
+        {
+            let winner = self.position.rule().winner(self.side.opponent());
+            return Some(if winner == self.side {
+                Ending::Win(0)
+            } else {
+                Ending::Loss(0)
+            });
+        }
+        (self.empty == 0).then_some(Ending::Draw)
+    }
+
+    /// Empty cells next to a piece, then the rest, each group central
+    /// cells first.
+    fn legal_moves(&mut self) -> Vec<Move> {
+        let mut near = Vec::new();
+        let mut far = Vec::new();
+        for &(row, col) in &self.cells_by_center {
+            if self.position.get(row, col) != CellState::Empty {
+                continue;
+            }
+            if self.has_neighbour(row, col) {
+                near.push((row, col));
+            } else {
+                far.push((row, col));
+            }
+        }
+
+        near.extend(far);
+        near
+    }
+
+    fn play(&mut self, (row, col): Move) {
+        self.position.set(row, col, self.side);
+        self.toggle_hash(row, col, self.side);
+        self.played.push((row, col));
+        self.empty -= 1;
+        self.side = self.side.opponent();
+    }
+
+    fn undo(&mut self, (row, col): Move) {
+        self.side = self.side.opponent();
+        self.position.set(row, col, CellState::Empty);
+        self.toggle_hash(row, col, self.side);
+        self.played.pop();
+        self.empty += 1;
+    }
+
+    fn evaluate(&mut self) -> f32 {
+        evaluate(&self.position, self.side)
+    }
+
+    fn hash(&self) -> Option<u64> {
+        // The side to move follows from the pieces on a plain board
+        Some(self.hash)
+    }
+}
+
+fn side_index(state: CellState) -> Option<usize> {
+    match state {
+        CellState::X => Some(0),
+        CellState::O => Some(1),
+        CellState::Empty => None,
+    }
+}
+
+// Proven scores count plies from the root. The table stores them counted from
+// the node instead, so an entry is still right when reached at another depth.
+fn score_to_table(score: f32, ply: usize) -> f32 {
+    if score > PROVEN_SCORE {
+        score + ply as f32
+    } else if score < -PROVEN_SCORE {
+        score - ply as f32
+    } else {
+        score
+    }
+}
+
+fn score_from_table(score: f32, ply: usize) -> f32 {
+    if score > PROVEN_SCORE {
+        score - ply as f32
+    } else if score < -PROVEN_SCORE {
+        score + ply as f32
+    } else {
+        score
+    }
+}
+
+/// Static score for `side` where the search was cut off. Every window that
+/// only one side occupies counts for that side, weighted by how full it is.
+fn evaluate(position: &Position, side: CellState) -> f32 {
+    let mut score = 0.0;
+
+    for (start, dir) in position.windows() {
+        let mut mine = 0;
+        let mut theirs = 0;
+        for (row, col) in position.window_cells(start, dir) {
+            match position.get(row, col) {
+                CellState::Empty => {}
+                state if state == side => mine += 1,
+                _ => theirs += 1,
+            }
+        }
+
+        if theirs == 0 && mine > 0 {
+            score += (mine * mine) as f32;
+        } else if mine == 0 && theirs > 0 {
+            score -= (theirs * theirs) as f32;
+        }
+    }
+
+    // In misère a line of your own is the danger, so the counting flips
+    if position.rule() == Rule::Misere {
+        score = -score;
+    }
+
+    score
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::position::Topology;
+    use crate::rules::Game;
+
+    // Far more than a 3x3 search needs, so the clock never cuts one short
+    const THINK_TIME: Duration = Duration::from_secs(10);
+
+    fn best_move(game: &Game, difficulty: Difficulty, seed: u64) -> Option<Move> {
+        find_best_move(
+            game.position(),
+            game.turn(),
+            difficulty,
+            Engine::Minimax,
+            THINK_TIME,
+            &mut Rng::new(seed),
+        )
+    }
+
+    #[test]
+    fn same_seed_same_move() {
+        let game =
+            Game::from_moves(3, 3, Rule::Standard, Topology::Flat, &[(1, 1), (0, 0)]).unwrap();
+        for difficulty in Difficulty::ALL {
+            for seed in 0..10 {
+                assert_eq!(
+                    best_move(&game, difficulty, seed),
+                    best_move(&game, difficulty, seed),
+                    "{} with seed {seed}",
+                    difficulty.name()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn perfect_never_loses_on_3x3() {
+        for opponent in Difficulty::ALL {
+            for seed in 0..10 {
+                for perfect in [CellState::X, CellState::O] {
+                    let mut rng = Rng::new(seed);
+                    let mut game = Game::new(3, 3);
+                    while !game.is_over() {
+                        let difficulty = if game.turn() == perfect {
+                            Difficulty::Perfect
+                        } else {
+                            opponent
+                        };
+                        let mv = find_best_move(
+                            game.position(),
+                            game.turn(),
+                            difficulty,
+                            Engine::Minimax,
+                            THINK_TIME,
+                            &mut rng,
+                        )
+                        .unwrap();
+                        game.play(mv).unwrap();
+                    }
+                    assert_ne!(
+                        game.winner(),
+                        Some(perfect.opponent()),
+                        "lost to {} with seed {seed}: {:?}",
+                        opponent.name(),
+                        game.moves()
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn easy_sometimes_differs_from_perfect() {
+        // O has to block the top row
+        let game = Game::from_moves(
+            3,
+            3,
+            Rule::Standard,
+            Topology::Flat,
+            &[(0, 0), (1, 1), (0, 1)],
+        )
+        .unwrap();
+        for seed in 0..10 {
+            assert_eq!(best_move(&game, Difficulty::Perfect, seed), Some((0, 2)));
+        }
+        assert!((0..10).any(|seed| best_move(&game, Difficulty::Easy, seed) != Some((0, 2))));
+    }
+}
@@ -0,0 +1,183 @@
+//! Looking back over a finished game: how every move compares with the best
+//! one the search can find in the same position.
+
+use crate::ai::{self, Evaluation};
+use crate::position::{CellState, Move, Position};
+use std::time::Duration;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Verdict {
+    /// As good as anything else on offer.
+    Best,
+    /// Keeps the same result, but wins more slowly or loses sooner.
+    Inaccuracy,
+    /// Throws away a win or a draw.
+    Blunder,
+    /// The search couldn't settle the move, or one that might beat it, in
+    /// time, so there's nothing to compare.
+    Unclear,
+}
+
+impl Verdict {
+    pub fn name(self) -> &'static str {
+        match self {
+            Verdict::Best => "best",
+            Verdict::Inaccuracy => "inaccuracy",
+            Verdict::Blunder => "blunder",
+            Verdict::Unclear => "unclear",
+        }
+    }
+}
+
+/// One move of the game and how it measured up.
+#[derive(Clone, Debug)]
+pub struct MoveReview {
+    pub mv: Move,
+    pub side: CellState,
+    pub played: Evaluation,
+    pub best_move: Move,
+    pub best: Evaluation,
+    pub verdict: Verdict,
+    /// Every move that was possible in the position, as `ai::evaluate_moves`
+    /// scored it.
+    pub evaluations: Vec<(Move, Evaluation)>,
+}
+
+/// Reviews the move at `ply` of `moves`, played from the empty board `start`
+/// (which carries the shape, rule and topology) with X moving first.
+/// Searches for up to `think_time`. `None` if the game has no move `ply`.
+pub fn review_move(
+    start: &Position,
+    moves: &[Move],
+    ply: usize,
+    think_time: Duration,
+) -> Option<MoveReview> {
+    let mv = *moves.get(ply)?;
+    let mut position = start.clone();
+    let mut side = CellState::X;
+    for &(row, col) in &moves[..ply] {
+        position.set(row, col, side);
+        side = side.opponent();
+    }
+
+    let evaluations = ai::evaluate_moves(&position, side, think_time);
+    let played = evaluations
+        .iter()
+        .find(|(cell, _)| *cell == mv)
+        .map_or(Evaluation::Unknown, |&(_, evaluation)| evaluation);
+    let (best_move, best) = evaluations
+        .iter()
+        .copied()
+        // Among equals, keep the one the search tried first (the most central)
+        .rev()
+        .filter(|&(_, evaluation)| rank(evaluation).is_some())
+        .max_by_key(|&(_, evaluation)| rank(evaluation))
+        .unwrap_or((mv, played));
+
+    // A move the search left open could be better than anything it settled,
+    // so only a proven win can still be judged next to one
+    let unsettled = evaluations
+        .iter()
+        .any(|&(_, evaluation)| evaluation == Evaluation::Unknown);
+    let verdict = match (rank(played), rank(best)) {
+        (Some(played), Some(best)) if played.0 < best.0 => Verdict::Blunder,
+        (Some(played), Some(best)) if played < best => Verdict::Inaccuracy,
+        (Some((2, _)), _) => Verdict::Best,
+        (Some(_), _) if unsettled => Verdict::Unclear,
+        (Some(_), _) => Verdict::Best,
+        (None, _) => Verdict::Unclear,
+    };
+
+    Some(MoveReview {
+        mv,
+        side,
+        played,
+        best_move,
+        best,
+        verdict,
+        evaluations,
+    })
+}
+
+/// Reviews every move of a game.
+pub fn analyze(start: &Position, moves: &[Move], think_time: Duration) -> Vec<MoveReview> {
+    (0..moves.len())
+        .filter_map(|ply| review_move(start, moves, ply, think_time))
+        .collect()
+}
+
+/// Where the game was lost: the index of the loser's first move that went
+/// from a position it could still hold into a forced loss. `None` for
+/// draws, or if the loser never had a chance the search could see.
+pub fn critical_move(reviews: &[MoveReview], winner: Option<CellState>) -> Option<usize> {
+    let loser = winner?.opponent();
+    reviews.iter().position(|review| {
+        review.side == loser
+            && matches!(review.played, Evaluation::Loss(_))
+            && !matches!(review.best, Evaluation::Loss(_))
+    })
+}
+
+/// Orders evaluations from the mover's point of view. The first number is
+/// the result (loss, draw, win), the second how good it is within that:
+/// quicker wins and slower losses. `None` for moves the search didn't
+/// settle, which can't be ranked against anything.
+fn rank(evaluation: Evaluation) -> Option<(u8, i64)> {
+    match evaluation {
+        Evaluation::Loss(moves) => Some((0, moves as i64)),
+        Evaluation::Draw => Some((1, 0)),
+        Evaluation::Win(moves) => Some((2, -(moves as i64))),
+        Evaluation::Unknown => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const THINK_TIME: Duration = Duration::from_millis(500);
+
+    fn verdict(start: &Position, moves: &[Move]) -> Verdict {
+        review_move(start, moves, moves.len() - 1, THINK_TIME)
+            .unwrap()
+            .verdict
+    }
+
+    #[test]
+    fn classifies_moves_on_three_by_three() {
+        let start = Position::new(3, 3);
+        // Every opening move draws
+        assert_eq!(verdict(&start, &[(1, 1)]), Verdict::Best);
+        // An edge reply to a corner opening loses
+        assert_eq!(verdict(&start, &[(0, 0), (0, 1)]), Verdict::Blunder);
+        // O is lost either way, but leaving the top row open loses a move sooner
+        assert_eq!(
+            verdict(&start, &[(0, 0), (1, 0), (0, 1), (2, 0)]),
+            Verdict::Inaccuracy
+        );
+        // X takes the bottom row rather than block O's column
+        let win = [(0, 0), (1, 1), (2, 2), (0, 2), (2, 0), (1, 0), (2, 1)];
+        assert_eq!(verdict(&start, &win), Verdict::Best);
+        // ...and missing the win for a move that lets O win is a blunder
+        let miss = [(0, 0), (1, 1), (2, 2), (0, 2), (2, 0), (1, 0), (0, 1)];
+        assert_eq!(verdict(&start, &miss), Verdict::Blunder);
+    }
+
+    #[test]
+    fn a_loss_next_to_an_unsettled_move_is_unclear() {
+        // O has three along the top of a 7×7 board; X plays elsewhere. That
+        // loses at once, while the block at (0, 3) is too deep to settle
+        let start = Position::new(7, 4);
+        let moves = [(6, 6), (0, 0), (6, 4), (0, 1), (6, 2), (0, 2), (6, 1)];
+        let review = review_move(&start, &moves, 6, THINK_TIME).unwrap();
+        assert_eq!(review.played, Evaluation::Loss(1));
+        assert_eq!(review.verdict, Verdict::Unclear);
+    }
+
+    #[test]
+    fn no_review_past_the_last_move() {
+        let start = Position::new(3, 3);
+        assert!(review_move(&start, &[(1, 1)], 1, THINK_TIME).is_none());
+        assert_eq!(analyze(&start, &[(1, 1), (0, 0)], THINK_TIME).len(), 2);
+    }
+}
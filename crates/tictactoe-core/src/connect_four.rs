@@ -0,0 +1,389 @@
+//! Connect Four: pieces drop to the lowest empty cell of the column they're
+//! played in, and the first side to line up `win_length` of them wins.
+//!
+//! The classic game is 7 columns by 6 rows with 4 in a row, but any grid a
+//! `Position` supports works.
+
+use crate::ai::{self, Difficulty, Ending, Searchable};
+use crate::position::{CellState, Move, Position};
+use crate::rng::Rng;
+use crate::rules::{IllegalMove, Outcome};
+use std::time::Duration;
+
+/// Rows on the classic board.
+pub const ROWS: usize = 6;
+/// Columns on the classic board.
+pub const COLS: usize = 7;
+/// Pieces in a row that win on the classic board.
+pub const WIN_LENGTH: usize = 4;
+
+const ZOBRIST_SEED: u64 = 0xC0FF_EE4C_0DE5_0004;
+
+// Only one move per column makes the tree narrow, so Hard looks further
+// ahead than in the other variants
+const MEDIUM_DEPTH: usize = 2;
+const HARD_DEPTH: usize = 6;
+
+/// A game of Connect Four in progress. X moves first.
+#[derive(Clone, Debug)]
+pub struct ConnectFour {
+    position: Position,
+    turn: CellState,
+    moves: Vec<Move>,
+    outcome: Option<Outcome>,
+}
+
+impl Default for ConnectFour {
+    fn default() -> Self {
+        Self::new(ROWS, COLS, WIN_LENGTH)
+    }
+}
+
+impl ConnectFour {
+    /// An empty grid `rows` high and `cols` wide, clamped like
+    /// `Position::rectangular`.
+    pub fn new(rows: usize, cols: usize, win_length: usize) -> Self {
+        Self {
+            position: Position::rectangular(rows, cols, win_length),
+            turn: CellState::X,
+            moves: Vec::new(),
+            outcome: None,
+        }
+    }
+
+    pub fn position(&self) -> &Position {
+        &self.position
+    }
+
+    /// The side to move. Once the game is over this stays on the last mover.
+    pub fn turn(&self) -> CellState {
+        self.turn
+    }
+
+    /// The cells played so far, in order.
+    pub fn moves(&self) -> &[Move] {
+        &self.moves
+    }
+
+    pub fn outcome(&self) -> Option<&Outcome> {
+        self.outcome.as_ref()
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.outcome.is_some()
+    }
+
+    pub fn winner(&self) -> Option<CellState> {
+        match &self.outcome {
+            Some(Outcome::Win(winner, _)) => Some(*winner),
+            _ => None,
+        }
+    }
+
+    /// The row a piece played in `col` lands on, or `None` if the column is
+    /// full or off the board.
+    pub fn landing_row(&self, col: usize) -> Option<usize> {
+        if col >= self.position.cols() {
+            return None;
+        }
+        (0..self.position.rows())
+            .rev()
+            .find(|&row| self.position.get(row, col) == CellState::Empty)
+    }
+
+    /// Every column that still has room.
+    pub fn legal_moves(&self) -> Vec<usize> {
+        if self.is_over() {
+            return Vec::new();
+        }
+        (0..self.position.cols())
+            .filter(|&col| self.landing_row(col).is_some())
+            .collect()
+    }
+
+    /// Drops a piece for the side to move into `col` and returns the cell it
+    /// landed on.
+    pub fn play(&mut self, col: usize) -> Result<Move, IllegalMove> {
+        if self.is_over() {
+            return Err(IllegalMove::GameOver);
+        }
+        if col >= self.position.cols() {
+            return Err(IllegalMove::OffBoard);
+        }
+        let Some(row) = self.landing_row(col) else {
+            return Err(IllegalMove::ColumnFull);
+        };
+
+        self.position.set(row, col, self.turn);
+        self.moves.push((row, col));
+
+        if self.position.completes_line(row, col) {
+            let (_, line) = self
+                .position
+                .winner()
+                .expect("a completed line is a winner");
+            self.outcome = Some(Outcome::Win(self.turn, line));
+        } else if self.position.is_full() {
+            self.outcome = Some(Outcome::Draw);
+        } else {
+            self.turn = self.turn.opponent();
+        }
+        Ok((row, col))
+    }
+
+    /// Takes back the last move, if there is one.
+    pub fn undo(&mut self) -> Option<Move> {
+        let (row, col) = self.moves.pop()?;
+        self.turn = self.position.get(row, col);
+        self.position.set(row, col, CellState::Empty);
+        self.outcome = None;
+        Some((row, col))
+    }
+}
+
+/// Picks a column for the side to move. Easy plays at random, Medium and
+/// Hard search a fixed number of moves ahead, and Perfect searches as deep as
+/// it can in `think_time`.
+pub fn find_best_move(
+    game: &ConnectFour,
+    difficulty: Difficulty,
+    think_time: Duration,
+    rng: &mut Rng,
+) -> Option<usize> {
+    let max_depth = match difficulty {
+        Difficulty::Easy => {
+            let moves = game.legal_moves();
+            return (!moves.is_empty()).then(|| moves[rng.gen_index(moves.len())]);
+        }
+        Difficulty::Medium => MEDIUM_DEPTH,
+        Difficulty::Hard => HARD_DEPTH,
+        Difficulty::Perfect => usize::MAX,
+    };
+    if game.is_over() {
+        return None;
+    }
+    ai::search_best_move(&mut Grid::new(game), max_depth, think_time, rng)
+}
+
+/// A scratch copy of the grid for the search, hashed for the transposition
+/// table, which pays off here since the same grid is reached by many orders
+/// of the same drops.
+struct Grid {
+    position: Position,
+    side: CellState,     // to move
+    heights: Vec<usize>, // pieces in each column
+    played: Vec<Move>,
+    empty: usize,
+    hash: u64,
+    zobrist: Vec<[u64; 2]>, // per cell: key for X, key for O
+    cols_by_center: Vec<usize>,
+}
+
+impl Grid {
+    fn new(game: &ConnectFour) -> Self {
+        let position = game.position.clone();
+        let (rows, cols) = (position.rows(), position.cols());
+        let mut rng = Rng::new(ZOBRIST_SEED);
+        let zobrist: Vec<[u64; 2]> = (0..rows * cols)
+            .map(|_| [rng.next_u64(), rng.next_u64()])
+            .collect();
+
+        let mut hash = 0;
+        let mut heights = vec![0; cols];
+        for row in 0..rows {
+            for col in 0..cols {
+                match position.get(row, col) {
+                    CellState::X => hash ^= zobrist[row * cols + col][0],
+                    CellState::O => hash ^= zobrist[row * cols + col][1],
+                    CellState::Empty => continue,
+                }
+                heights[col] += 1;
+            }
+        }
+
+        // Central columns take part in the most lines, so they're tried first
+        let center = (cols as f32 - 1.0) / 2.0;
+        let mut cols_by_center: Vec<usize> = (0..cols).collect();
+        cols_by_center.sort_by(|a, b| {
+            (*a as f32 - center)
+                .abs()
+                .total_cmp(&(*b as f32 - center).abs())
+        });
+
+        Self {
+            empty: position.empty_cells().len(),
+            position,
+            side: game.turn,
+            heights,
+            played: Vec::new(),
+            hash,
+            zobrist,
+            cols_by_center,
+        }
+    }
+
+    fn toggle_hash(&mut self, row: usize, col: usize, side: CellState) {
+        let index = usize::from(side == CellState::O);
+        self.hash ^= self.zobrist[row * self.position.cols() + col][index];
+    }
+}
+
+impl Searchable for Grid {
+    type Move = usize;
+
+    /// Only the cell the opponent's piece just landed on can have ended
+    /// the game.
+    fn outcome(&mut self) -> Option<Ending> {
+        if let Some(&(row, col)) = self.played.last()
+            && self.position.completes_line(row, col)
+        {
+            return Some(Ending::Loss(0));
+        }
+        (self.empty == 0).then_some(Ending::Draw)
+    }
+
+    /// Columns with room, from the center out.
+    fn legal_moves(&mut self) -> Vec<usize> {
+        let rows = self.position.rows();
+        self.cols_by_center
+            .iter()
+            .copied()
+            .filter(|&col| self.heights[col] < rows)
+            .collect()
+    }
+
+    fn play(&mut self, col: usize) {
+        let row = self.position.rows() - 1 - self.heights[col];
+        self.position.set(row, col, self.side);
+        self.toggle_hash(row, col, self.side);
+        self.heights[col] += 1;
+        self.played.push((row, col));
+        self.empty -= 1;
+        self.side = self.side.opponent();
+    }
+
+    fn undo(&mut self, col: usize) {
+        self.side = self.side.opponent();
+        self.heights[col] -= 1;
+        self.played.pop();
+        self.empty += 1;
+        let row = self.position.rows() - 1 - self.heights[col];
+        self.position.set(row, col, CellState::Empty);
+        self.toggle_hash(row, col, self.side);
+    }
+
+    fn evaluate(&mut self) -> f32 {
+        evaluate(&self.position, self.side)
+    }
+
+    fn hash(&self) -> Option<u64> {
+        Some(self.hash)
+    }
+}
+
+/// Static score for `side`. Windows only one side occupies count for it by
+/// how full they are, and one piece short counts four times over: that's a
+/// threat the other side has to keep a cell free underneath or block.
+fn evaluate(position: &Position, side: CellState) -> f32 {
+    let threat = position.win_length() - 1;
+    let weight = |pieces: usize| {
+        let base = (pieces * pieces) as f32;
+        if pieces == threat { base * 4.0 } else { base }
+    };
+
+    let mut score = 0.0;
+    for (start, dir) in position.windows() {
+        let mut mine = 0;
+        let mut theirs = 0;
+        for (row, col) in position.window_cells(start, dir) {
+            match position.get(row, col) {
+                CellState::Empty => {}
+                state if state == side => mine += 1,
+                _ => theirs += 1,
+            }
+        }
+
+        if theirs == 0 && mine > 0 {
+            score += weight(mine);
+        } else if mine == 0 && theirs > 0 {
+            score -= weight(theirs);
+        }
+    }
+    score
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play(game: &mut ConnectFour, cols: &[usize]) {
+        for &col in cols {
+            game.play(col).unwrap();
+        }
+    }
+
+    #[test]
+    fn pieces_fall_to_the_bottom_of_tall_and_wide_grids() {
+        // Eight rows, four columns
+        let mut tall = ConnectFour::new(8, 4, 4);
+        assert_eq!(tall.play(1), Ok((7, 1)));
+        assert_eq!(tall.play(1), Ok((6, 1)));
+        play(&mut tall, &[1; 6]);
+        assert_eq!(tall.landing_row(1), None);
+        assert_eq!(tall.play(1), Err(IllegalMove::ColumnFull));
+        assert_eq!(tall.play(4), Err(IllegalMove::OffBoard));
+        assert_eq!(tall.legal_moves(), [0, 2, 3]);
+
+        // Three rows, nine columns
+        let mut wide = ConnectFour::new(3, 9, 4);
+        assert_eq!(wide.play(8), Ok((2, 8)));
+        assert_eq!(wide.landing_row(8), Some(1));
+        assert_eq!(wide.landing_row(9), None);
+    }
+
+    #[test]
+    fn lines_win_across_and_up() {
+        let mut wide = ConnectFour::new(4, 9, 4);
+        play(&mut wide, &[5, 5, 6, 6, 7, 7, 8]);
+        assert_eq!(wide.winner(), Some(CellState::X));
+        assert_eq!(
+            wide.outcome(),
+            Some(&Outcome::Win(
+                CellState::X,
+                vec![(3, 5), (3, 6), (3, 7), (3, 8)]
+            ))
+        );
+
+        let mut tall = ConnectFour::new(8, 4, 4);
+        play(&mut tall, &[0, 1, 0, 1, 0, 1, 2, 1]);
+        assert_eq!(tall.winner(), Some(CellState::O));
+        assert_eq!(tall.undo(), Some((4, 1)));
+        assert!(!tall.is_over());
+        assert_eq!(tall.turn(), CellState::O);
+    }
+
+    #[test]
+    fn a_full_grid_without_a_line_is_a_draw() {
+        // Three rows are too few for a column or diagonal of four, and
+        // every row alternates
+        let mut game = ConnectFour::new(3, 4, 4);
+        play(&mut game, &[0, 1, 2, 3, 0, 1, 2, 3, 0, 1, 2]);
+        assert!(!game.is_over());
+        game.play(3).unwrap();
+        assert_eq!(game.outcome(), Some(&Outcome::Draw));
+        assert!(game.legal_moves().is_empty());
+    }
+
+    #[test]
+    fn the_ai_finishes_a_row() {
+        let mut game = ConnectFour::default();
+        play(&mut game, &[0, 0, 1, 1, 2, 2]);
+        let mv = find_best_move(
+            &game,
+            Difficulty::Hard,
+            Duration::from_secs(10),
+            &mut Rng::new(1),
+        );
+        assert_eq!(mv, Some(3));
+    }
+}
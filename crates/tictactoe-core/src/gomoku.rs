@@ -0,0 +1,815 @@
+//! Gomoku: five in a row on a 15×15 board, X (black) first.
+//!
+//! Lines can be held to exactly five, and an opening rule can restrict the
+//! first stones to take the edge off moving first. The AI plays on patterns
+//! and threats rather than searching the whole tree, which is far too wide.
+
+use crate::ai::{self, Clock, Difficulty, Ending, Searchable};
+use crate::position::{CellState, DIRECTIONS, Move, Position};
+use crate::rng::Rng;
+use crate::rules::{IllegalMove, Outcome};
+use std::time::{Duration, Instant};
+
+/// Cells per side of the board.
+pub const SIZE: usize = 15;
+/// Stones in a row that win.
+pub const WIN_LENGTH: usize = 5;
+
+// How far from the center X's second stone has to go under the pro rule,
+// counted in cells along a row, column or diagonal
+const PRO_DISTANCE: usize = 3;
+
+// What a window of five with only one side's stones is worth, by how many it
+// holds. Each step is worth more than any number of the step below it turns up
+const WINDOW_WEIGHTS: [f32; WIN_LENGTH + 1] = [0.0, 1.0, 10.0, 100.0, 1000.0, 100_000.0];
+
+// Blocking the opponent's best cell counts a little less than taking your own
+const DEFENSE_WEIGHT: f32 = 0.9;
+
+// How many fours in a row the threat search may chain
+const HARD_VCF_DEPTH: usize = 4;
+const PERFECT_VCF_DEPTH: usize = 8;
+
+// The alpha-beta search only looks at this many of the best-looking cells in
+// each position; the rest of a 15x15 board is almost never worth a move
+const BRANCHING: usize = 10;
+const HARD_DEPTH: usize = 2;
+
+// Swap2 openings aim for positions the static score calls even; anything
+// within this of the most even one will do
+const BALANCE_MARGIN: f32 = 5.0;
+
+/// Which lines of five or more win.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum FiveRule {
+    /// Freestyle: five or more in a row wins.
+    Freestyle,
+    /// Only exactly five wins; an overline of six or more doesn't count.
+    Exact,
+}
+
+impl FiveRule {
+    pub const ALL: [FiveRule; 2] = [FiveRule::Freestyle, FiveRule::Exact];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            FiveRule::Freestyle => "Five or more",
+            FiveRule::Exact => "Exactly five",
+        }
+    }
+}
+
+/// Restrictions on how the game starts.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Opening {
+    /// Anything goes.
+    Free,
+    /// X's first stone goes in the center, and its second at least three
+    /// cells away from it.
+    Pro,
+    /// The first player places three stones (X, O, X). The second then
+    /// takes X, takes O, or places two more (O, X) and leaves the choice to
+    /// the first player.
+    Swap2,
+}
+
+impl Opening {
+    pub const ALL: [Opening; 3] = [Opening::Free, Opening::Pro, Opening::Swap2];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Opening::Free => "Free",
+            Opening::Pro => "Pro",
+            Opening::Swap2 => "Swap2",
+        }
+    }
+}
+
+/// A side choice during a swap2 opening.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Decision {
+    /// Play X from here on.
+    TakeX,
+    /// Play O from here on.
+    TakeO,
+    /// Place an O and an X, then let the other player choose.
+    PlaceTwo,
+}
+
+impl Decision {
+    pub fn name(self) -> &'static str {
+        match self {
+            Decision::TakeX => "Take X",
+            Decision::TakeO => "Take O",
+            Decision::PlaceTwo => "Place two more",
+        }
+    }
+}
+
+// Where a swap2 opening has got to. Other openings start at `Decided`
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Stage {
+    FirstStones,
+    SwapChoice,
+    ExtraStones,
+    ColorChoice,
+    Decided,
+}
+
+/// A game of Gomoku in progress. The two players are numbered, since under
+/// swap2 who plays which color isn't known at the start: player 0 places
+/// the first stone.
+#[derive(Clone, Debug)]
+pub struct Gomoku {
+    position: Position,
+    five_rule: FiveRule,
+    opening: Opening,
+    stage: Stage,
+    x_player: usize,
+    moves: Vec<Move>,
+    outcome: Option<Outcome>,
+}
+
+impl Gomoku {
+    pub fn new(five_rule: FiveRule, opening: Opening) -> Self {
+        Self {
+            position: Position::new(SIZE, WIN_LENGTH),
+            five_rule,
+            opening,
+            stage: if opening == Opening::Swap2 {
+                Stage::FirstStones
+            } else {
+                Stage::Decided
+            },
+            x_player: 0,
+            moves: Vec::new(),
+            outcome: None,
+        }
+    }
+
+    pub fn position(&self) -> &Position {
+        &self.position
+    }
+
+    pub fn five_rule(&self) -> FiveRule {
+        self.five_rule
+    }
+
+    pub fn opening(&self) -> Opening {
+        self.opening
+    }
+
+    pub fn moves(&self) -> &[Move] {
+        &self.moves
+    }
+
+    pub fn outcome(&self) -> Option<&Outcome> {
+        self.outcome.as_ref()
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.outcome.is_some()
+    }
+
+    pub fn winner(&self) -> Option<CellState> {
+        match &self.outcome {
+            Some(Outcome::Win(winner, _)) => Some(*winner),
+            _ => None,
+        }
+    }
+
+    /// The color of the next stone. Colors always alternate, X first, even
+    /// while a swap2 opening has one player placing both.
+    pub fn turn(&self) -> CellState {
+        if self.moves.len().is_multiple_of(2) {
+            CellState::X
+        } else {
+            CellState::O
+        }
+    }
+
+    /// The player playing `side`, once a swap2 opening has settled it.
+    pub fn player_of(&self, side: CellState) -> Option<usize> {
+        if self.stage != Stage::Decided {
+            return None;
+        }
+        Some(if side == CellState::X {
+            self.x_player
+        } else {
+            1 - self.x_player
+        })
+    }
+
+    /// The player who acts next, by placing a stone or making a choice.
+    pub fn mover(&self) -> usize {
+        match self.stage {
+            Stage::FirstStones | Stage::ColorChoice => 0,
+            Stage::SwapChoice | Stage::ExtraStones => 1,
+            Stage::Decided => self.player_of(self.turn()).unwrap_or(0),
+        }
+    }
+
+    /// What `mover` may choose between right now; empty when it's time to
+    /// place a stone.
+    pub fn choices(&self) -> &'static [Decision] {
+        match self.stage {
+            _ if self.is_over() => &[],
+            Stage::SwapChoice => &[Decision::TakeX, Decision::TakeO, Decision::PlaceTwo],
+            Stage::ColorChoice => &[Decision::TakeX, Decision::TakeO],
+            _ => &[],
+        }
+    }
+
+    /// Whether a stone may go on `(row, col)` now.
+    pub fn is_allowed(&self, (row, col): Move) -> bool {
+        if self.is_over() || !self.choices().is_empty() {
+            return false;
+        }
+        if !self.position.contains(row, col) || self.position.get(row, col) != CellState::Empty {
+            return false;
+        }
+
+        let center = SIZE / 2;
+        match (self.opening, self.moves.len()) {
+            (Opening::Pro, 0) => (row, col) == (center, center),
+            (Opening::Pro, 2) => row.abs_diff(center).max(col.abs_diff(center)) >= PRO_DISTANCE,
+            _ => true,
+        }
+    }
+
+    pub fn legal_moves(&self) -> Vec<Move> {
+        self.position
+            .empty_cells()
+            .into_iter()
+            .filter(|&cell| self.is_allowed(cell))
+            .collect()
+    }
+
+    /// Places the next stone, whose color is `turn`.
+    pub fn play(&mut self, (row, col): Move) -> Result<(), IllegalMove> {
+        if self.is_over() {
+            return Err(IllegalMove::GameOver);
+        }
+        if !self.position.contains(row, col) {
+            return Err(IllegalMove::OffBoard);
+        }
+        if self.position.get(row, col) != CellState::Empty {
+            return Err(IllegalMove::Occupied);
+        }
+        if !self.is_allowed((row, col)) {
+            return Err(IllegalMove::Restricted);
+        }
+
+        let side = self.turn();
+        self.position.set(row, col, side);
+        self.moves.push((row, col));
+
+        if let Some(line) = five_through(&self.position, (row, col), self.five_rule) {
+            self.outcome = Some(Outcome::Win(side, line));
+        } else if self.position.is_full() {
+            self.outcome = Some(Outcome::Draw);
+        }
+
+        self.stage = match (self.stage, self.moves.len()) {
+            (Stage::FirstStones, 3) => Stage::SwapChoice,
+            (Stage::ExtraStones, 5) => Stage::ColorChoice,
+            (stage, _) => stage,
+        };
+        Ok(())
+    }
+
+    /// Makes `mover`'s swap2 choice.
+    pub fn choose(&mut self, decision: Decision) -> Result<(), IllegalMove> {
+        if !self.choices().contains(&decision) {
+            return Err(IllegalMove::NoChoice);
+        }
+
+        let chooser = self.mover();
+        match decision {
+            Decision::TakeX => self.x_player = chooser,
+            Decision::TakeO => self.x_player = 1 - chooser,
+            Decision::PlaceTwo => {}
+        }
+        self.stage = if decision == Decision::PlaceTwo {
+            Stage::ExtraStones
+        } else {
+            Stage::Decided
+        };
+        Ok(())
+    }
+}
+
+/// The winning line through `cell` under `rule`, if its stone made one.
+fn five_through(position: &Position, (row, col): Move, rule: FiveRule) -> Option<Vec<Move>> {
+    let side = position.get(row, col);
+    if side == CellState::Empty {
+        return None;
+    }
+
+    DIRECTIONS.iter().find_map(|&(dr, dc)| {
+        let reach = |dr: isize, dc: isize| {
+            let mut count = 0;
+            while let Some(cell) = position.step((row, col), (dr, dc), count + 1)
+                && position.get(cell.0, cell.1) == side
+            {
+                count += 1;
+            }
+            count
+        };
+        let (back, ahead) = (reach(-dr, -dc), reach(dr, dc));
+        let run = (1 + back + ahead) as usize;
+        let wins = match rule {
+            FiveRule::Freestyle => run >= WIN_LENGTH,
+            FiveRule::Exact => run == WIN_LENGTH,
+        };
+        wins.then(|| {
+            (-back..=ahead)
+                .filter_map(|i| position.step((row, col), (dr, dc), i))
+                .collect()
+        })
+    })
+}
+
+/// Picks a cell for the next stone. Easy plays anywhere near the stones.
+/// Medium takes a five or blocks one and otherwise plays the cell with the
+/// best patterns for both sides. Hard looks for a win by a chain of fours
+/// and otherwise searches a move and the reply ahead; Perfect chains
+/// further and searches as deep as it can in `think_time`. In the first
+/// stones of swap2, which either side may end up owning, everyone aims for
+/// an even position.
+pub fn find_best_move(
+    game: &Gomoku,
+    difficulty: Difficulty,
+    think_time: Duration,
+    rng: &mut Rng,
+) -> Option<Move> {
+    let candidates = candidates(game);
+    if candidates.is_empty() {
+        return None;
+    }
+    let depths = match difficulty {
+        Difficulty::Easy => return Some(candidates[rng.gen_index(candidates.len())]),
+        Difficulty::Medium => None,
+        Difficulty::Hard => Some((HARD_VCF_DEPTH, HARD_DEPTH)),
+        Difficulty::Perfect => Some((PERFECT_VCF_DEPTH, usize::MAX)),
+    };
+    if game.stage != Stage::Decided {
+        return Some(balanced_move(game, &candidates, rng));
+    }
+
+    let side = game.turn();
+    let start = Instant::now();
+    let mut threats = Threats {
+        position: game.position.clone(),
+        rule: game.five_rule,
+        clock: Clock::new(think_time),
+    };
+
+    // A five now, or else the opponent's next five blocked
+    if let Some(&cell) = threats.fives(side).first() {
+        return Some(cell);
+    }
+    if let Some(&cell) = threats.fives(side.opponent()).first() {
+        return Some(cell);
+    }
+
+    let Some((vcf_depth, max_depth)) = depths else {
+        // Best patterns for both sides, ties at random
+        let scored = ranked(&game.position, candidates, side);
+        let top = scored
+            .iter()
+            .take_while(|(_, score)| *score == scored[0].1)
+            .count();
+        return Some(scored[rng.gen_index(top)].0);
+    };
+    if let Some(cell) = threats.vcf(side, vcf_depth) {
+        return Some(cell);
+    }
+
+    let mut stones = Stones {
+        position: game.position.clone(),
+        rule: game.five_rule,
+        side,
+        played: Vec::new(),
+        root: candidates,
+    };
+    let think_time = think_time.saturating_sub(start.elapsed());
+    ai::search_best_move(&mut stones, max_depth, think_time, rng)
+}
+
+/// The swap2 choice for `mover`: whichever color the static score favors,
+/// counting O's move to come. Never hands the choice back with
+/// `PlaceTwo`. `None` when there's nothing to choose.
+pub fn find_best_choice(game: &Gomoku) -> Option<Decision> {
+    if game.choices().is_empty() {
+        return None;
+    }
+    // The side to move is worth about one stone's growth more than it shows
+    let to_move = game.turn();
+    let tempo = candidates(game)
+        .into_iter()
+        .map(|cell| gain(&game.position, cell, to_move))
+        .fold(0.0, f32::max);
+    let x_advantage = evaluate(&game.position, CellState::X)
+        + if to_move == CellState::X {
+            tempo
+        } else {
+            -tempo
+        };
+
+    Some(if x_advantage > 0.0 {
+        Decision::TakeX
+    } else {
+        Decision::TakeO
+    })
+}
+
+/// Allowed cells within two of a stone, or every allowed cell on an empty
+/// board (just the center, under the pro rule).
+fn candidates(game: &Gomoku) -> Vec<Move> {
+    let position = &game.position;
+    if game.moves.is_empty() {
+        let center = (SIZE / 2, SIZE / 2);
+        return if game.is_allowed(center) {
+            vec![center]
+        } else {
+            game.legal_moves()
+        };
+    }
+
+    let cells: Vec<Move> = near_stones(position)
+        .into_iter()
+        .filter(|&cell| game.is_allowed(cell))
+        .collect();
+    // The pro rule can push X's second stone away from everything
+    if cells.is_empty() {
+        game.legal_moves()
+    } else {
+        cells
+    }
+}
+
+/// `cells` scored by the patterns a `side` stone would make there plus
+/// those it would spoil for the opponent, best first.
+fn ranked(position: &Position, cells: Vec<Move>, side: CellState) -> Vec<(Move, f32)> {
+    let mut scored: Vec<(Move, f32)> = cells
+        .into_iter()
+        .map(|cell| {
+            let score =
+                gain(position, cell, side) + DEFENSE_WEIGHT * gain(position, cell, side.opponent());
+            (cell, score)
+        })
+        .collect();
+    scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+    scored
+}
+
+/// Empty cells within two of a stone; the search's candidates once the
+/// opening rules no longer matter.
+fn near_stones(position: &Position) -> Vec<Move> {
+    position
+        .empty_cells()
+        .into_iter()
+        .filter(|&(row, col)| {
+            (row.saturating_sub(2)..(row + 3).min(SIZE)).any(|r| {
+                (col.saturating_sub(2)..(col + 3).min(SIZE))
+                    .any(|c| position.get(r, c) != CellState::Empty)
+            })
+        })
+        .collect()
+}
+
+/// An opening stone that leaves the static score as close to even as it
+/// gets, picked at random among the closest.
+fn balanced_move(game: &Gomoku, candidates: &[Move], rng: &mut Rng) -> Move {
+    let mut position = game.position.clone();
+    let side = game.turn();
+    let imbalance: Vec<(Move, f32)> = candidates
+        .iter()
+        .map(|&(row, col)| {
+            position.set(row, col, side);
+            let score = evaluate(&position, CellState::X).abs();
+            position.set(row, col, CellState::Empty);
+            ((row, col), score)
+        })
+        .collect();
+
+    let best = imbalance
+        .iter()
+        .map(|&(_, score)| score)
+        .fold(f32::INFINITY, f32::min);
+    let even: Vec<Move> = imbalance
+        .iter()
+        .filter(|&&(_, score)| score <= best + BALANCE_MARGIN)
+        .map(|&(cell, _)| cell)
+        .collect();
+    even[rng.gen_index(even.len())]
+}
+
+/// The number of `side` stones in each window of five through `cell` that
+/// the opponent has no stone in.
+fn open_windows(
+    position: &Position,
+    cell: Move,
+    side: CellState,
+) -> impl Iterator<Item = usize> + '_ {
+    position
+        .windows_through(cell)
+        .filter_map(move |(start, dir)| {
+            let mut stones = 0;
+            for (row, col) in position.window_cells(start, dir) {
+                match position.get(row, col) {
+                    CellState::Empty => {}
+                    state if state == side => stones += 1,
+                    _ => return None,
+                }
+            }
+            Some(stones)
+        })
+}
+
+/// How much a `side` stone on the empty `cell` adds to the windows of five
+/// through it.
+fn gain(position: &Position, cell: Move, side: CellState) -> f32 {
+    open_windows(position, cell, side)
+        .map(|stones| WINDOW_WEIGHTS[stones + 1] - WINDOW_WEIGHTS[stones])
+        .sum()
+}
+
+/// Static score for `side`: its windows of five minus the opponent's.
+fn evaluate(position: &Position, side: CellState) -> f32 {
+    let mut score = 0.0;
+    for (start, dir) in position.windows() {
+        let mut mine = 0;
+        let mut theirs = 0;
+        for (row, col) in position.window_cells(start, dir) {
+            match position.get(row, col) {
+                CellState::Empty => {}
+                state if state == side => mine += 1,
+                _ => theirs += 1,
+            }
+        }
+        if theirs == 0 {
+            score += WINDOW_WEIGHTS[mine];
+        } else if mine == 0 {
+            score -= WINDOW_WEIGHTS[theirs];
+        }
+    }
+    score
+}
+
+/// The alpha-beta search's view of the game: only the few best-looking
+/// cells of each position, scored by `evaluate` where it stops.
+struct Stones {
+    position: Position,
+    rule: FiveRule,
+    side: CellState, // to move
+    played: Vec<Move>,
+    root: Vec<Move>, // the cells the opening rule allows at the start
+}
+
+impl Searchable for Stones {
+    type Move = Move;
+
+    /// Only the opponent's stone that led here can have just won.
+    fn outcome(&mut self) -> Option<Ending> {
+        if let Some(&last) = self.played.last()
+            && five_through(&self.position, last, self.rule).is_some()
+        {
+            return Some(Ending::Loss(0));
+        }
+        self.position.is_full().then_some(Ending::Draw)
+    }
+
+    fn legal_moves(&mut self) -> Vec<Move> {
+        let cells = if self.played.is_empty() {
+            self.root.clone()
+        } else {
+            near_stones(&self.position)
+        };
+        ranked(&self.position, cells, self.side)
+            .into_iter()
+            .take(BRANCHING)
+            .map(|(cell, _)| cell)
+            .collect()
+    }
+
+    fn play(&mut self, (row, col): Move) {
+        self.position.set(row, col, self.side);
+        self.played.push((row, col));
+        self.side = self.side.opponent();
+    }
+
+    fn undo(&mut self, (row, col): Move) {
+        self.position.set(row, col, CellState::Empty);
+        self.played.pop();
+        self.side = self.side.opponent();
+    }
+
+    fn evaluate(&mut self) -> f32 {
+        evaluate(&self.position, self.side)
+    }
+}
+
+/// Threat-space search: wins forced by making a four again and again, each
+/// of which the opponent has to block on the one cell that completes it.
+struct Threats {
+    position: Position,
+    rule: FiveRule,
+    clock: Clock,
+}
+
+impl Threats {
+    /// A first move for `side` that wins by a chain of at most `depth` fours.
+    fn vcf(&mut self, side: CellState, depth: usize) -> Option<Move> {
+        if depth == 0 {
+            return None;
+        }
+        if self.clock.tick() {
+            return None;
+        }
+
+        for cell in self.four_moves(side) {
+            self.place(cell, side);
+            let completions = self.fives_through_lines(cell, side);
+            let wins = match completions[..] {
+                [] => false,
+                [block] => {
+                    // The forced block mustn't win or make a four of its own
+                    self.place(block, side.opponent());
+                    let wins = five_through(&self.position, block, self.rule).is_none()
+                        && self.fives_through_lines(block, side.opponent()).is_empty()
+                        && self.vcf(side, depth - 1).is_some();
+                    self.remove(block);
+                    wins
+                }
+                // Two ways to make five: only one can be blocked
+                _ => true,
+            };
+            self.remove(cell);
+            if wins {
+                return Some(cell);
+            }
+        }
+        None
+    }
+
+    /// Empty cells where a `side` stone would win.
+    fn fives(&mut self, side: CellState) -> Vec<Move> {
+        self.position
+            .empty_cells()
+            .into_iter()
+            .filter(|&cell| self.makes_five(cell, side))
+            .collect()
+    }
+
+    /// Empty cells where a `side` stone would make four: a window of five
+    /// with three of its stones and nothing of the opponent's through them.
+    fn four_moves(&mut self, side: CellState) -> Vec<Move> {
+        self.position
+            .empty_cells()
+            .into_iter()
+            .filter(|&cell| open_windows(&self.position, cell, side).any(|stones| stones == 3))
+            .collect()
+    }
+
+    /// Empty cells on the lines through `cell` where a `side` stone would
+    /// win, which after a stone on `cell` are the only new ones.
+    fn fives_through_lines(&mut self, cell: Move, side: CellState) -> Vec<Move> {
+        let lines: Vec<(Move, (isize, isize))> = self.position.windows_through(cell).collect();
+        let mut fives = Vec::new();
+        for (start, dir) in lines {
+            for other in self.position.window_cells(start, dir) {
+                if self.position.get(other.0, other.1) == CellState::Empty
+                    && !fives.contains(&other)
+                    && self.makes_five(other, side)
+                {
+                    fives.push(other);
+                }
+            }
+        }
+        fives
+    }
+
+    fn makes_five(&mut self, cell: Move, side: CellState) -> bool {
+        self.place(cell, side);
+        let five = five_through(&self.position, cell, self.rule).is_some();
+        self.remove(cell);
+        five
+    }
+
+    fn place(&mut self, (row, col): Move, side: CellState) {
+        self.position.set(row, col, side);
+    }
+
+    fn remove(&mut self, (row, col): Move) {
+        self.position.set(row, col, CellState::Empty);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CENTER: usize = SIZE / 2;
+
+    /// Plays `x` and `o` stones turn about, X first, until the game ends.
+    fn play(game: &mut Gomoku, x: &[Move], o: &[Move]) {
+        for (index, &mv) in x.iter().enumerate() {
+            game.play(mv).unwrap();
+            if !game.is_over()
+                && let Some(&reply) = o.get(index)
+            {
+                game.play(reply).unwrap();
+            }
+        }
+    }
+
+    // O's stones, far enough apart to never make a line
+    const O_STONES: [Move; 5] = [(0, 0), (0, 3), (0, 6), (0, 9), (0, 12)];
+
+    #[test]
+    fn five_wins_and_an_overline_only_in_freestyle() {
+        let row = |cols: &[usize]| cols.iter().map(|&col| (7, col)).collect::<Vec<_>>();
+
+        for rule in FiveRule::ALL {
+            let mut game = Gomoku::new(rule, Opening::Free);
+            play(&mut game, &row(&[2, 3, 4, 5, 6]), &O_STONES);
+            assert_eq!(game.winner(), Some(CellState::X), "{}", rule.name());
+        }
+
+        // Filling the gap in X X X X _ X makes six
+        let mut game = Gomoku::new(FiveRule::Exact, Opening::Free);
+        play(&mut game, &row(&[2, 3, 4, 5, 7, 6]), &O_STONES);
+        assert!(!game.is_over());
+        let mut game = Gomoku::new(FiveRule::Freestyle, Opening::Free);
+        play(&mut game, &row(&[2, 3, 4, 5, 7, 6]), &O_STONES);
+        assert_eq!(
+            game.outcome(),
+            Some(&Outcome::Win(CellState::X, row(&[2, 3, 4, 5, 6, 7])))
+        );
+    }
+
+    #[test]
+    fn pro_places_x_in_the_center_then_away_from_it() {
+        let mut game = Gomoku::new(FiveRule::Freestyle, Opening::Pro);
+        assert_eq!(game.legal_moves(), [(CENTER, CENTER)]);
+        assert_eq!(game.play((0, 0)), Err(IllegalMove::Restricted));
+        game.play((CENTER, CENTER)).unwrap();
+
+        // O may go anywhere
+        game.play((CENTER + 1, CENTER)).unwrap();
+
+        assert!(!game.is_allowed((CENTER + 2, CENTER - 2)));
+        assert_eq!(
+            game.play((CENTER - 2, CENTER)),
+            Err(IllegalMove::Restricted)
+        );
+        assert!(game.is_allowed((CENTER - 3, CENTER + 1)));
+        // Everything outside the 5×5 square around the center
+        assert_eq!(game.legal_moves().len(), SIZE * SIZE - 5 * 5);
+        game.play((CENTER + 3, CENTER + 3)).unwrap();
+
+        // After that the opening is over
+        assert_eq!(game.legal_moves().len(), SIZE * SIZE - 3);
+    }
+
+    #[test]
+    fn swap2_lets_the_second_player_pick_a_side_or_place_two() {
+        let mut game = Gomoku::new(FiveRule::Exact, Opening::Swap2);
+        play(&mut game, &[(7, 7), (8, 8)], &[(7, 8)]);
+        assert_eq!(game.mover(), 1);
+        assert_eq!(game.choices().len(), 3);
+        assert_eq!(game.play((9, 9)), Err(IllegalMove::Restricted));
+        assert_eq!(game.player_of(CellState::X), None);
+
+        // Player 1 places O and X, and player 0 picks a color
+        game.choose(Decision::PlaceTwo).unwrap();
+        assert_eq!(game.turn(), CellState::O);
+        game.play((6, 6)).unwrap();
+        assert_eq!(game.mover(), 1);
+        assert_eq!(game.turn(), CellState::X);
+        game.play((6, 7)).unwrap();
+        assert_eq!(game.mover(), 0);
+        assert_eq!(game.choices(), [Decision::TakeX, Decision::TakeO]);
+        assert_eq!(game.choose(Decision::PlaceTwo), Err(IllegalMove::NoChoice));
+
+        game.choose(Decision::TakeO).unwrap();
+        assert_eq!(game.player_of(CellState::X), Some(1));
+        assert_eq!(game.player_of(CellState::O), Some(0));
+        assert_eq!(game.mover(), 0);
+    }
+
+    #[test]
+    fn swap2_taking_a_side_ends_the_opening() {
+        let mut game = Gomoku::new(FiveRule::Freestyle, Opening::Swap2);
+        play(&mut game, &[(7, 7), (8, 8)], &[(7, 8)]);
+        game.choose(Decision::TakeO).unwrap();
+        // Player 1 took O and plays the fourth stone
+        assert_eq!(game.player_of(CellState::O), Some(1));
+        assert_eq!(game.mover(), 1);
+        assert!(game.choices().is_empty());
+        game.play((9, 9)).unwrap();
+        assert_eq!(game.mover(), 0);
+    }
+}
@@ -0,0 +1,22 @@
+//! Rules, positions and AI for tic-tac-toe on N×N boards.
+//!
+//! Nothing in here knows about rendering, so the same engine drives the
+//! macroquad game, headless tools and bots.
+
+pub mod ai;
+pub mod analysis;
+pub mod connect_four;
+pub mod gomoku;
+pub mod mcts;
+pub mod order_chaos;
+pub mod position;
+pub mod qubic;
+pub mod rng;
+pub mod rules;
+pub mod three_piece;
+pub mod tournament;
+pub mod ultimate;
+pub mod wild;
+
+pub use position::{CellState, Move, Position, Topology};
+pub use rules::{Game, IllegalMove, Outcome, Rule};
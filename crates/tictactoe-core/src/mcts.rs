@@ -0,0 +1,253 @@
+//! Monte Carlo Tree Search. Instead of looking at every reply like minimax,
+//! it plays lots of random games and spends more of them on the moves that
+//! keep winning (UCT). That makes it useful on boards far too big to search.
+
+use crate::position::{CellState, Move, Position};
+use crate::rng::Rng;
+use std::time::{Duration, Instant};
+
+// Exploration weight in the UCT formula. √2 is the textbook value for
+// results between 0 and 1.
+const EXPLORATION: f32 = std::f32::consts::SQRT_2;
+
+/// How much searching to do before answering.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Budget {
+    /// A fixed number of playouts. Always gives the same answer for the same
+    /// seed, whatever machine it runs on.
+    Playouts(u32),
+    /// As many playouts as fit in the time.
+    Time(Duration),
+}
+
+struct Node {
+    mv: Option<Move>, // the move that led here; `None` at the root
+    mover: CellState, // who played `mv`
+    parent: Option<usize>,
+    children: Vec<usize>,
+    visits: u32,
+    wins: f32, // from `mover`'s side, a draw counts half
+    // Set when `mv` ended the game: `Some(winner)` or `Some(Empty)` for a draw
+    result: Option<CellState>,
+}
+
+/// The most visited move for `side`.
+pub fn find_best_move(
+    position: &Position,
+    side: CellState,
+    budget: Budget,
+    rng: &mut Rng,
+) -> Option<Move> {
+    visit_counts(position, side, budget, rng)
+        .into_iter()
+        .max_by_key(|&(_, visits)| visits)
+        .map(|(mv, _)| mv)
+}
+
+/// Runs the search and returns how often each tried move was visited. The
+/// randomness all comes from `rng`.
+pub fn visit_counts(
+    position: &Position,
+    side: CellState,
+    budget: Budget,
+    rng: &mut Rng,
+) -> Vec<(Move, u32)> {
+    if position.winner().is_some() || position.is_full() {
+        return Vec::new();
+    }
+
+    let mut nodes = vec![Node {
+        mv: None,
+        mover: side.opponent(),
+        parent: None,
+        children: Vec::new(),
+        visits: 0,
+        wins: 0.0,
+        result: None,
+    }];
+
+    let start = Instant::now();
+    let mut playouts = 0;
+    loop {
+        let done = match budget {
+            Budget::Playouts(limit) => playouts >= limit,
+            Budget::Time(limit) => start.elapsed() >= limit,
+        };
+        // Always play at least once so there is a move to return
+        if done && playouts > 0 {
+            break;
+        }
+
+        let mut scratch = position.clone();
+        let leaf = select_and_expand(&mut nodes, &mut scratch, side, rng);
+        let result = match nodes[leaf].result {
+            Some(result) => result,
+            None => playout(&mut scratch, nodes[leaf].mover.opponent(), rng),
+        };
+        backpropagate(&mut nodes, leaf, result);
+        playouts += 1;
+    }
+
+    nodes[0]
+        .children
+        .iter()
+        .map(|&child| (nodes[child].mv.unwrap(), nodes[child].visits))
+        .collect()
+}
+
+/// Walks down the tree by UCT until it finds a node with an untried move,
+/// adds that move as a new child and returns it. Moves are played onto
+/// `position` along the way.
+fn select_and_expand(
+    nodes: &mut Vec<Node>,
+    position: &mut Position,
+    root_side: CellState,
+    rng: &mut Rng,
+) -> usize {
+    let cols = position.cols();
+    let mut node = 0;
+    let mut side = root_side;
+
+    loop {
+        if nodes[node].result.is_some() {
+            return node;
+        }
+
+        let empty = position.empty_cells();
+        if nodes[node].children.len() < empty.len() {
+            let mut tried = vec![false; position.rows() * cols];
+            for &child in &nodes[node].children {
+                let (row, col) = nodes[child].mv.unwrap();
+                tried[row * cols + col] = true;
+            }
+            let untried: Vec<Move> = empty
+                .iter()
+                .copied()
+                .filter(|&(row, col)| !tried[row * cols + col])
+                .collect();
+            let (row, col) = untried[rng.gen_index(untried.len())];
+
+            position.set(row, col, side);
+            let result = if position.completes_line(row, col) {
+                Some(position.rule().winner(side))
+            } else if empty.len() == 1 {
+                Some(CellState::Empty)
+            } else {
+                None
+            };
+
+            nodes.push(Node {
+                mv: Some((row, col)),
+                mover: side,
+                parent: Some(node),
+                children: Vec::new(),
+                visits: 0,
+                wins: 0.0,
+                result,
+            });
+            let child = nodes.len() - 1;
+            nodes[node].children.push(child);
+            return child;
+        }
+
+        node = best_child(nodes, node);
+        let (row, col) = nodes[node].mv.unwrap();
+        position.set(row, col, side);
+        side = side.opponent();
+    }
+}
+
+/// The child with the highest UCT value. Every child has been visited at
+/// least once, since it got its first playout when it was created.
+fn best_child(nodes: &[Node], node: usize) -> usize {
+    let log_visits = (nodes[node].visits as f32).ln();
+    let uct = |child: usize| {
+        let child = &nodes[child];
+        let visits = child.visits as f32;
+        child.wins / visits + EXPLORATION * (log_visits / visits).sqrt()
+    };
+
+    let mut best = nodes[node].children[0];
+    for &child in &nodes[node].children[1..] {
+        if uct(child) > uct(best) {
+            best = child;
+        }
+    }
+    best
+}
+
+/// Plays random moves until the game ends. Returns the winner, or `Empty`
+/// for a draw.
+fn playout(position: &mut Position, mut side: CellState, rng: &mut Rng) -> CellState {
+    let mut empty = position.empty_cells();
+    while !empty.is_empty() {
+        let (row, col) = empty.swap_remove(rng.gen_index(empty.len()));
+        position.set(row, col, side);
+        if position.completes_line(row, col) {
+            return position.rule().winner(side);
+        }
+        side = side.opponent();
+    }
+    CellState::Empty
+}
+
+fn backpropagate(nodes: &mut [Node], leaf: usize, result: CellState) {
+    let mut current = Some(leaf);
+    while let Some(index) = current {
+        let node = &mut nodes[index];
+        node.visits += 1;
+        if result == node.mover {
+            node.wins += 1.0;
+        } else if result == CellState::Empty {
+            node.wins += 0.5;
+        }
+        current = node.parent;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PLAYOUTS: Budget = Budget::Playouts(2000);
+
+    fn position(moves: &[Move]) -> (Position, CellState) {
+        let mut position = Position::new(3, 3);
+        let mut side = CellState::X;
+        for &(row, col) in moves {
+            position.set(row, col, side);
+            side = side.opponent();
+        }
+        (position, side)
+    }
+
+    #[test]
+    fn same_seed_same_search() {
+        let mut position = Position::new(5, 4);
+        position.set(2, 2, CellState::X);
+        for seed in 0..5 {
+            let search = || visit_counts(&position, CellState::O, PLAYOUTS, &mut Rng::new(seed));
+            assert_eq!(search(), search(), "seed {seed}");
+        }
+    }
+
+    #[test]
+    fn takes_a_win_on_the_spot() {
+        // X can finish the top row
+        let (position, side) = position(&[(0, 0), (1, 1), (0, 1), (2, 2)]);
+        for seed in 0..5 {
+            let mv = find_best_move(&position, side, PLAYOUTS, &mut Rng::new(seed));
+            assert_eq!(mv, Some((0, 2)), "seed {seed}");
+        }
+    }
+
+    #[test]
+    fn blocks_a_line() {
+        // O has to stop X's top row
+        let (position, side) = position(&[(0, 0), (1, 1), (0, 1)]);
+        for seed in 0..5 {
+            let mv = find_best_move(&position, side, PLAYOUTS, &mut Rng::new(seed));
+            assert_eq!(mv, Some((0, 2)), "seed {seed}");
+        }
+    }
+}
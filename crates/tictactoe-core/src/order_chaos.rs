@@ -0,0 +1,555 @@
+//! Order and Chaos: on a 6×6 board both players place either symbol, as in
+//! wild tic-tac-toe, but they want different things. Order wins with five of
+//! one symbol in a row (six counts too); Chaos wins if the board fills up
+//! without one. Order moves first.
+//!
+//! There are no draws, and the result is a role rather than a symbol.
+
+use crate::ai::{self, Difficulty, Ending, Searchable};
+use crate::position::{CellState, Move, Position};
+use crate::rng::Rng;
+use crate::rules::IllegalMove;
+use crate::wild::WildMove;
+use std::time::Duration;
+
+/// Cells per side of the board.
+pub const SIZE: usize = 6;
+/// Pieces of one symbol in a row that win for Order.
+pub const WIN_LENGTH: usize = 5;
+
+// What a line nobody has spoiled with the other symbol is worth to Order, by
+// how many pieces it holds. An empty one is still a chance
+const LINE_WEIGHTS: [f32; WIN_LENGTH] = [0.5, 1.0, 4.0, 16.0, 64.0];
+
+// Medium weighs only its own move; Hard sees the reply to it too
+const MEDIUM_DEPTH: usize = 1;
+const HARD_DEPTH: usize = 2;
+
+/// The two sides, which want opposite things.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Role {
+    /// Wants five in a row.
+    Order,
+    /// Wants a full board without one.
+    Chaos,
+}
+
+impl Role {
+    pub const ALL: [Role; 2] = [Role::Order, Role::Chaos];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Role::Order => "Order",
+            Role::Chaos => "Chaos",
+        }
+    }
+
+    pub fn opponent(self) -> Self {
+        match self {
+            Role::Order => Role::Chaos,
+            Role::Chaos => Role::Order,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum OrderChaosOutcome {
+    /// Order's five in a row, whoever placed the last piece of it.
+    Line(Vec<Move>),
+    /// The board filled up without one, so Chaos wins.
+    Blocked,
+}
+
+impl OrderChaosOutcome {
+    pub fn winner(&self) -> Role {
+        match self {
+            OrderChaosOutcome::Line(_) => Role::Order,
+            OrderChaosOutcome::Blocked => Role::Chaos,
+        }
+    }
+}
+
+/// A game of Order and Chaos in progress.
+#[derive(Clone, Debug)]
+pub struct OrderChaos {
+    position: Position,
+    turn: Role,
+    moves: Vec<WildMove>,
+    outcome: Option<OrderChaosOutcome>,
+}
+
+impl Default for OrderChaos {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl OrderChaos {
+    pub fn new() -> Self {
+        Self {
+            position: Position::new(SIZE, WIN_LENGTH),
+            turn: Role::Order,
+            moves: Vec::new(),
+            outcome: None,
+        }
+    }
+
+    pub fn position(&self) -> &Position {
+        &self.position
+    }
+
+    /// The role to move. Once the game is over this stays on the last mover.
+    pub fn turn(&self) -> Role {
+        self.turn
+    }
+
+    pub fn moves(&self) -> &[WildMove] {
+        &self.moves
+    }
+
+    pub fn outcome(&self) -> Option<&OrderChaosOutcome> {
+        self.outcome.as_ref()
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.outcome.is_some()
+    }
+
+    pub fn winner(&self) -> Option<Role> {
+        self.outcome.as_ref().map(OrderChaosOutcome::winner)
+    }
+
+    /// Every empty cell, once with each symbol.
+    pub fn legal_moves(&self) -> Vec<WildMove> {
+        if self.is_over() {
+            return Vec::new();
+        }
+        self.position
+            .empty_cells()
+            .into_iter()
+            .flat_map(|cell| [CellState::X, CellState::O].map(|symbol| WildMove { cell, symbol }))
+            .collect()
+    }
+
+    pub fn play(&mut self, mv: WildMove) -> Result<(), IllegalMove> {
+        let (row, col) = mv.cell;
+        if self.is_over() {
+            return Err(IllegalMove::GameOver);
+        }
+        if !self.position.contains(row, col) {
+            return Err(IllegalMove::OffBoard);
+        }
+        if self.position.get(row, col) != CellState::Empty {
+            return Err(IllegalMove::Occupied);
+        }
+        if mv.symbol == CellState::Empty {
+            return Err(IllegalMove::NoSymbol);
+        }
+
+        self.position.set(row, col, mv.symbol);
+        self.moves.push(mv);
+
+        if self.position.completes_line(row, col) {
+            let (_, line) = self
+                .position
+                .winner()
+                .expect("a completed line is a winner");
+            self.outcome = Some(OrderChaosOutcome::Line(line));
+        } else if self.position.is_full() {
+            self.outcome = Some(OrderChaosOutcome::Blocked);
+        } else {
+            self.turn = self.turn.opponent();
+        }
+        Ok(())
+    }
+
+    /// Takes back the last move, if there is one.
+    pub fn undo(&mut self) -> Option<WildMove> {
+        let mv = self.moves.pop()?;
+        self.position.set(mv.cell.0, mv.cell.1, CellState::Empty);
+        // A finished game never switched turns after its last move
+        if self.outcome.take().is_none() {
+            self.turn = self.turn.opponent();
+        }
+        Some(mv)
+    }
+}
+
+/// Picks a move for the role to move, trying both symbols on every cell.
+/// Easy plays at random. Medium and Hard search a fixed number of moves
+/// ahead and Perfect as deep as it can in `think_time`; all three finish a
+/// five for Order and block one for Chaos.
+pub fn find_best_move(
+    game: &OrderChaos,
+    difficulty: Difficulty,
+    think_time: Duration,
+    rng: &mut Rng,
+) -> Option<WildMove> {
+    let moves = game.legal_moves();
+    if moves.is_empty() {
+        return None;
+    }
+    let max_depth = match difficulty {
+        Difficulty::Easy => return Some(moves[rng.gen_index(moves.len())]),
+        Difficulty::Medium => MEDIUM_DEPTH,
+        Difficulty::Hard => HARD_DEPTH,
+        Difficulty::Perfect => usize::MAX,
+    };
+
+    let mut lines = Lines::new(&game.position, game.turn());
+    let (cell, symbol) = ai::search_best_move(&mut lines, max_depth, think_time, rng)?;
+    Some(WildMove {
+        cell: (cell / SIZE, cell % SIZE),
+        symbol,
+    })
+}
+
+/// The board for the search, with each line's X and O counts kept up to
+/// date, so a move only touches the lines through its cell. A line with
+/// both symbols in it is dead: it can never be Order's five.
+struct Lines {
+    cells: Vec<CellState>,
+    lines: Vec<[usize; WIN_LENGTH]>,
+    lines_through: Vec<Vec<usize>>,
+    counts: Vec<[usize; 2]>, // X and O pieces on each line
+    live: usize,             // lines with only one symbol in them, or none
+    turn: Role,
+    five: bool, // the last move made Order's five
+}
+
+impl Lines {
+    fn new(position: &Position, turn: Role) -> Self {
+        let lines: Vec<[usize; WIN_LENGTH]> = position
+            .windows()
+            .map(|(start, dir)| {
+                let mut line = [0; WIN_LENGTH];
+                for (i, (row, col)) in position.window_cells(start, dir).enumerate() {
+                    line[i] = row * SIZE + col;
+                }
+                line
+            })
+            .collect();
+        let mut lines_through = vec![Vec::new(); SIZE * SIZE];
+        for (index, line) in lines.iter().enumerate() {
+            for &cell in line {
+                lines_through[cell].push(index);
+            }
+        }
+
+        let mut board = Self {
+            cells: vec![CellState::Empty; SIZE * SIZE],
+            counts: vec![[0; 2]; lines.len()],
+            live: lines.len(),
+            lines,
+            lines_through,
+            turn,
+            five: false,
+        };
+        for (row, col) in (0..SIZE).flat_map(|row| (0..SIZE).map(move |col| (row, col))) {
+            let state = position.get(row, col);
+            if state != CellState::Empty {
+                board.place(row * SIZE + col, state);
+            }
+        }
+        board
+    }
+
+    /// Places `symbol` on `cell` and returns whether that made five.
+    fn place(&mut self, cell: usize, symbol: CellState) -> bool {
+        self.cells[cell] = symbol;
+        let mut five = false;
+        for &line in &self.lines_through[cell] {
+            let [x, o] = self.counts[line];
+            let counts = &mut self.counts[line];
+            counts[symbol_index(symbol)] += 1;
+            if x.min(o) == 0 && counts[0].min(counts[1]) > 0 {
+                self.live -= 1;
+            }
+            five |= counts[symbol_index(symbol)] == WIN_LENGTH;
+        }
+        five
+    }
+
+    fn remove(&mut self, cell: usize) {
+        let symbol = self.cells[cell];
+        self.cells[cell] = CellState::Empty;
+        for &line in &self.lines_through[cell] {
+            let counts = &mut self.counts[line];
+            let was_dead = counts[0].min(counts[1]) > 0;
+            counts[symbol_index(symbol)] -= 1;
+            if was_dead && counts[0].min(counts[1]) == 0 {
+                self.live += 1;
+            }
+        }
+    }
+
+    /// The empty cells that would finish a live line of four, with the
+    /// symbol that finishes it, without repeating a cell.
+    fn threats(&self) -> Vec<(usize, CellState)> {
+        let mut threats: Vec<(usize, CellState)> = Vec::new();
+        for (line, &[x, o]) in self.counts.iter().enumerate() {
+            let symbol = match (x, o) {
+                (4, 0) => CellState::X,
+                (0, 4) => CellState::O,
+                _ => continue,
+            };
+            let Some(&cell) = self.lines[line]
+                .iter()
+                .find(|&&cell| self.cells[cell] == CellState::Empty)
+            else {
+                continue;
+            };
+            match threats.iter().position(|&(other, _)| other == cell) {
+                // Both symbols finish a line here, so nothing can spoil it
+                Some(i) if threats[i].1 != symbol => threats.push((cell, symbol)),
+                Some(_) => {}
+                None => threats.push((cell, symbol)),
+            }
+        }
+        threats
+    }
+
+    /// Every empty cell with each symbol, in the order `role` would like
+    /// them best: Order building lines up, Chaos spoiling the fullest.
+    fn ordered_moves(&self, role: Role) -> Vec<(usize, CellState)> {
+        let mut moves: Vec<((usize, CellState), f32)> = (0..SIZE * SIZE)
+            .filter(|&cell| self.cells[cell] == CellState::Empty)
+            .flat_map(|cell| {
+                [CellState::X, CellState::O].map(|symbol| {
+                    let gain = self.gain(cell, symbol);
+                    (
+                        (cell, symbol),
+                        if role == Role::Order { gain } else { -gain },
+                    )
+                })
+            })
+            .collect();
+        moves.sort_by(|a, b| b.1.total_cmp(&a.1));
+        moves.into_iter().map(|(mv, _)| mv).collect()
+    }
+
+    /// How much `symbol` on the empty `cell` changes Order's score.
+    fn gain(&self, cell: usize, symbol: CellState) -> f32 {
+        self.lines_through[cell]
+            .iter()
+            .map(|&line| {
+                let before = line_value(self.counts[line]);
+                let mut after = self.counts[line];
+                after[symbol_index(symbol)] += 1;
+                line_value(after) - before
+            })
+            .sum()
+    }
+
+    /// Static score for `role`: how much Order has to build on.
+    fn evaluate(&self, role: Role) -> f32 {
+        let score: f32 = self.counts.iter().map(|&counts| line_value(counts)).sum();
+        if role == Role::Order { score } else { -score }
+    }
+}
+
+impl Searchable for Lines {
+    type Move = (usize, CellState);
+
+    fn outcome(&mut self) -> Option<Ending> {
+        let won_by = |winner: Role| {
+            Some(if winner == self.turn {
+                Ending::Win(0)
+            } else {
+                Ending::Loss(0)
+            })
+        };
+        // Order's five, whoever made it
+        if self.five {
+            return won_by(Role::Order);
+        }
+        // Once every line is dead, or the board is full, Chaos has won
+        if self.live == 0 {
+            return won_by(Role::Chaos);
+        }
+
+        // Order finishes any line one short; Chaos can spoil only one
+        let threats = self.threats();
+        match self.turn {
+            Role::Order if !threats.is_empty() => Some(Ending::Win(1)),
+            Role::Chaos if threats.len() > 1 => Some(Ending::Loss(2)),
+            _ => None,
+        }
+    }
+
+    /// Chaos answering a single threat has one cell to play on.
+    fn forced_move(&mut self) -> Option<(usize, CellState)> {
+        match self.threats()[..] {
+            [(cell, symbol)] => Some((cell, symbol.opponent())),
+            _ => None,
+        }
+    }
+
+    fn legal_moves(&mut self) -> Vec<(usize, CellState)> {
+        self.ordered_moves(self.turn)
+    }
+
+    fn play(&mut self, (cell, symbol): (usize, CellState)) {
+        self.five = self.place(cell, symbol);
+        self.turn = self.turn.opponent();
+    }
+
+    /// Nothing is ever played after a five, so taking any move back leaves
+    /// a position without one.
+    fn undo(&mut self, (cell, _): (usize, CellState)) {
+        self.remove(cell);
+        self.five = false;
+        self.turn = self.turn.opponent();
+    }
+
+    fn evaluate(&mut self) -> f32 {
+        Lines::evaluate(self, self.turn)
+    }
+}
+
+/// What a line is worth to Order: nothing once it holds both symbols.
+fn line_value([x, o]: [usize; 2]) -> f32 {
+    match (x, o) {
+        (n, 0) | (0, n) if n < WIN_LENGTH => LINE_WEIGHTS[n],
+        _ => 0.0,
+    }
+}
+
+fn symbol_index(symbol: CellState) -> usize {
+    match symbol {
+        CellState::O => 1,
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use CellState::{O, X};
+
+    fn play(game: &mut OrderChaos, moves: &[(Move, CellState)]) {
+        for &(cell, symbol) in moves {
+            game.play(WildMove { cell, symbol }).unwrap();
+        }
+    }
+
+    #[test]
+    fn five_of_a_symbol_wins_for_order_whoever_places_it() {
+        let mut game = OrderChaos::new();
+        play(
+            &mut game,
+            &[
+                ((0, 0), O),
+                ((5, 5), X),
+                ((1, 1), O),
+                ((5, 0), X),
+                ((2, 2), O),
+                ((5, 1), X),
+                ((3, 3), O),
+            ],
+        );
+        assert_eq!(game.turn(), Role::Chaos);
+        // An X on (4, 4) spoils the diagonal; an O there finishes it for Order
+        let mut spoiled = game.clone();
+        play(&mut spoiled, &[((4, 4), X)]);
+        assert!(!spoiled.is_over());
+        play(&mut game, &[((4, 4), O)]);
+        assert_eq!(game.winner(), Some(Role::Order));
+        assert_eq!(game.turn(), Role::Chaos);
+
+        // A sixth in the row counts as well
+        let mut game = OrderChaos::new();
+        let row: Vec<_> = [0, 1, 2, 3, 5, 4].map(|col| ((2, col), X)).to_vec();
+        play(&mut game, &row[..5]);
+        assert!(!game.is_over());
+        play(&mut game, &row[5..]);
+        assert_eq!(game.winner(), Some(Role::Order));
+    }
+
+    #[test]
+    fn a_full_board_without_five_wins_for_chaos() {
+        // Symbols swap every column and every second row, so no run of one
+        // symbol is longer than two
+        let mut game = OrderChaos::new();
+        for row in 0..SIZE {
+            for col in 0..SIZE {
+                let symbol = if (row / 2 + col) % 2 == 0 { X } else { O };
+                game.play(WildMove {
+                    cell: (row, col),
+                    symbol,
+                })
+                .unwrap();
+            }
+        }
+        assert_eq!(game.outcome(), Some(&OrderChaosOutcome::Blocked));
+        assert_eq!(game.winner(), Some(Role::Chaos));
+        // The 36th move was Chaos's
+        assert_eq!(game.turn(), Role::Chaos);
+
+        game.undo();
+        assert!(!game.is_over());
+        assert_eq!(game.turn(), Role::Chaos);
+        assert_eq!(game.legal_moves().len(), 2);
+    }
+
+    #[test]
+    fn rejects_bad_moves() {
+        let mut game = OrderChaos::new();
+        play(&mut game, &[((0, 0), X)]);
+        let empty = WildMove {
+            cell: (1, 1),
+            symbol: CellState::Empty,
+        };
+        assert_eq!(game.play(empty), Err(IllegalMove::NoSymbol));
+        assert_eq!(
+            game.play(WildMove {
+                cell: (0, 0),
+                symbol: O
+            }),
+            Err(IllegalMove::Occupied)
+        );
+        assert_eq!(
+            game.play(WildMove {
+                cell: (6, 0),
+                symbol: O
+            }),
+            Err(IllegalMove::OffBoard)
+        );
+    }
+
+    #[test]
+    fn the_ai_finishes_or_spoils_a_four() {
+        let mut game = OrderChaos::new();
+        play(
+            &mut game,
+            &[
+                ((0, 0), X),
+                ((5, 5), O),
+                ((0, 1), X),
+                ((5, 0), O),
+                ((0, 2), X),
+                ((3, 5), O),
+                ((0, 3), X),
+            ],
+        );
+        let think_time = Duration::from_secs(10);
+        let mv = find_best_move(&game, Difficulty::Medium, think_time, &mut Rng::new(1));
+        assert_eq!(
+            mv,
+            Some(WildMove {
+                cell: (0, 4),
+                symbol: O
+            })
+        );
+
+        play(&mut game, &[((4, 2), O)]);
+        let mv = find_best_move(&game, Difficulty::Medium, think_time, &mut Rng::new(1));
+        assert_eq!(
+            mv,
+            Some(WildMove {
+                cell: (0, 4),
+                symbol: X
+            })
+        );
+    }
+}
@@ -0,0 +1,413 @@
+use crate::rules::Rule;
+
+pub const MIN_BOARD_SIZE: usize = 3;
+pub const MAX_BOARD_SIZE: usize = 15;
+pub const MIN_WIN_LENGTH: usize = 3;
+
+// Row/column steps for horizontal, vertical, diagonal and anti-diagonal lines
+pub(crate) const DIRECTIONS: [(isize, isize); 4] = [(0, 1), (1, 0), (1, 1), (1, -1)];
+
+/// A cell as `(row, col)`, counted from the top left.
+pub type Move = (usize, usize);
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum CellState {
+    Empty,
+    X,
+    O,
+}
+
+impl CellState {
+    /// The other side. `Empty` stays `Empty`.
+    pub fn opponent(self) -> Self {
+        match self {
+            CellState::X => CellState::O,
+            CellState::O => CellState::X,
+            CellState::Empty => CellState::Empty,
+        }
+    }
+}
+
+/// How the board's edges join up, which decides where lines can run.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Topology {
+    /// Lines stop at the edges.
+    Flat,
+    /// The left and right edges join, so a line can run off one side and
+    /// carry on from the other.
+    Cylinder,
+    /// The top and bottom edges join as well.
+    Torus,
+}
+
+impl Topology {
+    pub const ALL: [Topology; 3] = [Topology::Flat, Topology::Cylinder, Topology::Torus];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Topology::Flat => "Flat",
+            Topology::Cylinder => "Cylinder",
+            Topology::Torus => "Torus",
+        }
+    }
+
+    /// Whether lines can wrap from the bottom row to the top.
+    pub fn wraps_rows(self) -> bool {
+        self == Topology::Torus
+    }
+
+    /// Whether lines can wrap from the rightmost column to the leftmost.
+    pub fn wraps_cols(self) -> bool {
+        self != Topology::Flat
+    }
+}
+
+/// The pieces on a board plus the number in a row that makes a line and
+/// what completing one does. Boards are N×N except in variants that ask for
+/// a rectangle.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Position {
+    rows: usize,
+    cols: usize,
+    win_length: usize,
+    rule: Rule,
+    topology: Topology,
+    cells: Vec<CellState>,
+}
+
+impl Position {
+    /// An empty board. Size and win length are clamped to the supported range.
+    pub fn new(size: usize, win_length: usize) -> Self {
+        Self::rectangular(size, size, win_length)
+    }
+
+    /// An empty board `rows` high and `cols` wide. Both are clamped like the
+    /// size in `new`, and the win length to the longer side.
+    pub fn rectangular(rows: usize, cols: usize, win_length: usize) -> Self {
+        let rows = rows.clamp(MIN_BOARD_SIZE, MAX_BOARD_SIZE);
+        let cols = cols.clamp(MIN_BOARD_SIZE, MAX_BOARD_SIZE);
+        let win_length = win_length.clamp(MIN_WIN_LENGTH, rows.max(cols));
+
+        Self {
+            rows,
+            cols,
+            win_length,
+            rule: Rule::Standard,
+            topology: Topology::Flat,
+            cells: vec![CellState::Empty; rows * cols],
+        }
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    pub fn win_length(&self) -> usize {
+        self.win_length
+    }
+
+    pub fn rule(&self) -> Rule {
+        self.rule
+    }
+
+    pub fn set_rule(&mut self, rule: Rule) {
+        self.rule = rule;
+    }
+
+    pub fn topology(&self) -> Topology {
+        self.topology
+    }
+
+    pub fn set_topology(&mut self, topology: Topology) {
+        self.topology = topology;
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> CellState {
+        self.cells[row * self.cols + col]
+    }
+
+    pub fn set(&mut self, row: usize, col: usize, state: CellState) {
+        self.cells[row * self.cols + col] = state;
+    }
+
+    pub fn contains(&self, row: usize, col: usize) -> bool {
+        row < self.rows && col < self.cols
+    }
+
+    pub fn empty_cells(&self) -> Vec<Move> {
+        let mut cells = Vec::new();
+        for row in 0..self.rows {
+            for col in 0..self.cols {
+                if self.get(row, col) == CellState::Empty {
+                    cells.push((row, col));
+                }
+            }
+        }
+        cells
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.cells.iter().all(|&cell| cell != CellState::Empty)
+    }
+
+    pub fn clear(&mut self) {
+        self.cells.fill(CellState::Empty);
+    }
+
+    /// The first completed line, with the side that owns it. That side has
+    /// lost rather than won under `Rule::Misere`; see `Rule::winner`.
+    pub fn winner(&self) -> Option<(CellState, Vec<Move>)> {
+        for (start, dir) in self.windows() {
+            let state = self.get(start.0, start.1);
+            if state != CellState::Empty
+                && self
+                    .window_cells(start, dir)
+                    .all(|(row, col)| self.get(row, col) == state)
+            {
+                return Some((state, self.window_cells(start, dir).collect()));
+            }
+        }
+
+        None
+    }
+
+    /// Whether the piece on `(row, col)` is part of a completed line. Much
+    /// cheaper than `winner` when only the last move can have won.
+    pub fn completes_line(&self, row: usize, col: usize) -> bool {
+        let state = self.get(row, col);
+        if state == CellState::Empty {
+            return false;
+        }
+
+        // Lines that wrap can come back round to the same cell, so walk
+        // every window through it rather than counting runs
+        if self.topology != Topology::Flat {
+            return self.windows_through((row, col)).any(|(start, dir)| {
+                self.window_cells(start, dir)
+                    .all(|(r, c)| self.get(r, c) == state)
+            });
+        }
+
+        let run = |dr: isize, dc: isize| {
+            let mut count = 0;
+            let (mut r, mut c) = (row as isize + dr, col as isize + dc);
+            while r >= 0
+                && c >= 0
+                && self.contains(r as usize, c as usize)
+                && self.get(r as usize, c as usize) == state
+            {
+                count += 1;
+                r += dr;
+                c += dc;
+            }
+            count
+        };
+
+        DIRECTIONS
+            .iter()
+            .any(|&(dr, dc)| 1 + run(dr, dc) + run(-dr, -dc) >= self.win_length)
+    }
+
+    /// Every line of `win_length` cells on the board, as a start cell plus a
+    /// direction. Feed these into `window_cells` to walk a run.
+    pub fn windows(&self) -> impl Iterator<Item = (Move, (isize, isize))> + '_ {
+        (0..self.rows).flat_map(move |row| {
+            (0..self.cols).flat_map(move |col| {
+                DIRECTIONS
+                    .into_iter()
+                    .filter(move |&dir| self.is_window((row, col), dir))
+                    .map(move |dir| ((row, col), dir))
+            })
+        })
+    }
+
+    /// The lines from `windows` that pass through `cell`.
+    pub fn windows_through(&self, cell: Move) -> impl Iterator<Item = (Move, (isize, isize))> + '_ {
+        DIRECTIONS.into_iter().flat_map(move |dir| {
+            (0..self.win_length as isize)
+                .filter_map(move |back| self.step(cell, dir, -back))
+                .filter(move |&start| self.is_window(start, dir))
+                .map(move |start| (start, dir))
+        })
+    }
+
+    /// Whether the run of `win_length` cells from `start` along `dir` is a
+    /// line: it stays on the board, wrapping only where the topology joins
+    /// the edges, and never visits a cell twice. A run that wraps all the
+    /// way round is the same line from any of its cells, so it only counts
+    /// from its first one.
+    fn is_window(&self, start: Move, (dr, dc): (isize, isize)) -> bool {
+        let reach = self.win_length as isize - 1;
+        // Steps until the run gets back to its start, if it ever does
+        let mut period = Some(1);
+        for (at, step, len, wraps) in [
+            (start.0, dr, self.rows, self.topology.wraps_rows()),
+            (start.1, dc, self.cols, self.topology.wraps_cols()),
+        ] {
+            if step == 0 {
+                continue;
+            }
+            if wraps {
+                period = period.map(|period| lcm(period, len));
+            } else if (0..len as isize).contains(&(at as isize + step * reach)) {
+                period = None;
+            } else {
+                return false;
+            }
+        }
+
+        match period {
+            Some(period) if self.win_length == period => {
+                self.window_cells(start, (dr, dc)).min() == Some(start)
+            }
+            Some(period) => self.win_length < period,
+            None => true,
+        }
+    }
+
+    /// `cell` moved `times` steps along `dir`, wrapping where the topology
+    /// allows. `None` if that goes off an edge that doesn't join.
+    pub(crate) fn step(
+        &self,
+        (row, col): Move,
+        (dr, dc): (isize, isize),
+        times: isize,
+    ) -> Option<Move> {
+        let wrap = |at: usize, step: isize, len: usize, wraps: bool| {
+            let to = at as isize + step * times;
+            if wraps {
+                Some(to.rem_euclid(len as isize) as usize)
+            } else {
+                (0..len as isize).contains(&to).then_some(to as usize)
+            }
+        };
+        Some((
+            wrap(row, dr, self.rows, self.topology.wraps_rows())?,
+            wrap(col, dc, self.cols, self.topology.wraps_cols())?,
+        ))
+    }
+
+    pub fn window_cells(
+        &self,
+        start: Move,
+        dir: (isize, isize),
+    ) -> impl Iterator<Item = Move> + use<> {
+        // Wrapping is a no-op for runs that stay on the board
+        let (rows, cols) = (self.rows as isize, self.cols as isize);
+        (0..self.win_length as isize).map(move |i| {
+            (
+                (start.0 as isize + dir.0 * i).rem_euclid(rows) as usize,
+                (start.1 as isize + dir.1 * i).rem_euclid(cols) as usize,
+            )
+        })
+    }
+}
+
+fn lcm(a: usize, b: usize) -> usize {
+    let gcd = |mut a: usize, mut b: usize| {
+        while b != 0 {
+            (a, b) = (b, a % b);
+        }
+        a
+    };
+    a / gcd(a, b) * b
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn board(rows: usize, cols: usize, win_length: usize, topology: Topology) -> Position {
+        let mut position = Position::rectangular(rows, cols, win_length);
+        position.set_topology(topology);
+        position
+    }
+
+    #[test]
+    fn counts_windows() {
+        let cases = [
+            // rows, cols, win length, topology, windows
+            (3, 3, 3, Topology::Flat, 8),
+            (4, 4, 3, Topology::Flat, 24),
+            // Columns and diagonals are shorter than a line
+            (3, 5, 5, Topology::Flat, 3),
+            (3, 3, 3, Topology::Cylinder, 12),
+            (4, 4, 3, Topology::Torus, 64),
+            // A line that wraps all the way round counts once, not once per cell
+            (3, 3, 3, Topology::Torus, 12),
+            // Columns would come back round to their start before five, the
+            // rows just make it, and diagonals take fifteen steps to repeat
+            (3, 5, 5, Topology::Torus, 33),
+        ];
+        for (rows, cols, win_length, topology, windows) in cases {
+            let position = board(rows, cols, win_length, topology);
+            assert_eq!(
+                position.windows().count(),
+                windows,
+                "{rows}x{cols}, {win_length} in a row, {}",
+                topology.name()
+            );
+        }
+    }
+
+    #[test]
+    fn windows_through_a_cell_match_windows() {
+        for topology in Topology::ALL {
+            for (rows, cols, win_length) in [(3, 3, 3), (4, 4, 3), (3, 5, 5), (5, 5, 4)] {
+                let position = board(rows, cols, win_length, topology);
+                for row in 0..rows {
+                    for col in 0..cols {
+                        let mut through: Vec<_> = position.windows_through((row, col)).collect();
+                        through.sort();
+                        let mut expected: Vec<_> = position
+                            .windows()
+                            .filter(|&(start, dir)| {
+                                position
+                                    .window_cells(start, dir)
+                                    .any(|cell| cell == (row, col))
+                            })
+                            .collect();
+                        expected.sort();
+                        assert_eq!(through, expected, "({row}, {col}) {}", topology.name());
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn wins_across_the_seam() {
+        let cases = [
+            // Along a row, off the right edge and back in on the left
+            (4, Topology::Cylinder, [(1, 3), (1, 0), (1, 1)]),
+            // Down a column, off the bottom and back in at the top
+            (4, Topology::Torus, [(3, 2), (0, 2), (1, 2)]),
+            // Through the corner
+            (4, Topology::Torus, [(3, 3), (0, 0), (1, 1)]),
+            // A diagonal that wraps round a board its own length
+            (3, Topology::Torus, [(0, 1), (1, 2), (2, 0)]),
+        ];
+        for (size, topology, line) in cases {
+            for wraps in [false, true] {
+                let mut position = Position::new(size, 3);
+                if wraps {
+                    position.set_topology(topology);
+                }
+                for (row, col) in line {
+                    position.set(row, col, CellState::X);
+                }
+                let (row, col) = line[1];
+                assert_eq!(position.completes_line(row, col), wraps, "{line:?}");
+                assert_eq!(
+                    position.winner().map(|(side, _)| side),
+                    wraps.then_some(CellState::X),
+                    "{line:?}"
+                );
+            }
+        }
+    }
+}
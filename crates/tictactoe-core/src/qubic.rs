@@ -0,0 +1,657 @@
+//! Qubic: tic-tac-toe in a 4×4×4 cube, where four in a row along any of its
+//! 76 lines wins.
+//!
+//! The cube is four 4×4 layers. Cells are addressed with the layers laid side
+//! by side, so `(1, 9)` is row 1, column 1 of layer 2; win lines in
+//! `Outcome::Win` use the same coordinates. Of the lines, 48 run along a row,
+//! column or pillar, 24 are diagonals of a flat slice and 4 run corner to
+//! corner through the middle.
+
+use crate::ai::{self, Clock, Difficulty, Ending, Searchable};
+use crate::position::{CellState, Move};
+use crate::rng::Rng;
+use crate::rules::{IllegalMove, Outcome};
+use std::time::{Duration, Instant};
+
+/// Cells per edge of the cube, and pieces in a row that win.
+pub const SIZE: usize = 4;
+/// Cells in the whole cube.
+pub const CELLS: usize = SIZE * SIZE * SIZE;
+/// Winning lines through the cube.
+pub const LINE_COUNT: usize = 76;
+
+// Every line as cell indices, layer by layer, then row by row within a layer
+const LINES: [[usize; SIZE]; LINE_COUNT] = lines();
+
+// The lines through each cell: seven through corners and the middle eight
+// cells, four through the rest
+const MAX_LINES_PER_CELL: usize = 7;
+const LINES_THROUGH: [[usize; MAX_LINES_PER_CELL]; CELLS] = lines_through().0;
+const LINES_THROUGH_COUNT: [usize; CELLS] = lines_through().1;
+
+// What a line with only one side's pieces in it is worth, by how many. Two
+// of them meeting on an empty cell is how forks start, so two in a line is
+// worth a lot more than one
+const LINE_WEIGHTS: [f32; SIZE] = [0.0, 1.0, 6.0, 40.0];
+
+// What each cell the opponent could fork on costs at the end of a search line
+const FORK_WEIGHT: f32 = 100.0;
+
+// Blocking the opponent's best cell counts a little less than taking your own
+const DEFENSE_WEIGHT: f32 = 0.9;
+
+// How many threes in a row the threat search may chain
+const HARD_THREAT_DEPTH: usize = 4;
+const PERFECT_THREAT_DEPTH: usize = 12;
+
+// Search depths for the weaker levels; Perfect deepens until time runs out
+const MEDIUM_DEPTH: usize = 1;
+const HARD_DEPTH: usize = 3;
+
+const fn lines() -> [[usize; SIZE]; LINE_COUNT] {
+    let mut lines = [[0; SIZE]; LINE_COUNT];
+    let mut count = 0;
+    let last = SIZE as isize - 1;
+
+    // Each direction with its first nonzero step positive, so every line
+    // turns up once. A line has to span the cube, so a coordinate that
+    // moves starts at the near edge and one that doesn't can be anything
+    let mut dir = 0;
+    while dir < 27 {
+        let step = [dir / 9 - 1, dir / 3 % 3 - 1, dir % 3 - 1];
+        let first = if step[0] != 0 {
+            step[0]
+        } else if step[1] != 0 {
+            step[1]
+        } else {
+            step[2]
+        };
+        if first > 0 {
+            let mut start = 0;
+            while start < CELLS as isize {
+                let from = [
+                    start / (SIZE * SIZE) as isize,
+                    start / SIZE as isize % SIZE as isize,
+                    start % SIZE as isize,
+                ];
+                let mut spans = true;
+                let mut axis = 0;
+                while axis < 3 {
+                    spans &= match step[axis] {
+                        1 => from[axis] == 0,
+                        -1 => from[axis] == last,
+                        _ => true,
+                    };
+                    axis += 1;
+                }
+                if spans {
+                    let mut i = 0;
+                    while i < SIZE as isize {
+                        let layer = from[0] + step[0] * i;
+                        let row = from[1] + step[1] * i;
+                        let col = from[2] + step[2] * i;
+                        lines[count][i as usize] =
+                            (layer as usize * SIZE + row as usize) * SIZE + col as usize;
+                        i += 1;
+                    }
+                    count += 1;
+                }
+                start += 1;
+            }
+        }
+        dir += 1;
+    }
+    assert!(count == LINE_COUNT);
+    lines
+}
+
+const fn lines_through() -> ([[usize; MAX_LINES_PER_CELL]; CELLS], [usize; CELLS]) {
+    let mut through = [[0; MAX_LINES_PER_CELL]; CELLS];
+    let mut counts = [0; CELLS];
+    let mut line = 0;
+    while line < LINE_COUNT {
+        let mut i = 0;
+        while i < SIZE {
+            let cell = LINES[line][i];
+            through[cell][counts[cell]] = line;
+            counts[cell] += 1;
+            i += 1;
+        }
+        line += 1;
+    }
+    (through, counts)
+}
+
+/// The layer, row and column of a cell.
+pub fn split((row, col): Move) -> (usize, usize, usize) {
+    (col / SIZE, row, col % SIZE)
+}
+
+fn index(mv: Move) -> usize {
+    let (layer, row, col) = split(mv);
+    (layer * SIZE + row) * SIZE + col
+}
+
+fn cell_at(index: usize) -> Move {
+    let (layer, row, col) = (index / (SIZE * SIZE), index / SIZE % SIZE, index % SIZE);
+    (row, layer * SIZE + col)
+}
+
+fn side_index(side: CellState) -> usize {
+    match side {
+        CellState::O => 1,
+        _ => 0,
+    }
+}
+
+/// The pieces in the cube, with a running count of each side's pieces on
+/// every line so wins and threats don't need a scan of the whole cube.
+#[derive(Clone, Debug)]
+struct Cube {
+    cells: [CellState; CELLS],
+    counts: [[u8; 2]; LINE_COUNT],
+    filled: usize,
+}
+
+impl Cube {
+    fn new() -> Self {
+        Self {
+            cells: [CellState::Empty; CELLS],
+            counts: [[0; 2]; LINE_COUNT],
+            filled: 0,
+        }
+    }
+
+    fn lines_through(cell: usize) -> &'static [usize] {
+        &LINES_THROUGH[cell][..LINES_THROUGH_COUNT[cell]]
+    }
+
+    fn place(&mut self, cell: usize, side: CellState) {
+        self.cells[cell] = side;
+        self.filled += 1;
+        for &line in Self::lines_through(cell) {
+            self.counts[line][side_index(side)] += 1;
+        }
+    }
+
+    fn remove(&mut self, cell: usize) {
+        let side = self.cells[cell];
+        self.cells[cell] = CellState::Empty;
+        self.filled -= 1;
+        for &line in Self::lines_through(cell) {
+            self.counts[line][side_index(side)] -= 1;
+        }
+    }
+
+    fn is_full(&self) -> bool {
+        self.filled == CELLS
+    }
+
+    /// `side`'s pieces and the opponent's on `line`.
+    fn count(&self, line: usize, side: CellState) -> (usize, usize) {
+        let [x, o] = self.counts[line];
+        match side {
+            CellState::O => (o as usize, x as usize),
+            _ => (x as usize, o as usize),
+        }
+    }
+
+    /// A line through `cell` that `side` has filled, if any.
+    fn line_won_through(&self, cell: usize, side: CellState) -> Option<usize> {
+        Self::lines_through(cell)
+            .iter()
+            .copied()
+            .find(|&line| self.count(line, side).0 == SIZE)
+    }
+
+    fn empty_cells(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..CELLS).filter(|&cell| self.cells[cell] == CellState::Empty)
+    }
+
+    /// The empty cell of a line `side` has three pieces on, if any.
+    fn completion(&self, line: usize, side: CellState) -> Option<usize> {
+        if self.count(line, side) != (SIZE - 1, 0) {
+            return None;
+        }
+        LINES[line]
+            .into_iter()
+            .find(|&cell| self.cells[cell] == CellState::Empty)
+    }
+
+    /// Empty cells where a `side` piece would win, without repeats.
+    fn wins(&self, side: CellState) -> Vec<usize> {
+        let mut wins = Vec::new();
+        for line in 0..LINE_COUNT {
+            if let Some(cell) = self.completion(line, side)
+                && !wins.contains(&cell)
+            {
+                wins.push(cell);
+            }
+        }
+        wins
+    }
+
+    /// Empty cells a `side` piece on `cell` leaves it one move from winning
+    /// on, which are the only new ones it can make.
+    fn wins_through(&self, cell: usize, side: CellState) -> Vec<usize> {
+        let mut wins = Vec::new();
+        for &line in Self::lines_through(cell) {
+            if let Some(win) = self.completion(line, side)
+                && !wins.contains(&win)
+            {
+                wins.push(win);
+            }
+        }
+        wins
+    }
+
+    /// How much a `side` piece on the empty `cell` adds to the lines through
+    /// it.
+    fn gain(&self, cell: usize, side: CellState) -> f32 {
+        Self::lines_through(cell)
+            .iter()
+            .map(|&line| match self.count(line, side) {
+                (mine, 0) if mine < SIZE - 1 => LINE_WEIGHTS[mine + 1] - LINE_WEIGHTS[mine],
+                _ => 0.0,
+            })
+            .sum()
+    }
+
+    /// Empty cells scored by what a `side` piece would make there plus what
+    /// it would spoil for the opponent, best first.
+    fn ranked(&self, side: CellState) -> Vec<(usize, f32)> {
+        let mut scored: Vec<(usize, f32)> = self
+            .empty_cells()
+            .map(|cell| {
+                let score =
+                    self.gain(cell, side) + DEFENSE_WEIGHT * self.gain(cell, side.opponent());
+                (cell, score)
+            })
+            .collect();
+        scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+        scored
+    }
+
+    /// Empty cells where a `side` piece would make two threes at once,
+    /// which only one block can answer.
+    fn forks(&self, side: CellState) -> usize {
+        self.empty_cells()
+            .filter(|&cell| {
+                Self::lines_through(cell)
+                    .iter()
+                    .filter(|&&line| self.count(line, side) == (SIZE - 2, 0))
+                    .count()
+                    > 1
+            })
+            .count()
+    }
+
+    /// Static score for `side`: its open lines minus the opponent's, less
+    /// the opponent's forks.
+    fn evaluate(&self, side: CellState) -> f32 {
+        let lines: f32 = (0..LINE_COUNT)
+            .map(|line| match self.count(line, side) {
+                (mine, 0) if mine < SIZE => LINE_WEIGHTS[mine],
+                (0, theirs) if theirs < SIZE => -LINE_WEIGHTS[theirs],
+                _ => 0.0,
+            })
+            .sum();
+        lines - FORK_WEIGHT * self.forks(side.opponent()) as f32
+    }
+}
+
+/// A game of Qubic in progress. X moves first.
+#[derive(Clone, Debug)]
+pub struct Qubic {
+    cube: Cube,
+    turn: CellState,
+    moves: Vec<Move>,
+    outcome: Option<Outcome>,
+}
+
+impl Default for Qubic {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Qubic {
+    pub fn new() -> Self {
+        Self {
+            cube: Cube::new(),
+            turn: CellState::X,
+            moves: Vec::new(),
+            outcome: None,
+        }
+    }
+
+    /// The piece on `mv`, or `Empty` off the cube.
+    pub fn get(&self, (row, col): Move) -> CellState {
+        if row >= SIZE || col >= SIZE * SIZE {
+            return CellState::Empty;
+        }
+        self.cube.cells[index((row, col))]
+    }
+
+    /// The side to move. Once the game is over this stays on the last mover.
+    pub fn turn(&self) -> CellState {
+        self.turn
+    }
+
+    /// The cells played so far, in order.
+    pub fn moves(&self) -> &[Move] {
+        &self.moves
+    }
+
+    pub fn outcome(&self) -> Option<&Outcome> {
+        self.outcome.as_ref()
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.outcome.is_some()
+    }
+
+    pub fn winner(&self) -> Option<CellState> {
+        match &self.outcome {
+            Some(Outcome::Win(winner, _)) => Some(*winner),
+            _ => None,
+        }
+    }
+
+    pub fn legal_moves(&self) -> Vec<Move> {
+        if self.is_over() {
+            return Vec::new();
+        }
+        self.cube.empty_cells().map(cell_at).collect()
+    }
+
+    /// Places a piece for the side to move.
+    pub fn play(&mut self, (row, col): Move) -> Result<(), IllegalMove> {
+        if self.is_over() {
+            return Err(IllegalMove::GameOver);
+        }
+        if row >= SIZE || col >= SIZE * SIZE {
+            return Err(IllegalMove::OffBoard);
+        }
+        let cell = index((row, col));
+        if self.cube.cells[cell] != CellState::Empty {
+            return Err(IllegalMove::Occupied);
+        }
+
+        let side = self.turn;
+        self.cube.place(cell, side);
+        self.moves.push((row, col));
+        if let Some(line) = self.cube.line_won_through(cell, side) {
+            let line = LINES[line].into_iter().map(cell_at).collect();
+            self.outcome = Some(Outcome::Win(side, line));
+        } else if self.cube.is_full() {
+            self.outcome = Some(Outcome::Draw);
+        } else {
+            self.turn = side.opponent();
+        }
+        Ok(())
+    }
+
+    /// Takes back the last move, returning it.
+    pub fn undo(&mut self) -> Option<Move> {
+        let mv = self.moves.pop()?;
+        let cell = index(mv);
+        let side = self.cube.cells[cell];
+        self.cube.remove(cell);
+        self.turn = side;
+        self.outcome = None;
+        Some(mv)
+    }
+}
+
+/// Picks a cell for the side to move. Easy plays anywhere. Medium wins or
+/// blocks when it must and otherwise looks one move ahead. Hard looks for a
+/// win by a chain of threes and then searches a few moves ahead; Perfect
+/// chains further and searches as deep as it can in `think_time`.
+pub fn find_best_move(
+    game: &Qubic,
+    difficulty: Difficulty,
+    think_time: Duration,
+    rng: &mut Rng,
+) -> Option<Move> {
+    let moves = game.legal_moves();
+    if moves.is_empty() {
+        return None;
+    }
+    if difficulty == Difficulty::Easy {
+        return Some(moves[rng.gen_index(moves.len())]);
+    }
+
+    let side = game.turn();
+    let cube = &game.cube;
+    if let Some(&cell) = cube.wins(side).first() {
+        return Some(cell_at(cell));
+    }
+    let threats = cube.wins(side.opponent());
+    if let Some(&cell) = threats.first() {
+        return Some(cell_at(cell));
+    }
+
+    // The threat search gets half the time and the full search the rest
+    let start = Instant::now();
+    let (threat_depth, max_depth) = match difficulty {
+        Difficulty::Easy | Difficulty::Medium => (0, MEDIUM_DEPTH),
+        Difficulty::Hard => (HARD_THREAT_DEPTH, HARD_DEPTH),
+        Difficulty::Perfect => (PERFECT_THREAT_DEPTH, usize::MAX),
+    };
+    let mut threats = Threats {
+        cube: cube.clone(),
+        clock: Clock::new(think_time / 2),
+    };
+    if let Some(cell) = threats.threat_win(side, threat_depth) {
+        return Some(cell_at(cell));
+    }
+
+    let think_time = think_time.saturating_sub(start.elapsed());
+    ai::search_best_move(&mut game.clone(), max_depth, think_time, rng)
+}
+
+impl Searchable for Qubic {
+    type Move = Move;
+
+    /// The search never plays a winning move, since it scores the position
+    /// before one instead, so the side to move can't have lost yet.
+    fn outcome(&mut self) -> Option<Ending> {
+        let side = self.turn;
+        if !self.cube.wins(side).is_empty() {
+            return Some(Ending::Win(1));
+        }
+        let threats = self.cube.wins(side.opponent());
+        if threats.len() > 1 {
+            return Some(Ending::Loss(2));
+        }
+        if self.cube.is_full() {
+            return Some(Ending::Draw);
+        }
+        // Free to move and able to fork: the opponent blocks one three and
+        // the other wins
+        if threats.is_empty() && self.cube.forks(side) > 0 {
+            return Some(Ending::Win(3));
+        }
+        None
+    }
+
+    /// A single threat from the opponent leaves one move, the block.
+    fn forced_move(&mut self) -> Option<Move> {
+        match self.cube.wins(self.turn.opponent())[..] {
+            [block] => Some(cell_at(block)),
+            _ => None,
+        }
+    }
+
+    fn legal_moves(&mut self) -> Vec<Move> {
+        self.cube
+            .ranked(self.turn)
+            .into_iter()
+            .map(|(cell, _)| cell_at(cell))
+            .collect()
+    }
+
+    fn play(&mut self, mv: Move) {
+        let _ = Qubic::play(self, mv);
+    }
+
+    fn undo(&mut self, _: Move) {
+        Qubic::undo(self);
+    }
+
+    fn evaluate(&mut self) -> f32 {
+        self.cube.evaluate(self.turn)
+    }
+}
+
+/// Threat-space search: wins forced by making three in a line again and
+/// again.
+struct Threats {
+    cube: Cube,
+    clock: Clock,
+}
+
+impl Threats {
+    /// A first move for `side` that wins by a chain of at most `depth`
+    /// threes, each of which the opponent has to block on the one cell that
+    /// completes it. The opponent mustn't have a three of their own.
+    fn threat_win(&mut self, side: CellState, depth: usize) -> Option<usize> {
+        if depth == 0 {
+            return None;
+        }
+        if self.clock.tick() {
+            return None;
+        }
+
+        let opponent = side.opponent();
+        for cell in self.three_moves(side) {
+            self.cube.place(cell, side);
+            let wins = match self.cube.wins_through(cell, side)[..] {
+                [] => false,
+                [block] => {
+                    // The forced block mustn't make a three of its own
+                    self.cube.place(block, opponent);
+                    let wins = self.cube.wins_through(block, opponent).is_empty()
+                        && self.threat_win(side, depth - 1).is_some();
+                    self.cube.remove(block);
+                    wins
+                }
+                // Two ways to win: only one can be blocked
+                _ => true,
+            };
+            self.cube.remove(cell);
+            if wins {
+                return Some(cell);
+            }
+        }
+        None
+    }
+
+    /// Empty cells where a `side` piece makes three on a line the opponent
+    /// hasn't touched, the ones making most at once first.
+    fn three_moves(&self, side: CellState) -> Vec<usize> {
+        let mut cells: Vec<(usize, usize)> = self
+            .cube
+            .empty_cells()
+            .map(|cell| {
+                let threes = Cube::lines_through(cell)
+                    .iter()
+                    .filter(|&&line| self.cube.count(line, side) == (SIZE - 2, 0))
+                    .count();
+                (cell, threes)
+            })
+            .filter(|&(_, threes)| threes > 0)
+            .collect();
+        cells.sort_by_key(|&(_, threes)| std::cmp::Reverse(threes));
+        cells.into_iter().map(|(cell, _)| cell).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn builds_each_of_the_76_lines_once() {
+        assert_eq!(LINES.len(), 76);
+        let distinct: HashSet<Vec<usize>> = LINES
+            .iter()
+            .map(|line| {
+                let mut cells = line.to_vec();
+                cells.sort();
+                cells
+            })
+            .collect();
+        assert_eq!(distinct.len(), LINES.len());
+    }
+
+    fn play(moves: &[Move]) -> Qubic {
+        let mut game = Qubic::new();
+        for &mv in moves {
+            game.play(mv).unwrap();
+        }
+        game
+    }
+
+    #[test]
+    fn addresses_layers_side_by_side() {
+        assert_eq!(split((1, 9)), (2, 1, 1));
+        assert_eq!(cell_at(index((3, 14))), (3, 14));
+        let game = play(&[(1, 9)]);
+        assert_eq!(game.get((1, 9)), CellState::X);
+        assert_eq!(game.get((4, 0)), CellState::Empty);
+        assert_eq!(game.legal_moves().len(), CELLS - 1);
+    }
+
+    #[test]
+    fn wins_down_a_pillar_and_through_the_middle() {
+        // The same cell on every layer
+        let game = play(&[(1, 1), (0, 0), (1, 5), (0, 1), (1, 9), (0, 2), (1, 13)]);
+        assert_eq!(
+            game.outcome(),
+            Some(&Outcome::Win(
+                CellState::X,
+                vec![(1, 1), (1, 5), (1, 9), (1, 13)]
+            ))
+        );
+
+        // Corner to corner, a row, a column and a layer further each step
+        let game = play(&[
+            (0, 4),
+            (0, 0),
+            (0, 8),
+            (1, 5),
+            (1, 12),
+            (2, 10),
+            (3, 3),
+            (3, 15),
+        ]);
+        assert_eq!(game.winner(), Some(CellState::O));
+    }
+
+    #[test]
+    fn rejects_bad_moves_and_undoes_a_win() {
+        let mut game = play(&[(1, 1), (0, 0), (1, 5), (0, 1), (1, 9), (0, 2)]);
+        assert_eq!(game.play((4, 0)), Err(IllegalMove::OffBoard));
+        assert_eq!(game.play((0, 16)), Err(IllegalMove::OffBoard));
+        assert_eq!(game.play((0, 0)), Err(IllegalMove::Occupied));
+
+        game.play((1, 13)).unwrap();
+        assert_eq!(game.play((2, 2)), Err(IllegalMove::GameOver));
+        assert!(game.legal_moves().is_empty());
+        assert_eq!(game.undo(), Some((1, 13)));
+        assert!(!game.is_over());
+        assert_eq!(game.turn(), CellState::X);
+    }
+
+    #[test]
+    fn the_ai_blocks_a_row_of_three() {
+        let game = play(&[(0, 0), (3, 3), (0, 1), (3, 7), (0, 2)]);
+        for difficulty in [Difficulty::Medium, Difficulty::Hard] {
+            let mv = find_best_move(&game, difficulty, Duration::from_secs(10), &mut Rng::new(1));
+            assert_eq!(mv, Some((0, 3)), "{}", difficulty.name());
+        }
+    }
+}
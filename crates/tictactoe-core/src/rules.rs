@@ -0,0 +1,179 @@
+use crate::position::{CellState, Move, Position, Topology};
+
+/// What completing a line does.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Rule {
+    /// Completing a line wins.
+    Standard,
+    /// Misère: completing a line loses, so the game is about avoiding one.
+    Misere,
+}
+
+impl Rule {
+    pub const ALL: [Rule; 2] = [Rule::Standard, Rule::Misere];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Rule::Standard => "Standard",
+            Rule::Misere => "Misere",
+        }
+    }
+
+    /// Who wins once `side` has completed a line.
+    pub fn winner(self, side: CellState) -> CellState {
+        match self {
+            Rule::Standard => side,
+            Rule::Misere => side.opponent(),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Outcome {
+    /// The winning side and the completed line. Under `Rule::Misere` the
+    /// line is the loser's.
+    Win(CellState, Vec<Move>),
+    Draw,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum IllegalMove {
+    GameOver,
+    OffBoard,
+    Occupied,
+    /// A wild move that places neither X nor O.
+    NoSymbol,
+    /// A gravity move into a column with no room left.
+    ColumnFull,
+    /// A stone the opening rules don't allow there yet.
+    Restricted,
+    /// A side choice when there's none to make, or one not on offer.
+    NoChoice,
+}
+
+impl std::fmt::Display for IllegalMove {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            IllegalMove::GameOver => "the game is already over",
+            IllegalMove::OffBoard => "the cell is off the board",
+            IllegalMove::Occupied => "the cell is already taken",
+            IllegalMove::NoSymbol => "the piece must be X or O",
+            IllegalMove::ColumnFull => "the column is already full",
+            IllegalMove::Restricted => "the opening rules don't allow a stone there",
+            IllegalMove::NoChoice => "there's no such choice to make",
+        })
+    }
+}
+
+impl std::error::Error for IllegalMove {}
+
+/// A game in progress: the position, whose turn it is and the moves so far.
+/// X always moves first.
+#[derive(Clone, Debug)]
+pub struct Game {
+    position: Position,
+    turn: CellState,
+    moves: Vec<Move>,
+    outcome: Option<Outcome>,
+}
+
+impl Game {
+    pub fn new(size: usize, win_length: usize) -> Self {
+        Self::with_rule(size, win_length, Rule::Standard)
+    }
+
+    pub fn with_rule(size: usize, win_length: usize, rule: Rule) -> Self {
+        let mut position = Position::new(size, win_length);
+        position.set_rule(rule);
+        Self {
+            position,
+            turn: CellState::X,
+            moves: Vec::new(),
+            outcome: None,
+        }
+    }
+
+    /// The same fresh game with the board's edges joined up as in
+    /// `topology`. Only meant for games nobody has moved in yet.
+    pub fn with_topology(mut self, topology: Topology) -> Self {
+        self.position.set_topology(topology);
+        self
+    }
+
+    /// Replays `moves` from the start, stopping at the first illegal one.
+    pub fn from_moves(
+        size: usize,
+        win_length: usize,
+        rule: Rule,
+        topology: Topology,
+        moves: &[Move],
+    ) -> Result<Self, IllegalMove> {
+        let mut game = Self::with_rule(size, win_length, rule).with_topology(topology);
+        for &mv in moves {
+            game.play(mv)?;
+        }
+        Ok(game)
+    }
+
+    pub fn position(&self) -> &Position {
+        &self.position
+    }
+
+    /// The side to move. Once the game is over this stays on the last mover.
+    pub fn turn(&self) -> CellState {
+        self.turn
+    }
+
+    pub fn moves(&self) -> &[Move] {
+        &self.moves
+    }
+
+    pub fn outcome(&self) -> Option<&Outcome> {
+        self.outcome.as_ref()
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.outcome.is_some()
+    }
+
+    pub fn winner(&self) -> Option<CellState> {
+        match &self.outcome {
+            Some(Outcome::Win(winner, _)) => Some(*winner),
+            _ => None,
+        }
+    }
+
+    pub fn play(&mut self, (row, col): Move) -> Result<(), IllegalMove> {
+        if self.is_over() {
+            return Err(IllegalMove::GameOver);
+        }
+        if !self.position.contains(row, col) {
+            return Err(IllegalMove::OffBoard);
+        }
+        if self.position.get(row, col) != CellState::Empty {
+            return Err(IllegalMove::Occupied);
+        }
+
+        self.position.set(row, col, self.turn);
+        self.moves.push((row, col));
+
+        if let Some((side, line)) = self.position.winner() {
+            let winner = self.position.rule().winner(side);
+            self.outcome = Some(Outcome::Win(winner, line));
+        } else if self.position.is_full() {
+            self.outcome = Some(Outcome::Draw);
+        } else {
+            self.turn = self.turn.opponent();
+        }
+        Ok(())
+    }
+
+    /// Takes back the last move, if there is one.
+    pub fn undo(&mut self) -> Option<Move> {
+        let (row, col) = self.moves.pop()?;
+        self.turn = self.position.get(row, col);
+        self.position.set(row, col, CellState::Empty);
+        self.outcome = None;
+        Some((row, col))
+    }
+}
@@ -0,0 +1,426 @@
+//! Three-piece tic-tac-toe: nobody ever has more than three pieces on the
+//! board. Placing a fourth removes that side's oldest piece first, so the
+//! board never fills up and the game goes on until someone makes a line.
+//!
+//! To keep it finite, the third time the same position comes up with the
+//! same side to move is a draw, and so is reaching `MOVE_LIMIT` moves.
+
+use crate::ai::{self, Difficulty, Ending, Searchable};
+use crate::position::{CellState, Move, Position};
+use crate::rng::Rng;
+use crate::rules::{IllegalMove, Outcome};
+use std::time::Duration;
+
+/// Cells per side of the board, which is also the line length.
+pub const SIZE: usize = 3;
+/// Pieces each side may have on the board at once.
+pub const PIECES: usize = 3;
+/// Moves (by both sides together) after which the game is drawn.
+pub const MOVE_LIMIT: usize = 100;
+/// How many times a position may come up before it's a draw.
+pub const REPETITIONS: usize = 3;
+
+// Medium sees a move and the reply; Hard two of each
+const MEDIUM_DEPTH: usize = 2;
+const HARD_DEPTH: usize = 4;
+
+// Each side's pieces oldest first, then the side to move; what repetitions
+// compare, since the order decides which piece goes next
+type Key = ([Option<Move>; PIECES], [Option<Move>; PIECES], CellState);
+
+#[derive(Clone, Copy, Debug)]
+struct Played {
+    mv: Move,
+    removed: Option<Move>, // the mover's oldest piece, if it had to go
+}
+
+/// A game of three-piece tic-tac-toe in progress. X moves first.
+#[derive(Clone, Debug)]
+pub struct ThreePiece {
+    position: Position,
+    turn: CellState,
+    pieces: [Vec<Move>; 2], // per side, oldest first
+    history: Vec<Played>,
+    keys: Vec<Key>, // the position before each move, then the current one
+    outcome: Option<Outcome>,
+}
+
+impl Default for ThreePiece {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ThreePiece {
+    pub fn new() -> Self {
+        let mut game = Self {
+            position: Position::new(SIZE, SIZE),
+            turn: CellState::X,
+            pieces: [Vec::new(), Vec::new()],
+            history: Vec::new(),
+            keys: Vec::new(),
+            outcome: None,
+        };
+        game.keys.push(game.key());
+        game
+    }
+
+    pub fn position(&self) -> &Position {
+        &self.position
+    }
+
+    /// The side to move. Once the game is over this stays on the last mover.
+    pub fn turn(&self) -> CellState {
+        self.turn
+    }
+
+    pub fn moves(&self) -> Vec<Move> {
+        self.history.iter().map(|played| played.mv).collect()
+    }
+
+    pub fn outcome(&self) -> Option<&Outcome> {
+        self.outcome.as_ref()
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.outcome.is_some()
+    }
+
+    pub fn winner(&self) -> Option<CellState> {
+        match &self.outcome {
+            Some(Outcome::Win(winner, _)) => Some(*winner),
+            _ => None,
+        }
+    }
+
+    /// `side`'s pieces, oldest first.
+    pub fn pieces(&self, side: CellState) -> &[Move] {
+        &self.pieces[side_index(side)]
+    }
+
+    /// The piece the side to move loses with its next move, if it already
+    /// has all of its pieces out.
+    pub fn next_to_vanish(&self) -> Option<Move> {
+        let pieces = self.pieces(self.turn);
+        if pieces.len() == PIECES && !self.is_over() {
+            pieces.first().copied()
+        } else {
+            None
+        }
+    }
+
+    /// How many times the current position has come up so far.
+    pub fn repetitions(&self) -> usize {
+        let current = self.key();
+        self.keys.iter().filter(|&&key| key == current).count()
+    }
+
+    pub fn legal_moves(&self) -> Vec<Move> {
+        if self.is_over() {
+            return Vec::new();
+        }
+        self.position.empty_cells()
+    }
+
+    /// Places a piece for the side to move, removing its oldest one first if
+    /// it already has three. The cell has to be empty before that removal,
+    /// so a piece can't be put straight back where it vanished from.
+    pub fn play(&mut self, (row, col): Move) -> Result<(), IllegalMove> {
+        if self.is_over() {
+            return Err(IllegalMove::GameOver);
+        }
+        if !self.position.contains(row, col) {
+            return Err(IllegalMove::OffBoard);
+        }
+        if self.position.get(row, col) != CellState::Empty {
+            return Err(IllegalMove::Occupied);
+        }
+
+        let side = self.turn;
+        let pieces = &mut self.pieces[side_index(side)];
+        let removed = if pieces.len() == PIECES {
+            let (old_row, old_col) = pieces.remove(0);
+            self.position.set(old_row, old_col, CellState::Empty);
+            Some((old_row, old_col))
+        } else {
+            None
+        };
+        pieces.push((row, col));
+        self.position.set(row, col, side);
+        self.history.push(Played {
+            mv: (row, col),
+            removed,
+        });
+
+        if self.position.completes_line(row, col) {
+            let (_, line) = self
+                .position
+                .winner()
+                .expect("a completed line is a winner");
+            self.outcome = Some(Outcome::Win(side, line));
+        } else {
+            self.turn = side.opponent();
+        }
+        self.keys.push(self.key());
+
+        if self.outcome.is_none()
+            && (self.history.len() >= MOVE_LIMIT || self.repetitions() >= REPETITIONS)
+        {
+            self.outcome = Some(Outcome::Draw);
+        }
+        Ok(())
+    }
+
+    /// Takes back the last move, putting back any piece it removed.
+    pub fn undo(&mut self) -> Option<Move> {
+        let played = self.history.pop()?;
+        self.keys.pop();
+        let (row, col) = played.mv;
+        let side = self.position.get(row, col);
+
+        let pieces = &mut self.pieces[side_index(side)];
+        pieces.pop();
+        self.position.set(row, col, CellState::Empty);
+        if let Some((old_row, old_col)) = played.removed {
+            pieces.insert(0, (old_row, old_col));
+            self.position.set(old_row, old_col, side);
+        }
+
+        self.turn = side;
+        self.outcome = None;
+        Some(played.mv)
+    }
+
+    fn key(&self) -> Key {
+        let slots = |pieces: &[Move]| {
+            let mut slots = [None; PIECES];
+            for (slot, &piece) in slots.iter_mut().zip(pieces) {
+                *slot = Some(piece);
+            }
+            slots
+        };
+        (slots(&self.pieces[0]), slots(&self.pieces[1]), self.turn)
+    }
+}
+
+fn side_index(side: CellState) -> usize {
+    if side == CellState::O { 1 } else { 0 }
+}
+
+/// Picks a move for the side to move. Easy plays at random, Medium and Hard
+/// search a fixed number of moves ahead, and Perfect searches as deep as it
+/// can in `think_time`.
+pub fn find_best_move(
+    game: &ThreePiece,
+    difficulty: Difficulty,
+    think_time: Duration,
+    rng: &mut Rng,
+) -> Option<Move> {
+    let max_depth = match difficulty {
+        Difficulty::Easy => {
+            let moves = game.legal_moves();
+            return (!moves.is_empty()).then(|| moves[rng.gen_index(moves.len())]);
+        }
+        Difficulty::Medium => MEDIUM_DEPTH,
+        Difficulty::Hard => HARD_DEPTH,
+        Difficulty::Perfect => usize::MAX,
+    };
+    ai::search_best_move(&mut game.clone(), max_depth, think_time, rng)
+}
+
+impl Searchable for ThreePiece {
+    type Move = Move;
+
+    /// A position seen before on the way here counts as a draw: if repeating
+    /// it was good for either side, it would just repeat it until the rule
+    /// kicks in.
+    fn outcome(&mut self) -> Option<Ending> {
+        match self.outcome {
+            // Only the side that just moved can have won
+            Some(Outcome::Win(..)) => Some(Ending::Loss(0)),
+            Some(Outcome::Draw) => Some(Ending::Draw),
+            None if self.repetitions() > 1 => Some(Ending::Draw),
+            None => None,
+        }
+    }
+
+    /// Winning moves first, then the center, corners and edges.
+    fn legal_moves(&mut self) -> Vec<Move> {
+        let mut moves = ThreePiece::legal_moves(self);
+        let weight = |(row, col): Move| match (row % 2, col % 2) {
+            (1, 1) => 0,
+            (0, 0) => 1,
+            _ => 2,
+        };
+        moves.sort_by_key(|&mv| weight(mv));
+
+        let mut wins = Vec::new();
+        moves.retain(|&mv| {
+            let _ = ThreePiece::play(self, mv);
+            let won = self.winner().is_some();
+            ThreePiece::undo(self);
+            if won {
+                wins.push(mv);
+            }
+            !won
+        });
+        wins.extend(moves);
+        wins
+    }
+
+    fn play(&mut self, mv: Move) {
+        let _ = ThreePiece::play(self, mv);
+    }
+
+    fn undo(&mut self, _: Move) {
+        ThreePiece::undo(self);
+    }
+
+    fn evaluate(&mut self) -> f32 {
+        evaluate(self)
+    }
+}
+
+/// Static score for the side to move: lines it could finish right away, and
+/// lines the opponent could finish next, not counting the pieces each side
+/// is about to lose.
+fn evaluate(game: &ThreePiece) -> f32 {
+    let side = game.turn;
+    let staying = |owner: CellState| -> Vec<Move> {
+        let pieces = game.pieces(owner);
+        let skip = usize::from(pieces.len() == PIECES);
+        pieces[skip..].to_vec()
+    };
+    let mine = staying(side);
+    let theirs = staying(side.opponent());
+    let position = &game.position;
+
+    let mut score = 0.0;
+    for (start, dir) in position.windows() {
+        let cells: Vec<Move> = position.window_cells(start, dir).collect();
+        let count = |pieces: &[Move]| cells.iter().filter(|cell| pieces.contains(cell)).count();
+        let (own, other) = (count(&mine), count(&theirs));
+        let open = cells
+            .iter()
+            .filter(|&&(row, col)| position.get(row, col) == CellState::Empty)
+            .count();
+
+        if own == PIECES - 1 && other == 0 && open >= 1 {
+            // Ours to finish on this very move
+            score += 100.0;
+        } else if other == PIECES - 1 && own == 0 && open >= 1 {
+            score -= 20.0;
+        } else if other == 0 {
+            score += own as f32;
+        } else if own == 0 {
+            score -= other as f32;
+        }
+    }
+    score
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Four cells for each side with no line among any three of them
+    const X_CELLS: [Move; 4] = [(0, 0), (0, 1), (1, 0), (1, 1)];
+    const O_CELLS: [Move; 4] = [(0, 2), (1, 2), (2, 0), (2, 1)];
+
+    /// Both sides cycling through their four cells, `plies` moves in all.
+    fn cycle(plies: usize) -> ThreePiece {
+        let mut game = ThreePiece::new();
+        for ply in 0..plies {
+            let cells = if ply % 2 == 0 { X_CELLS } else { O_CELLS };
+            game.play(cells[ply / 2 % 4]).unwrap();
+        }
+        game
+    }
+
+    #[test]
+    fn the_oldest_piece_vanishes() {
+        let mut game = cycle(6);
+        assert_eq!(game.next_to_vanish(), Some((0, 0)));
+        // The oldest piece is still there while choosing where to go
+        assert_eq!(game.play((0, 0)), Err(IllegalMove::Occupied));
+
+        game.play((1, 1)).unwrap();
+        assert_eq!(game.position().get(0, 0), CellState::Empty);
+        assert_eq!(game.pieces(CellState::X), [(0, 1), (1, 0), (1, 1)]);
+        assert_eq!(game.next_to_vanish(), Some((0, 2)));
+
+        assert_eq!(game.undo(), Some((1, 1)));
+        assert_eq!(game.position().get(0, 0), CellState::X);
+        assert_eq!(game.pieces(CellState::X), [(0, 0), (0, 1), (1, 0)]);
+    }
+
+    #[test]
+    fn only_the_pieces_left_make_a_line() {
+        let mut game = ThreePiece::new();
+        for mv in [(0, 0), (0, 1), (1, 1), (1, 0), (2, 1), (0, 2)] {
+            game.play(mv).unwrap();
+        }
+        // (2, 2) would finish the diagonal, but (0, 0) goes as it's placed
+        game.play((2, 2)).unwrap();
+        assert!(!game.is_over());
+        assert_eq!(game.position().get(0, 0), CellState::Empty);
+
+        let mut game = ThreePiece::new();
+        for mv in [(2, 0), (0, 0), (0, 1), (0, 2), (1, 1), (1, 2)] {
+            game.play(mv).unwrap();
+        }
+        // (2, 0) goes, and the two newer pieces make the middle column
+        game.play((2, 1)).unwrap();
+        assert_eq!(game.winner(), Some(CellState::X));
+        assert_eq!(game.position().get(2, 0), CellState::Empty);
+    }
+
+    #[test]
+    fn the_third_repetition_is_a_draw() {
+        // The pieces come back to the same places in the same order every
+        // eight moves
+        assert_eq!(cycle(6).repetitions(), 1);
+        assert_eq!(cycle(14).repetitions(), 2);
+        let mut game = cycle(21);
+        assert!(!game.is_over());
+        game.play(O_CELLS[2]).unwrap();
+        assert_eq!(game.repetitions(), REPETITIONS);
+        assert_eq!(game.outcome(), Some(&Outcome::Draw));
+
+        game.undo();
+        assert!(!game.is_over());
+    }
+
+    #[test]
+    fn the_move_limit_is_a_draw() {
+        // Always the first move that doesn't end the game, so nothing repeats
+        // three times and nobody wins before the limit
+        let mut game = ThreePiece::new();
+        while game.moves().len() + 1 < MOVE_LIMIT {
+            let mv = game
+                .legal_moves()
+                .into_iter()
+                .find(|&mv| {
+                    game.play(mv).unwrap();
+                    let over = game.is_over();
+                    game.undo();
+                    !over
+                })
+                .expect("a move that keeps the game going");
+            game.play(mv).unwrap();
+        }
+        let last = game
+            .legal_moves()
+            .into_iter()
+            .find(|&mv| {
+                game.play(mv).unwrap();
+                let won = game.winner().is_some();
+                game.undo();
+                !won
+            })
+            .unwrap();
+        game.play(last).unwrap();
+        assert_eq!(game.moves().len(), MOVE_LIMIT);
+        assert_eq!(game.outcome(), Some(&Outcome::Draw));
+    }
+}
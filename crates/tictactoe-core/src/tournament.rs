@@ -0,0 +1,342 @@
+//! Headless matches between AI configurations, for comparing engines and
+//! settings without a window. Games are played through `Game` and
+//! `ai::find_best_move`, the same rules and AI the frontends use.
+
+use crate::ai::{self, Difficulty, Engine};
+use crate::position::CellState;
+use crate::rng::Rng;
+use crate::rules::Game;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+// Rating the field is centered on
+const MEAN_ELO: f64 = 1500.0;
+
+// Every pairing starts with one imaginary draw, so a bot that won or lost
+// every game still gets a finite rating
+const PRIOR_DRAWS: f64 = 1.0;
+
+const ELO_ITERATIONS: usize = 1000;
+
+/// One AI configuration taking part.
+#[derive(Clone, Debug)]
+pub struct Bot {
+    pub name: String,
+    pub difficulty: Difficulty,
+    pub engine: Engine,
+    pub think_time: Duration,
+}
+
+/// Who plays whom.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Format {
+    /// Everyone plays everyone else.
+    RoundRobin,
+    /// The first bot plays each of the others, who don't play each other.
+    Gauntlet,
+}
+
+#[derive(Clone, Debug)]
+pub struct Tournament {
+    pub bots: Vec<Bot>,
+    pub format: Format,
+    pub size: usize,
+    pub win_length: usize,
+    /// Games per pairing. Sides alternate, so an even number is fairest.
+    pub games: u32,
+    pub threads: usize,
+    pub seed: u64,
+}
+
+/// Wins, draws and losses of one bot against another.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct Record {
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+impl Record {
+    pub fn games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+
+    /// Points out of `games()`, a draw counting half.
+    pub fn score(&self) -> f64 {
+        self.wins as f64 + self.draws as f64 / 2.0
+    }
+
+    fn add(&mut self, other: Record) {
+        self.wins += other.wins;
+        self.draws += other.draws;
+        self.losses += other.losses;
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Results {
+    pub bots: Vec<Bot>,
+    /// `matrix[i][j]` is bot `i`'s record against bot `j`.
+    pub matrix: Vec<Vec<Record>>,
+}
+
+impl Results {
+    /// Bot `i`'s record against everyone.
+    pub fn total(&self, i: usize) -> Record {
+        let mut total = Record::default();
+        for &record in &self.matrix[i] {
+            total.add(record);
+        }
+        total
+    }
+
+    /// Elo estimates, in bot order, fitted to every game at once rather than
+    /// updated game by game, so the order games finish in doesn't matter.
+    /// The field averages 1500.
+    pub fn elo(&self) -> Vec<f64> {
+        let count = self.bots.len();
+        let played = |i: usize, j: usize| self.matrix[i][j].games() > 0;
+
+        // Bradley-Terry strengths by minorization-maximization: each bot's
+        // strength is its score divided by how many points it "should" have
+        // per unit of strength against its opponents
+        let mut strength = vec![1.0; count];
+        for _ in 0..ELO_ITERATIONS {
+            let mut next = strength.clone();
+            for i in 0..count {
+                let mut score = 0.0;
+                let mut expected = 0.0;
+                for j in (0..count).filter(|&j| j != i && played(i, j)) {
+                    let games = self.matrix[i][j].games() as f64 + PRIOR_DRAWS;
+                    score += self.matrix[i][j].score() + PRIOR_DRAWS / 2.0;
+                    expected += games / (strength[i] + strength[j]);
+                }
+                if expected > 0.0 {
+                    next[i] = score / expected;
+                }
+            }
+
+            // Only differences matter; keep the geometric mean at 1
+            let log_mean = next.iter().map(|s: &f64| s.ln()).sum::<f64>() / count as f64;
+            for s in &mut next {
+                *s /= log_mean.exp();
+            }
+            strength = next;
+        }
+
+        strength
+            .iter()
+            .map(|s| MEAN_ELO + 400.0 * s.log10())
+            .collect()
+    }
+}
+
+/// The pairs of bot indices that meet.
+pub fn pairings(bot_count: usize, format: Format) -> Vec<(usize, usize)> {
+    match format {
+        Format::RoundRobin => (0..bot_count)
+            .flat_map(|i| (i + 1..bot_count).map(move |j| (i, j)))
+            .collect(),
+        Format::Gauntlet => (1..bot_count).map(|j| (0, j)).collect(),
+    }
+}
+
+/// Plays one game to the end. Returns the winner, or `None` for a draw.
+pub fn play_game(
+    size: usize,
+    win_length: usize,
+    x: &Bot,
+    o: &Bot,
+    rng: &mut Rng,
+) -> Option<CellState> {
+    let mut game = Game::new(size, win_length);
+    while !game.is_over() {
+        let bot = if game.turn() == CellState::X { x } else { o };
+        let mv = ai::find_best_move(
+            game.position(),
+            game.turn(),
+            bot.difficulty,
+            bot.engine,
+            bot.think_time,
+            rng,
+        )?;
+        // The AI only ever picks empty cells
+        game.play(mv).ok()?;
+    }
+    game.winner()
+}
+
+/// Plays every game of the tournament across `threads` worker threads.
+/// `progress` is called with the number of games finished so far.
+pub fn run(tournament: &Tournament, progress: impl Fn(usize, usize) + Sync) -> Results {
+    let count = tournament.bots.len();
+    // Game `g` of a pairing has the first bot as X when `g` is even
+    let jobs: Vec<(usize, usize, u32)> = pairings(count, tournament.format)
+        .into_iter()
+        .flat_map(|(a, b)| (0..tournament.games).map(move |g| (a, b, g)))
+        .collect();
+
+    let matrix = Mutex::new(vec![vec![Record::default(); count]; count]);
+    let next_job = AtomicUsize::new(0);
+    let finished = AtomicUsize::new(0);
+
+    std::thread::scope(|scope| {
+        for _ in 0..tournament.threads.max(1) {
+            scope.spawn(|| {
+                loop {
+                    let index = next_job.fetch_add(1, Ordering::Relaxed);
+                    let Some(&(a, b, g)) = jobs.get(index) else {
+                        break;
+                    };
+                    let (x, o) = if g % 2 == 0 { (a, b) } else { (b, a) };
+
+                    // Seeded per game, so results don't depend on which
+                    // thread picked the game up
+                    let mut rng = Rng::new(tournament.seed.wrapping_add(index as u64));
+                    let winner = play_game(
+                        tournament.size,
+                        tournament.win_length,
+                        &tournament.bots[x],
+                        &tournament.bots[o],
+                        &mut rng,
+                    );
+
+                    let mut matrix = matrix.lock().unwrap();
+                    match winner {
+                        Some(CellState::X) => {
+                            matrix[x][o].wins += 1;
+                            matrix[o][x].losses += 1;
+                        }
+                        Some(_) => {
+                            matrix[o][x].wins += 1;
+                            matrix[x][o].losses += 1;
+                        }
+                        None => {
+                            matrix[x][o].draws += 1;
+                            matrix[o][x].draws += 1;
+                        }
+                    }
+                    drop(matrix);
+
+                    let done = finished.fetch_add(1, Ordering::Relaxed) + 1;
+                    progress(done, jobs.len());
+                }
+            });
+        }
+    });
+
+    Results {
+        bots: tournament.bots.clone(),
+        matrix: matrix.into_inner().unwrap(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bot(difficulty: Difficulty) -> Bot {
+        Bot {
+            name: difficulty.name().to_string(),
+            difficulty,
+            engine: Engine::Minimax,
+            // Far more than 3x3 needs, so no search is cut short
+            think_time: Duration::from_secs(10),
+        }
+    }
+
+    fn record(wins: u32, draws: u32, losses: u32) -> Record {
+        Record {
+            wins,
+            draws,
+            losses,
+        }
+    }
+
+    fn results(matrix: Vec<Vec<Record>>) -> Results {
+        Results {
+            bots: vec![bot(Difficulty::Easy); matrix.len()],
+            matrix,
+        }
+    }
+
+    #[test]
+    fn pairs_everyone_or_just_the_first_bot() {
+        assert_eq!(
+            pairings(4, Format::RoundRobin),
+            [(0, 1), (0, 2), (0, 3), (1, 2), (1, 3), (2, 3)]
+        );
+        assert_eq!(pairings(4, Format::Gauntlet), [(0, 1), (0, 2), (0, 3)]);
+        assert!(pairings(1, Format::RoundRobin).is_empty());
+    }
+
+    #[test]
+    fn fits_elo_to_the_scores() {
+        // Two wins plus the prior draw is 2.5 points to 0.5, odds of 5 to 1
+        let elo = results(vec![
+            vec![Record::default(), record(2, 0, 0)],
+            vec![record(0, 0, 2), Record::default()],
+        ])
+        .elo();
+        let gap = 400.0 * 5f64.log10();
+        assert!((elo[0] - (MEAN_ELO + gap / 2.0)).abs() < 0.01, "{elo:?}");
+        assert!((elo[1] - (MEAN_ELO - gap / 2.0)).abs() < 0.01, "{elo:?}");
+
+        // Even results rate everyone the same
+        let even = record(1, 2, 1);
+        let elo = results(vec![
+            vec![Record::default(), even],
+            vec![even, Record::default()],
+        ])
+        .elo();
+        assert!(elo.iter().all(|&e| (e - MEAN_ELO).abs() < 0.01), "{elo:?}");
+    }
+
+    #[test]
+    fn rates_a_gauntlet_by_who_beat_whom() {
+        // The first bot beats the second and loses to the third, who never
+        // meet each other
+        let none = Record::default();
+        let elo = results(vec![
+            vec![none, record(4, 0, 0), record(0, 0, 4)],
+            vec![record(0, 0, 4), none, none],
+            vec![record(4, 0, 0), none, none],
+        ])
+        .elo();
+        assert!(elo[2] > elo[0] && elo[0] > elo[1], "{elo:?}");
+        assert!(
+            (elo.iter().sum::<f64>() / 3.0 - MEAN_ELO).abs() < 0.5,
+            "{elo:?}"
+        );
+    }
+
+    #[test]
+    fn same_seed_same_results_on_any_thread_count() {
+        let mut tournament = Tournament {
+            bots: vec![
+                bot(Difficulty::Easy),
+                bot(Difficulty::Medium),
+                bot(Difficulty::Easy),
+            ],
+            format: Format::RoundRobin,
+            size: 3,
+            win_length: 3,
+            games: 6,
+            threads: 1,
+            seed: 42,
+        };
+        let single = run(&tournament, |_, _| {});
+        tournament.threads = 4;
+        let parallel = run(&tournament, |_, _| {});
+        assert_eq!(single.matrix, parallel.matrix);
+
+        for i in 0..3 {
+            for j in 0..3 {
+                let (ours, theirs) = (single.matrix[i][j], single.matrix[j][i]);
+                assert_eq!((ours.wins, ours.draws), (theirs.losses, theirs.draws));
+                assert_eq!(ours.games(), if i == j { 0 } else { 6 });
+            }
+        }
+    }
+}
@@ -0,0 +1,419 @@
+//! Ultimate tic-tac-toe: nine small boards inside one big one.
+//!
+//! Cells are addressed on the full 9×9 grid, so `(4, 7)` is the middle row
+//! of the middle-right small board. The cell a move lands on inside its small
+//! board picks the small board the opponent has to play in next; if that one
+//! is already won or full they may play anywhere. Winning a small board
+//! claims its spot on the big board, and three claimed boards in a row win.
+
+use crate::ai::{self, Difficulty, Ending, Searchable};
+use crate::position::{CellState, Move, Position};
+use crate::rng::Rng;
+use crate::rules::{IllegalMove, Outcome};
+use std::time::Duration;
+
+/// Small boards per side of the big board, and cells per side of each.
+pub const SUB_SIZE: usize = 3;
+/// Cells per side of the whole grid.
+pub const GRID_SIZE: usize = SUB_SIZE * SUB_SIZE;
+
+// Medium sees a move and the reply; Hard two of each
+const MEDIUM_DEPTH: usize = 2;
+const HARD_DEPTH: usize = 4;
+
+// The big board's lines, as small board indices
+const LINES: [[usize; 3]; 8] = [
+    [0, 1, 2],
+    [3, 4, 5],
+    [6, 7, 8],
+    [0, 3, 6],
+    [1, 4, 7],
+    [2, 5, 8],
+    [0, 4, 8],
+    [2, 4, 6],
+];
+
+// How much a small board is worth for where it sits: the center is in four
+// lines of the big board, corners in three, edges in two
+const BOARD_WEIGHTS: [f32; 9] = [1.2, 1.0, 1.2, 1.0, 1.5, 1.0, 1.2, 1.0, 1.2];
+
+#[derive(Clone, Copy, Debug)]
+struct Played {
+    mv: Move,
+    next_board: Option<usize>, // the restriction before this move
+    claimed: bool,             // the move won its small board
+}
+
+/// A game of ultimate tic-tac-toe in progress. X moves first.
+#[derive(Clone, Debug)]
+pub struct Ultimate {
+    boards: Vec<Position>, // small boards, row by row
+    meta: Position,        // who has claimed each small board
+    turn: CellState,
+    next_board: Option<usize>, // `None`: any open board
+    history: Vec<Played>,
+    outcome: Option<Outcome>,
+}
+
+impl Default for Ultimate {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Ultimate {
+    pub fn new() -> Self {
+        Self {
+            boards: vec![Position::new(SUB_SIZE, SUB_SIZE); SUB_SIZE * SUB_SIZE],
+            meta: Position::new(SUB_SIZE, SUB_SIZE),
+            turn: CellState::X,
+            next_board: None,
+            history: Vec::new(),
+            outcome: None,
+        }
+    }
+
+    /// Replays `moves` from the start, stopping at the first illegal one.
+    pub fn from_moves(moves: &[Move]) -> Result<Self, IllegalMove> {
+        let mut game = Self::new();
+        for &mv in moves {
+            game.play(mv)?;
+        }
+        Ok(game)
+    }
+
+    /// The small board a cell of the full grid belongs to.
+    pub fn board_of((row, col): Move) -> usize {
+        (row / SUB_SIZE) * SUB_SIZE + col / SUB_SIZE
+    }
+
+    /// The small board at `index`, in its own coordinates.
+    pub fn board(&self, index: usize) -> &Position {
+        &self.boards[index]
+    }
+
+    /// The big board: X or O where a small board has been won.
+    pub fn meta(&self) -> &Position {
+        &self.meta
+    }
+
+    pub fn get(&self, (row, col): Move) -> CellState {
+        self.boards[Self::board_of((row, col))].get(row % SUB_SIZE, col % SUB_SIZE)
+    }
+
+    pub fn turn(&self) -> CellState {
+        self.turn
+    }
+
+    pub fn moves(&self) -> Vec<Move> {
+        self.history.iter().map(|played| played.mv).collect()
+    }
+
+    pub fn outcome(&self) -> Option<&Outcome> {
+        self.outcome.as_ref()
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.outcome.is_some()
+    }
+
+    pub fn winner(&self) -> Option<CellState> {
+        match &self.outcome {
+            Some(Outcome::Win(winner, _)) => Some(*winner),
+            _ => None,
+        }
+    }
+
+    /// Won by someone or full, so nobody can play there any more.
+    pub fn is_closed(&self, board: usize) -> bool {
+        self.meta.get(board / SUB_SIZE, board % SUB_SIZE) != CellState::Empty
+            || self.boards[board].is_full()
+    }
+
+    /// Whether the side to move may play in small board `board`.
+    pub fn is_open_target(&self, board: usize) -> bool {
+        !self.is_over()
+            && !self.is_closed(board)
+            && self.next_board.is_none_or(|next| next == board)
+    }
+
+    pub fn legal_moves(&self) -> Vec<Move> {
+        let mut moves = Vec::new();
+        for board in (0..self.boards.len()).filter(|&b| self.is_open_target(b)) {
+            let (top, left) = (board / SUB_SIZE * SUB_SIZE, board % SUB_SIZE * SUB_SIZE);
+            for (row, col) in self.boards[board].empty_cells() {
+                moves.push((top + row, left + col));
+            }
+        }
+        moves
+    }
+
+    pub fn play(&mut self, (row, col): Move) -> Result<(), IllegalMove> {
+        if self.is_over() {
+            return Err(IllegalMove::GameOver);
+        }
+        if row >= GRID_SIZE || col >= GRID_SIZE {
+            return Err(IllegalMove::OffBoard);
+        }
+        let board = Self::board_of((row, col));
+        let (sub_row, sub_col) = (row % SUB_SIZE, col % SUB_SIZE);
+        if !self.is_open_target(board)
+            || self.boards[board].get(sub_row, sub_col) != CellState::Empty
+        {
+            return Err(IllegalMove::Occupied);
+        }
+
+        self.boards[board].set(sub_row, sub_col, self.turn);
+        let claimed = self.boards[board].completes_line(sub_row, sub_col);
+        if claimed {
+            self.meta.set(board / SUB_SIZE, board % SUB_SIZE, self.turn);
+        }
+        self.history.push(Played {
+            mv: (row, col),
+            next_board: self.next_board,
+            claimed,
+        });
+
+        // The cell inside the small board picks the opponent's board
+        let target = sub_row * SUB_SIZE + sub_col;
+        self.next_board = (!self.is_closed(target)).then_some(target);
+
+        if claimed && let Some((winner, line)) = self.meta.winner() {
+            self.outcome = Some(Outcome::Win(winner, line));
+        } else if (0..self.boards.len()).all(|b| self.is_closed(b)) {
+            self.outcome = Some(Outcome::Draw);
+        } else {
+            self.turn = self.turn.opponent();
+        }
+        Ok(())
+    }
+
+    /// Takes back the last move, if there is one.
+    pub fn undo(&mut self) -> Option<Move> {
+        let played = self.history.pop()?;
+        let (row, col) = played.mv;
+        let board = Self::board_of(played.mv);
+        self.turn = self.boards[board].get(row % SUB_SIZE, col % SUB_SIZE);
+        self.boards[board].set(row % SUB_SIZE, col % SUB_SIZE, CellState::Empty);
+        if played.claimed {
+            self.meta
+                .set(board / SUB_SIZE, board % SUB_SIZE, CellState::Empty);
+        }
+        self.next_board = played.next_board;
+        self.outcome = None;
+        Some(played.mv)
+    }
+}
+
+/// Picks a move for the side to move. Easy plays at random, Medium and Hard
+/// search a fixed number of moves ahead, and Perfect searches as deep as it
+/// can in `think_time` (the game is far too big to solve).
+pub fn find_best_move(
+    game: &Ultimate,
+    difficulty: Difficulty,
+    think_time: Duration,
+    rng: &mut Rng,
+) -> Option<Move> {
+    let max_depth = match difficulty {
+        Difficulty::Easy => {
+            let moves = game.legal_moves();
+            return (!moves.is_empty()).then(|| moves[rng.gen_index(moves.len())]);
+        }
+        Difficulty::Medium => MEDIUM_DEPTH,
+        Difficulty::Hard => HARD_DEPTH,
+        Difficulty::Perfect => usize::MAX,
+    };
+    ai::search_best_move(&mut game.clone(), max_depth, think_time, rng)
+}
+
+impl Searchable for Ultimate {
+    type Move = Move;
+
+    fn outcome(&mut self) -> Option<Ending> {
+        match self.outcome {
+            // Only the side that just moved can have won
+            Some(Outcome::Win(..)) => Some(Ending::Loss(0)),
+            Some(Outcome::Draw) => Some(Ending::Draw),
+            None => None,
+        }
+    }
+
+    /// Legal moves, those that win a small board first.
+    fn legal_moves(&mut self) -> Vec<Move> {
+        let side = self.turn;
+        let mut moves = Ultimate::legal_moves(self);
+        let mut claims = Vec::new();
+        moves.retain(|&(row, col)| {
+            let board = &mut self.boards[Ultimate::board_of((row, col))];
+            let (sub_row, sub_col) = (row % SUB_SIZE, col % SUB_SIZE);
+            board.set(sub_row, sub_col, side);
+            let claims_board = board.completes_line(sub_row, sub_col);
+            board.set(sub_row, sub_col, CellState::Empty);
+            if claims_board {
+                claims.push((row, col));
+            }
+            !claims_board
+        });
+        claims.extend(moves);
+        claims
+    }
+
+    fn play(&mut self, mv: Move) {
+        let _ = Ultimate::play(self, mv);
+    }
+
+    fn undo(&mut self, _: Move) {
+        Ultimate::undo(self);
+    }
+
+    fn evaluate(&mut self) -> f32 {
+        evaluate(self, self.turn)
+    }
+}
+
+/// Static score for `side`. Claimed boards count by how many big-board lines
+/// they can still help with; open boards by their own lines in progress.
+fn evaluate(game: &Ultimate, side: CellState) -> f32 {
+    let mut score = 0.0;
+
+    // Big board lines that only one side has claimed boards on
+    for line in LINES {
+        let mut mine = 0;
+        let mut theirs = 0;
+        let mut dead = false;
+        for board in line {
+            match game.meta.get(board / SUB_SIZE, board % SUB_SIZE) {
+                CellState::Empty if game.boards[board].is_full() => dead = true,
+                CellState::Empty => {}
+                owner if owner == side => mine += 1,
+                _ => theirs += 1,
+            }
+        }
+        if dead {
+            continue;
+        }
+        if theirs == 0 && mine > 0 {
+            score += (mine * mine * 40) as f32;
+        } else if mine == 0 && theirs > 0 {
+            score -= (theirs * theirs * 40) as f32;
+        }
+    }
+
+    for (index, board) in game.boards.iter().enumerate() {
+        let weight = BOARD_WEIGHTS[index];
+        match game.meta.get(index / SUB_SIZE, index % SUB_SIZE) {
+            CellState::Empty => {}
+            owner if owner == side => {
+                score += 30.0 * weight;
+                continue;
+            }
+            _ => {
+                score -= 30.0 * weight;
+                continue;
+            }
+        }
+
+        // Two in a row with the third cell free is a threat to claim it
+        for (start, dir) in board.windows() {
+            let mut mine = 0;
+            let mut theirs = 0;
+            for (row, col) in board.window_cells(start, dir) {
+                match board.get(row, col) {
+                    CellState::Empty => {}
+                    state if state == side => mine += 1,
+                    _ => theirs += 1,
+                }
+            }
+            if theirs == 0 && mine > 0 {
+                score += (mine * mine) as f32 * weight;
+            } else if mine == 0 && theirs > 0 {
+                score -= (theirs * theirs) as f32 * weight;
+            }
+        }
+    }
+
+    // Being sent anywhere is a small edge for whoever gets to choose
+    if game.next_board.is_none() {
+        score += 5.0;
+    }
+
+    score
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // X takes the top row of board 0, O answering in boards 1 and 2 so X is
+    // always sent back; the last move sends O to board 0, now closed
+    const CLAIM_TOP_LEFT: [Move; 5] = [(0, 1), (0, 3), (0, 2), (0, 6), (0, 0)];
+
+    #[test]
+    fn sent_to_a_closed_board_plays_anywhere() {
+        let mut game = Ultimate::from_moves(&CLAIM_TOP_LEFT).unwrap();
+        assert_eq!(game.meta().get(0, 0), CellState::X);
+        assert_eq!(game.turn(), CellState::O);
+
+        let moves = game.legal_moves();
+        assert!(moves.iter().all(|&mv| Ultimate::board_of(mv) != 0));
+        assert_eq!(moves.len(), 8 * SUB_SIZE * SUB_SIZE - 2);
+        assert!((1..9).all(|board| game.is_open_target(board)));
+        assert_eq!(game.play((1, 1)), Err(IllegalMove::Occupied));
+        assert!(game.play((4, 4)).is_ok());
+    }
+
+    #[test]
+    fn a_move_sends_the_opponent_to_the_matching_board() {
+        let mut game = Ultimate::new();
+        game.play((4, 5)).unwrap(); // middle-right cell of the center board
+        let moves = game.legal_moves();
+        assert!(moves.iter().all(|&mv| Ultimate::board_of(mv) == 5));
+        assert_eq!(game.play((0, 0)), Err(IllegalMove::Occupied));
+    }
+
+    #[test]
+    fn undo_restores_the_target_board_and_the_claim() {
+        let mut game = Ultimate::from_moves(&CLAIM_TOP_LEFT).unwrap();
+        assert_eq!(game.undo(), Some((0, 0)));
+        assert_eq!(game.meta().get(0, 0), CellState::Empty);
+        assert_eq!(game.get((0, 0)), CellState::Empty);
+        assert_eq!(game.turn(), CellState::X);
+        assert!(
+            game.legal_moves()
+                .iter()
+                .all(|&mv| Ultimate::board_of(mv) == 0)
+        );
+        assert_eq!(game.moves(), CLAIM_TOP_LEFT[..4]);
+
+        while game.undo().is_some() {}
+        assert_eq!(game.legal_moves().len(), GRID_SIZE * GRID_SIZE);
+    }
+
+    #[test]
+    fn closing_every_board_without_a_line_is_a_draw() {
+        // The big board and the last small board share a drawn pattern:
+        //   X O X
+        //   X O O
+        //   O X X
+        use CellState::{O, X};
+        let pattern = [[X, O, X], [X, O, O], [O, X, X]];
+        let mut game = Ultimate::new();
+        for board in 0..8 {
+            let (row, col) = (board / SUB_SIZE, board % SUB_SIZE);
+            game.meta.set(row, col, pattern[row][col]);
+        }
+        for (row, cells) in pattern.iter().enumerate() {
+            for (col, &state) in cells.iter().enumerate() {
+                if (row, col) != (2, 2) {
+                    game.boards[8].set(row, col, state);
+                }
+            }
+        }
+
+        assert_eq!(game.legal_moves(), vec![(8, 8)]);
+        game.play((8, 8)).unwrap();
+        assert_eq!(game.outcome(), Some(&Outcome::Draw));
+        assert_eq!(game.winner(), None);
+        assert!(game.legal_moves().is_empty());
+    }
+}
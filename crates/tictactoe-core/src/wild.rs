@@ -0,0 +1,339 @@
+//! Wild tic-tac-toe: on every turn the player picks whether to place an X or
+//! an O, and whoever completes a line of either symbol wins.
+//!
+//! Nobody owns a symbol, so the two sides are numbered instead: player 0
+//! moves first.
+
+use crate::ai::{self, Difficulty, Ending, PROVEN_SCORE, Searchable};
+use crate::position::{CellState, Move, Position};
+use crate::rng::Rng;
+use crate::rules::IllegalMove;
+use std::time::Duration;
+
+// Medium sees a move and the reply; Hard two of each
+const MEDIUM_DEPTH: usize = 2;
+const HARD_DEPTH: usize = 4;
+
+/// A cell plus the symbol placed on it.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct WildMove {
+    pub cell: Move,
+    pub symbol: CellState,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum WildOutcome {
+    /// The player who completed a line, and the line.
+    Win(usize, Vec<Move>),
+    Draw,
+}
+
+/// A game of wild tic-tac-toe in progress.
+#[derive(Clone, Debug)]
+pub struct Wild {
+    position: Position,
+    turn: usize,
+    moves: Vec<WildMove>,
+    outcome: Option<WildOutcome>,
+}
+
+impl Wild {
+    pub fn new(size: usize, win_length: usize) -> Self {
+        Self {
+            position: Position::new(size, win_length),
+            turn: 0,
+            moves: Vec::new(),
+            outcome: None,
+        }
+    }
+
+    pub fn position(&self) -> &Position {
+        &self.position
+    }
+
+    /// The player to move, 0 or 1. Once the game is over this stays on the
+    /// last mover.
+    pub fn turn(&self) -> usize {
+        self.turn
+    }
+
+    pub fn moves(&self) -> &[WildMove] {
+        &self.moves
+    }
+
+    pub fn outcome(&self) -> Option<&WildOutcome> {
+        self.outcome.as_ref()
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.outcome.is_some()
+    }
+
+    pub fn winner(&self) -> Option<usize> {
+        match &self.outcome {
+            Some(WildOutcome::Win(winner, _)) => Some(*winner),
+            _ => None,
+        }
+    }
+
+    /// Every empty cell, once with each symbol.
+    pub fn legal_moves(&self) -> Vec<WildMove> {
+        if self.is_over() {
+            return Vec::new();
+        }
+        self.position
+            .empty_cells()
+            .into_iter()
+            .flat_map(|cell| [CellState::X, CellState::O].map(|symbol| WildMove { cell, symbol }))
+            .collect()
+    }
+
+    pub fn play(&mut self, mv: WildMove) -> Result<(), IllegalMove> {
+        let (row, col) = mv.cell;
+        if self.is_over() {
+            return Err(IllegalMove::GameOver);
+        }
+        if !self.position.contains(row, col) {
+            return Err(IllegalMove::OffBoard);
+        }
+        if self.position.get(row, col) != CellState::Empty {
+            return Err(IllegalMove::Occupied);
+        }
+        if mv.symbol == CellState::Empty {
+            return Err(IllegalMove::NoSymbol);
+        }
+
+        self.position.set(row, col, mv.symbol);
+        self.moves.push(mv);
+
+        if self.position.completes_line(row, col) {
+            let (_, line) = self
+                .position
+                .winner()
+                .expect("a completed line is a winner");
+            self.outcome = Some(WildOutcome::Win(self.turn, line));
+        } else if self.position.is_full() {
+            self.outcome = Some(WildOutcome::Draw);
+        } else {
+            self.turn = 1 - self.turn;
+        }
+        Ok(())
+    }
+
+    /// Takes back the last move, if there is one.
+    pub fn undo(&mut self) -> Option<WildMove> {
+        let mv = self.moves.pop()?;
+        self.position.set(mv.cell.0, mv.cell.1, CellState::Empty);
+        // A finished game never switched turns after its last move
+        if self.outcome.take().is_none() {
+            self.turn = 1 - self.turn;
+        }
+        Some(mv)
+    }
+}
+
+/// Picks a move for the player to move, trying both symbols on every cell.
+/// Easy plays at random, Medium and Hard search a fixed number of moves
+/// ahead, and Perfect searches as deep as it can in `think_time`.
+pub fn find_best_move(
+    game: &Wild,
+    difficulty: Difficulty,
+    think_time: Duration,
+    rng: &mut Rng,
+) -> Option<WildMove> {
+    let max_depth = match difficulty {
+        Difficulty::Easy => {
+            let moves = game.legal_moves();
+            return (!moves.is_empty()).then(|| moves[rng.gen_index(moves.len())]);
+        }
+        Difficulty::Medium => MEDIUM_DEPTH,
+        Difficulty::Hard => HARD_DEPTH,
+        Difficulty::Perfect => usize::MAX,
+    };
+    ai::search_best_move(&mut game.clone(), max_depth, think_time, rng)
+}
+
+impl Searchable for Wild {
+    type Move = WildMove;
+
+    fn outcome(&mut self) -> Option<Ending> {
+        match self.outcome {
+            // Only the player who just moved can have won
+            Some(WildOutcome::Win(..)) => Some(Ending::Loss(0)),
+            Some(WildOutcome::Draw) => Some(Ending::Draw),
+            None => None,
+        }
+    }
+
+    /// Moves that complete a line first, moves that leave the opponent one
+    /// last, and the rest in between, central cells first within each.
+    fn legal_moves(&mut self) -> Vec<WildMove> {
+        let mut moves = Wild::legal_moves(self);
+        let center_row = (self.position.rows() as f32 - 1.0) / 2.0;
+        let center_col = (self.position.cols() as f32 - 1.0) / 2.0;
+        let distance =
+            |(row, col): Move| (row as f32 - center_row).abs() + (col as f32 - center_col).abs();
+        moves.sort_by(|a, b| distance(a.cell).total_cmp(&distance(b.cell)));
+
+        let position = &mut self.position;
+
+        let mut wins = Vec::new();
+        let mut quiet = Vec::new();
+        let mut gifts = Vec::new();
+        for mv in moves {
+            let (row, col) = mv.cell;
+            position.set(row, col, mv.symbol);
+            if position.completes_line(row, col) {
+                wins.push(mv);
+            } else if sets_up_line(position, mv.cell) {
+                gifts.push(mv);
+            } else {
+                quiet.push(mv);
+            }
+            position.set(row, col, CellState::Empty);
+        }
+
+        wins.extend(quiet);
+        wins.extend(gifts);
+        wins
+    }
+
+    fn play(&mut self, mv: WildMove) {
+        let _ = Wild::play(self, mv);
+    }
+
+    fn undo(&mut self, _: WildMove) {
+        Wild::undo(self);
+    }
+
+    fn evaluate(&mut self) -> f32 {
+        evaluate(&self.position)
+    }
+}
+
+/// Whether the piece on `cell` leaves a line one piece short of complete,
+/// which the next player can finish whoever set it up.
+fn sets_up_line(position: &Position, cell: Move) -> bool {
+    let symbol = position.get(cell.0, cell.1);
+    position.windows_through(cell).any(|(start, dir)| {
+        let mut pieces = 0;
+        let mut empty = 0;
+        for (row, col) in position.window_cells(start, dir) {
+            match position.get(row, col) {
+                CellState::Empty => empty += 1,
+                state if state == symbol => pieces += 1,
+                _ => {}
+            }
+        }
+        pieces == position.win_length() - 1 && empty == 1
+    })
+}
+
+/// Static score for the player to move. Any line one piece short is theirs
+/// to finish; otherwise there's nothing to tell the sides apart.
+fn evaluate(position: &Position) -> f32 {
+    let threat = position.windows().any(|(start, dir)| {
+        let mut counts = [0; 3]; // empty, X, O
+        for (row, col) in position.window_cells(start, dir) {
+            counts[position.get(row, col) as usize] += 1;
+        }
+        counts[0] == 1 && (counts[1] == 0 || counts[2] == 0)
+    });
+    if threat { PROVEN_SCORE / 2.0 } else { 0.0 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use CellState::{O, X};
+
+    fn play(moves: &[(Move, CellState)]) -> Wild {
+        let mut game = Wild::new(3, 3);
+        for &(cell, symbol) in moves {
+            game.play(WildMove { cell, symbol }).unwrap();
+        }
+        game
+    }
+
+    #[test]
+    fn a_line_of_either_symbol_wins() {
+        // Player 0 finishes a row of O
+        let game = play(&[
+            ((0, 0), O),
+            ((1, 0), X),
+            ((0, 1), O),
+            ((2, 2), X),
+            ((0, 2), O),
+        ]);
+        assert_eq!(game.winner(), Some(0));
+
+        // Player 1 finishes a diagonal of X that player 0 started
+        let game = play(&[((0, 0), X), ((1, 1), X), ((0, 1), O), ((2, 2), X)]);
+        assert_eq!(game.winner(), Some(1));
+        assert_eq!(
+            game.outcome(),
+            Some(&WildOutcome::Win(1, vec![(0, 0), (1, 1), (2, 2)]))
+        );
+    }
+
+    #[test]
+    fn a_mixed_line_wins_nothing() {
+        let game = play(&[((0, 0), X), ((0, 1), O), ((0, 2), X)]);
+        assert!(!game.is_over());
+        assert_eq!(game.turn(), 1);
+        assert_eq!(game.legal_moves().len(), 2 * 6);
+    }
+
+    #[test]
+    fn rejects_bad_moves_and_undoes_a_win() {
+        let mut game = play(&[((0, 0), O), ((0, 1), O)]);
+        let cell = (0, 2);
+        assert_eq!(
+            game.play(WildMove {
+                cell,
+                symbol: CellState::Empty
+            }),
+            Err(IllegalMove::NoSymbol)
+        );
+        assert_eq!(
+            game.play(WildMove {
+                cell: (0, 0),
+                symbol: X
+            }),
+            Err(IllegalMove::Occupied)
+        );
+
+        game.play(WildMove { cell, symbol: O }).unwrap();
+        assert_eq!(game.winner(), Some(0));
+        assert_eq!(
+            game.play(WildMove {
+                cell: (2, 2),
+                symbol: X
+            }),
+            Err(IllegalMove::GameOver)
+        );
+        assert_eq!(game.undo(), Some(WildMove { cell, symbol: O }));
+        assert!(!game.is_over());
+        assert_eq!(game.turn(), 0);
+    }
+
+    #[test]
+    fn the_ai_completes_a_line_of_the_other_symbol() {
+        // Two Xs in the top row: either side can finish it with an X
+        let game = play(&[((0, 0), X), ((2, 0), O), ((0, 1), X)]);
+        let mut rng = Rng::new(1);
+        let mv = find_best_move(
+            &game,
+            Difficulty::Perfect,
+            Duration::from_secs(10),
+            &mut rng,
+        );
+        assert_eq!(
+            mv,
+            Some(WildMove {
+                cell: (0, 2),
+                symbol: X
+            })
+        );
+    }
+}
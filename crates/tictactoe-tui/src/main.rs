@@ -0,0 +1,524 @@
+//! Terminal frontend for tic-tac-toe, for machines without a display.
+//!
+//! Plays the same rules and AI as the graphical game through `tictactoe-core`.
+//! Run `tictactoe-tui --help` for the options.
+
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::style::{
+    Attribute, Color, Print, ResetColor, SetAttribute, SetBackgroundColor, SetForegroundColor,
+};
+use crossterm::{cursor, execute, queue, terminal};
+use std::io::{self, Write};
+use std::time::Duration;
+use tictactoe_core::ai::{self, DEFAULT_THINK_TIME, Difficulty, Engine};
+use tictactoe_core::position::{MAX_BOARD_SIZE, MIN_BOARD_SIZE, MIN_WIN_LENGTH};
+use tictactoe_core::rng::Rng;
+use tictactoe_core::{CellState, Game, Outcome};
+
+mod palette;
+mod tournament;
+
+use palette::{PALETTES, Palette};
+
+const USAGE: &str = "\
+Usage: tictactoe-tui [options]
+       tictactoe-tui tournament [options] <bot> <bot>...   (see tournament --help)
+
+Options:
+  --ai <level>      Play against the AI: easy, medium, hard or perfect
+  --symbol <x|o>    Side you play against the AI (default x)
+  --engine <name>   AI engine: minimax (default) or mcts
+  --think <ms>      How long the AI may think per move (default 500)
+  --size <n>        Board size, 3 to 15 (default 3)
+  --win <k>         Marks in a row needed to win (default: the board size)
+  --theme <name>    modern, neon, royal or terminal
+  --help            Show this message
+
+Keys:
+  Arrows / hjkl     Move the cursor
+  Enter / Space     Place a mark
+  u                 Undo
+  r                 New game
+  t                 Next theme
+  q / Esc           Quit";
+
+struct App {
+    game: Game,
+    ai: Option<Difficulty>,
+    engine: Engine,
+    think_time: Duration,
+    player_symbol: CellState,
+    cursor: (usize, usize),
+    palette: usize,
+    rng: Rng,
+}
+
+impl App {
+    fn new(size: usize, win_length: usize) -> Self {
+        let game = Game::new(size, win_length);
+        let center = (game.position().rows() / 2, game.position().cols() / 2);
+        Self {
+            game,
+            ai: None,
+            engine: Engine::Minimax,
+            think_time: DEFAULT_THINK_TIME,
+            player_symbol: CellState::X,
+            cursor: center,
+            palette: 0,
+            rng: Rng::from_time(),
+        }
+    }
+
+    fn palette(&self) -> &'static Palette {
+        &PALETTES[self.palette]
+    }
+
+    fn is_ai_turn(&self) -> bool {
+        self.ai.is_some() && !self.game.is_over() && self.game.turn() != self.player_symbol
+    }
+
+    fn move_cursor(&mut self, d_row: isize, d_col: isize) {
+        let position = self.game.position();
+        let (last_row, last_col) = (position.rows() as isize - 1, position.cols() as isize - 1);
+        self.cursor.0 = (self.cursor.0 as isize + d_row).clamp(0, last_row) as usize;
+        self.cursor.1 = (self.cursor.1 as isize + d_col).clamp(0, last_col) as usize;
+    }
+
+    fn place(&mut self) {
+        if !self.is_ai_turn() {
+            // Occupied cells and finished games are simply ignored
+            let _ = self.game.play(self.cursor);
+        }
+    }
+
+    fn play_ai(&mut self) {
+        if let Some(difficulty) = self.ai
+            && let Some(mv) = ai::find_best_move(
+                self.game.position(),
+                self.game.turn(),
+                difficulty,
+                self.engine,
+                self.think_time,
+                &mut self.rng,
+            )
+        {
+            let _ = self.game.play(mv);
+        }
+    }
+
+    /// Takes back the last move. Against the AI this goes back to the
+    /// player's previous turn, so the AI's reply is undone as well.
+    fn undo(&mut self) {
+        self.game.undo();
+        if self.ai.is_some() && self.game.turn() != self.player_symbol {
+            self.game.undo();
+        }
+    }
+
+    /// Takes every move back, keeping the board and its rules.
+    fn restart(&mut self) {
+        while self.game.undo().is_some() {}
+    }
+}
+
+fn main() {
+    let mut args = std::env::args().skip(1).peekable();
+    if args.peek().is_some_and(|arg| arg == "tournament") {
+        args.next();
+        tournament::main(args);
+        return;
+    }
+
+    let app = match parse_args() {
+        Ok(Some(app)) => app,
+        Ok(None) => {
+            println!("{USAGE}");
+            return;
+        }
+        Err(e) => {
+            eprintln!("{e}\n\n{USAGE}");
+            std::process::exit(2);
+        }
+    };
+
+    if let Err(e) = run(app) {
+        eprintln!("Terminal error: {e}");
+        std::process::exit(1);
+    }
+}
+
+/// Builds the app from the command line. `None` means `--help` was asked for.
+fn parse_args() -> Result<Option<App>, String> {
+    let mut size = 3;
+    let mut win_length = None;
+    let mut ai = None;
+    let mut engine = Engine::Minimax;
+    let mut think_time = DEFAULT_THINK_TIME;
+    let mut player_symbol = CellState::X;
+    let mut palette = 0;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--help" || arg == "-h" {
+            return Ok(None);
+        }
+        if ![
+            "--ai", "--symbol", "--engine", "--think", "--size", "--win", "--theme",
+        ]
+        .contains(&arg.as_str())
+        {
+            return Err(format!("unknown option `{arg}`"));
+        }
+        let value = args.next().ok_or_else(|| format!("{arg} needs a value"))?;
+        match arg.as_str() {
+            "--ai" => {
+                ai = Some(
+                    Difficulty::ALL
+                        .into_iter()
+                        .find(|d| d.name().eq_ignore_ascii_case(&value))
+                        .ok_or_else(|| format!("unknown AI level `{value}`"))?,
+                )
+            }
+            "--symbol" => {
+                player_symbol = match value.to_lowercase().as_str() {
+                    "x" => CellState::X,
+                    "o" => CellState::O,
+                    _ => return Err(format!("symbol must be x or o, not `{value}`")),
+                }
+            }
+            "--engine" => {
+                engine = Engine::ALL
+                    .into_iter()
+                    .find(|e| e.name().eq_ignore_ascii_case(&value))
+                    .ok_or_else(|| format!("unknown engine `{value}`"))?
+            }
+            "--think" => think_time = Duration::from_millis(parse_number(&arg, &value)? as u64),
+            "--size" => size = parse_number(&arg, &value)?,
+            "--win" => win_length = Some(parse_number(&arg, &value)?),
+            "--theme" => {
+                palette = palette::find(&value).ok_or_else(|| format!("unknown theme `{value}`"))?
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    let size = size.clamp(MIN_BOARD_SIZE, MAX_BOARD_SIZE);
+    let win_length = win_length.unwrap_or(size).clamp(MIN_WIN_LENGTH, size);
+
+    let mut app = App::new(size, win_length);
+    app.ai = ai;
+    app.engine = engine;
+    app.think_time = think_time;
+    app.player_symbol = player_symbol;
+    app.palette = palette;
+    Ok(Some(app))
+}
+
+fn parse_number(arg: &str, value: &str) -> Result<usize, String> {
+    value
+        .parse()
+        .map_err(|_| format!("{arg} needs a number, not `{value}`"))
+}
+
+/// Puts the terminal back the way we found it, even if the game panics.
+struct TerminalGuard;
+
+impl TerminalGuard {
+    fn enter() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        execute!(io::stdout(), terminal::EnterAlternateScreen, cursor::Hide)?;
+        Ok(Self)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = execute!(
+            io::stdout(),
+            ResetColor,
+            cursor::Show,
+            terminal::LeaveAlternateScreen
+        );
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+fn run(mut app: App) -> io::Result<()> {
+    let _guard = TerminalGuard::enter()?;
+    let mut out = io::stdout();
+
+    loop {
+        let (_, height) = terminal::size()?;
+        if app.is_ai_turn() {
+            // Show the player's move while the AI thinks
+            draw(&mut out, &app, true, height)?;
+            app.play_ai();
+        }
+        draw(&mut out, &app, false, height)?;
+
+        if let Event::Key(key) = event::read()?
+            && key.kind == KeyEventKind::Press
+            && !handle_key(&mut app, key)
+        {
+            return Ok(());
+        }
+    }
+}
+
+/// Applies one key press. Returns false when the player wants to quit.
+fn handle_key(app: &mut App, key: KeyEvent) -> bool {
+    match key.code {
+        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return false,
+        KeyCode::Char('q') | KeyCode::Esc => return false,
+        KeyCode::Up | KeyCode::Char('k') => app.move_cursor(-1, 0),
+        KeyCode::Down | KeyCode::Char('j') => app.move_cursor(1, 0),
+        KeyCode::Left | KeyCode::Char('h') => app.move_cursor(0, -1),
+        KeyCode::Right | KeyCode::Char('l') => app.move_cursor(0, 1),
+        KeyCode::Enter | KeyCode::Char(' ') => app.place(),
+        KeyCode::Char('u') => app.undo(),
+        KeyCode::Char('r') => app.restart(),
+        KeyCode::Char('t') => app.palette = (app.palette + 1) % PALETTES.len(),
+        _ => {}
+    }
+    true
+}
+
+/// Redraws the whole screen for a terminal `height` lines tall.
+fn draw(out: &mut impl Write, app: &App, thinking: bool, height: u16) -> io::Result<()> {
+    let palette = app.palette();
+    let position = app.game.position();
+    let (rows, cols) = (position.rows(), position.cols());
+
+    let winning_cells = match app.game.outcome() {
+        Some(Outcome::Win(_, line)) => line.as_slice(),
+        _ => &[],
+    };
+    // Lines between rows only when the whole board still fits on screen
+    let row_lines = rows * 2 + 6 <= height as usize;
+
+    queue!(
+        out,
+        terminal::Clear(terminal::ClearType::All),
+        cursor::MoveTo(0, 0)
+    )?;
+
+    let mode = match app.ai {
+        Some(difficulty) => format!(
+            "vs AI ({}, {}), you are {}",
+            difficulty.name(),
+            app.engine.name(),
+            symbol_char(app.player_symbol)
+        ),
+        None => "Player vs Player".to_string(),
+    };
+    queue!(
+        out,
+        SetForegroundColor(palette.text),
+        SetAttribute(Attribute::Bold),
+        Print("Tic-Tac-Toe"),
+        SetAttribute(Attribute::Reset),
+        SetForegroundColor(palette.muted),
+        Print(format!(
+            "  {rows}x{cols}, {} in a row | {mode} | {}\r\n\r\n",
+            position.win_length(),
+            palette.name
+        )),
+    )?;
+
+    // Column letters match the save file notation, e.g. `b2`
+    queue!(out, SetForegroundColor(palette.muted), Print("    "))?;
+    for col in 0..cols {
+        queue!(out, Print(format!(" {} ", (b'a' + col as u8) as char)))?;
+        if col + 1 < cols {
+            queue!(out, Print(" "))?;
+        }
+    }
+    queue!(out, Print("\r\n"))?;
+
+    for row in 0..rows {
+        queue!(
+            out,
+            SetForegroundColor(palette.muted),
+            Print(format!("{:>3} ", row + 1))
+        )?;
+        for col in 0..cols {
+            let state = position.get(row, col);
+            let color = match state {
+                CellState::X => palette.primary,
+                CellState::O => palette.secondary,
+                CellState::Empty => palette.muted,
+            };
+            let glyph = match state {
+                CellState::Empty => '·',
+                _ => symbol_char(state),
+            };
+
+            if winning_cells.contains(&(row, col)) {
+                // Winning line: the winner's color as a solid block
+                queue!(
+                    out,
+                    SetBackgroundColor(color),
+                    SetForegroundColor(Color::Black),
+                    SetAttribute(Attribute::Bold),
+                )?;
+            } else {
+                queue!(out, SetForegroundColor(color))?;
+                if state != CellState::Empty {
+                    queue!(out, SetAttribute(Attribute::Bold))?;
+                }
+            }
+            if (row, col) == app.cursor && !app.game.is_over() {
+                queue!(out, SetAttribute(Attribute::Reverse))?;
+            }
+            queue!(
+                out,
+                Print(format!(" {glyph} ")),
+                SetAttribute(Attribute::Reset),
+                ResetColor
+            )?;
+
+            if col + 1 < cols {
+                queue!(out, SetForegroundColor(palette.accent), Print("│"))?;
+            }
+        }
+        queue!(out, Print("\r\n"))?;
+
+        if row_lines && row + 1 < rows {
+            let line = vec!["───"; cols].join("┼");
+            queue!(
+                out,
+                SetForegroundColor(palette.accent),
+                Print(format!("    {line}\r\n"))
+            )?;
+        }
+    }
+
+    let (status, color) = match app.game.outcome() {
+        Some(Outcome::Win(winner, _)) => {
+            let color = if *winner == CellState::X {
+                palette.primary
+            } else {
+                palette.secondary
+            };
+            (
+                format!("{} wins! Press r to play again.", symbol_char(*winner)),
+                color,
+            )
+        }
+        Some(Outcome::Draw) => (
+            "It's a draw! Press r to play again.".to_string(),
+            palette.text,
+        ),
+        None if thinking => ("AI is thinking...".to_string(), palette.muted),
+        None => (
+            format!("{} to move", symbol_char(app.game.turn())),
+            palette.text,
+        ),
+    };
+    queue!(
+        out,
+        Print("\r\n"),
+        SetForegroundColor(color),
+        Print(status),
+        Print("\r\n"),
+        SetForegroundColor(palette.muted),
+        Print("Arrows/hjkl: Move | Enter: Place | u: Undo | r: New game | t: Theme | q: Quit"),
+        ResetColor,
+    )?;
+
+    out.flush()
+}
+
+fn symbol_char(symbol: CellState) -> char {
+    match symbol {
+        CellState::X => 'X',
+        CellState::O => 'O',
+        CellState::Empty => ' ',
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(app: &mut App, code: KeyCode) -> bool {
+        handle_key(app, KeyEvent::new(code, KeyModifiers::NONE))
+    }
+
+    fn screen(app: &App, height: u16) -> String {
+        let mut out = Vec::new();
+        draw(&mut out, app, false, height).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn cursor_starts_centered_and_stays_on_the_board() {
+        let mut app = App::new(5, 4);
+        assert_eq!(app.cursor, (2, 2));
+        for _ in 0..10 {
+            press(&mut app, KeyCode::Up);
+            press(&mut app, KeyCode::Char('l'));
+        }
+        assert_eq!(app.cursor, (0, 4));
+        press(&mut app, KeyCode::Char('j'));
+        press(&mut app, KeyCode::Left);
+        assert_eq!(app.cursor, (1, 3));
+    }
+
+    #[test]
+    fn keys_place_undo_and_restart() {
+        let mut app = App::new(3, 3);
+        assert!(press(&mut app, KeyCode::Enter));
+        assert_eq!(app.game.position().get(1, 1), CellState::X);
+        // The cell is taken, so O can't play there
+        press(&mut app, KeyCode::Char(' '));
+        assert_eq!(app.game.moves().len(), 1);
+
+        press(&mut app, KeyCode::Up);
+        press(&mut app, KeyCode::Enter);
+        press(&mut app, KeyCode::Char('u'));
+        assert_eq!(app.game.moves(), [(1, 1)]);
+        press(&mut app, KeyCode::Char('r'));
+        assert!(app.game.moves().is_empty());
+
+        press(&mut app, KeyCode::Char('t'));
+        assert_eq!(app.palette, 1 % PALETTES.len());
+        assert!(!press(&mut app, KeyCode::Char('q')));
+        assert!(!press(&mut app, KeyCode::Esc));
+    }
+
+    #[test]
+    fn undo_against_the_ai_takes_back_its_reply() {
+        let mut app = App::new(3, 3);
+        app.ai = Some(Difficulty::Perfect);
+        press(&mut app, KeyCode::Enter);
+        assert!(app.is_ai_turn());
+        // Placing does nothing while it's the AI's turn
+        press(&mut app, KeyCode::Up);
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.game.moves().len(), 1);
+
+        app.play_ai();
+        assert_eq!(app.game.moves().len(), 2);
+        assert!(!app.is_ai_turn());
+        press(&mut app, KeyCode::Char('u'));
+        assert!(app.game.moves().is_empty());
+    }
+
+    #[test]
+    fn draws_the_board_and_the_status() {
+        let mut app = App::new(3, 3);
+        let tall = screen(&app, 40);
+        assert!(tall.contains("3x3, 3 in a row | Player vs Player"));
+        assert!(tall.contains(" a  ") && tall.contains(" c "));
+        assert!(tall.contains("X to move"));
+        assert!(tall.contains("┼"));
+        // A short terminal drops the lines between rows
+        assert!(!screen(&app, 10).contains("┼"));
+
+        for mv in [(0, 0), (1, 0), (0, 1), (1, 1), (0, 2)] {
+            app.game.play(mv).unwrap();
+        }
+        assert!(screen(&app, 40).contains("X wins! Press r to play again."));
+    }
+}
@@ -0,0 +1,57 @@
+use crossterm::style::Color;
+
+/// The game's themes reduced to the 16 ANSI colors, so they look right on any
+/// terminal. Mirrors the palettes in the graphical `src/theme.rs`.
+pub struct Palette {
+    pub name: &'static str,
+    pub text: Color,
+    pub primary: Color,   // X
+    pub secondary: Color, // O
+    pub muted: Color,
+    pub accent: Color, // Grid
+}
+
+pub const PALETTES: [Palette; 4] = [
+    Palette {
+        name: "Modern",
+        text: Color::Reset,
+        primary: Color::Blue,
+        secondary: Color::Green,
+        muted: Color::DarkGrey,
+        accent: Color::Grey,
+    },
+    Palette {
+        name: "Neon Night",
+        text: Color::White,
+        primary: Color::Magenta,
+        secondary: Color::Cyan,
+        muted: Color::DarkBlue,
+        accent: Color::Blue,
+    },
+    Palette {
+        name: "Royal Gold",
+        text: Color::White,
+        primary: Color::Yellow,
+        secondary: Color::Red,
+        muted: Color::DarkYellow,
+        accent: Color::DarkYellow,
+    },
+    Palette {
+        name: "Terminal",
+        text: Color::Green,
+        primary: Color::Green,
+        secondary: Color::DarkGreen,
+        muted: Color::DarkGreen,
+        accent: Color::DarkGreen,
+    },
+];
+
+/// Looks a palette up by name, ignoring case and anything after the first
+/// word, so `neon` finds "Neon Night".
+pub fn find(name: &str) -> Option<usize> {
+    let name = name.to_lowercase();
+    PALETTES.iter().position(|palette| {
+        let full = palette.name.to_lowercase();
+        full == name || full.split_whitespace().next() == Some(name.as_str())
+    })
+}
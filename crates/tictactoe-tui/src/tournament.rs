@@ -0,0 +1,445 @@
+//! `tictactoe-tui tournament`: plays AI configurations against each other
+//! without a board on screen and reports the results.
+
+use std::io::Write;
+use std::time::Duration;
+use tictactoe_core::ai::{DEFAULT_THINK_TIME, Difficulty, Engine};
+use tictactoe_core::position::{MAX_BOARD_SIZE, MIN_BOARD_SIZE, MIN_WIN_LENGTH};
+use tictactoe_core::tournament::{self, Bot, Format, Results, Tournament};
+
+const USAGE: &str = "\
+Usage: tictactoe-tui tournament [options] <bot> <bot>...
+
+Bots are written <level>[:<engine>][@<ms>], for example `perfect`,
+`hard:mcts` or `perfect@100`. Levels are easy (or random), medium (looks
+two moves ahead), hard and perfect; engines are minimax (default) and mcts;
+<ms> is the think time per move.
+
+Options:
+  --gauntlet        The first bot plays each of the others (default: round robin)
+  --games <n>       Games per pairing, sides alternating (default 10)
+  --threads <n>     Games played at once (default: one per CPU)
+  --seed <n>        Seed for the bots' random choices (default: the clock)
+  --size <n>        Board size, 3 to 15 (default 3)
+  --win <k>         Marks in a row needed to win (default: the board size)
+  --think <ms>      Think time for bots without @<ms> (default 500)
+  --csv <file>      Also write the results as CSV (`-` for stdout)
+  --json <file>     Also write the results as JSON (`-` for stdout)
+  --help            Show this message";
+
+const DEFAULT_GAMES: u32 = 10;
+
+struct Options {
+    tournament: Tournament,
+    csv: Option<String>,
+    json: Option<String>,
+}
+
+/// Runs the subcommand with the arguments after `tournament`.
+pub fn main(args: impl Iterator<Item = String>) {
+    let options = match parse_args(args) {
+        Ok(Some(options)) => options,
+        Ok(None) => {
+            println!("{USAGE}");
+            return;
+        }
+        Err(e) => {
+            eprintln!("{e}\n\n{USAGE}");
+            std::process::exit(2);
+        }
+    };
+
+    let results = tournament::run(&options.tournament, |done, total| {
+        eprint!("\rPlayed {done}/{total} games");
+        if done == total {
+            eprintln!();
+        }
+    });
+    let elo = results.elo();
+    print_table(&results, &elo);
+
+    if let Some(path) = &options.csv {
+        write_output(path, &to_csv(&results, &elo));
+    }
+    if let Some(path) = &options.json {
+        write_output(path, &to_json(&results, &elo));
+    }
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Option<Options>, String> {
+    let mut format = Format::RoundRobin;
+    let mut games = DEFAULT_GAMES;
+    let mut threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let mut seed = None;
+    let mut size = 3;
+    let mut win_length = None;
+    let mut think_time = DEFAULT_THINK_TIME;
+    let mut csv = None;
+    let mut json = None;
+    let mut specs = Vec::new();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--help" | "-h" => return Ok(None),
+            "--gauntlet" => format = Format::Gauntlet,
+            "--games" | "--threads" | "--seed" | "--size" | "--win" | "--think" | "--csv"
+            | "--json" => {
+                let value = args.next().ok_or_else(|| format!("{arg} needs a value"))?;
+                match arg.as_str() {
+                    "--games" => games = parse_number(&arg, &value)?,
+                    "--threads" => threads = parse_number(&arg, &value)?,
+                    "--seed" => seed = Some(parse_number(&arg, &value)?),
+                    "--size" => size = parse_number(&arg, &value)?,
+                    "--win" => win_length = Some(parse_number(&arg, &value)?),
+                    "--think" => think_time = Duration::from_millis(parse_number(&arg, &value)?),
+                    "--csv" => csv = Some(value),
+                    "--json" => json = Some(value),
+                    _ => unreachable!(),
+                }
+            }
+            _ if arg.starts_with('-') => return Err(format!("unknown option `{arg}`")),
+            _ => specs.push(arg),
+        }
+    }
+
+    if specs.len() < 2 {
+        return Err("a tournament needs at least two bots".to_string());
+    }
+    let bots = specs
+        .into_iter()
+        .map(|spec| parse_bot(spec, think_time))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let size = size.clamp(MIN_BOARD_SIZE, MAX_BOARD_SIZE);
+    let win_length = win_length.unwrap_or(size).clamp(MIN_WIN_LENGTH, size);
+    let seed = seed.unwrap_or_else(|| tictactoe_core::rng::Rng::from_time().next_u64());
+
+    Ok(Some(Options {
+        tournament: Tournament {
+            bots,
+            format,
+            size,
+            win_length,
+            games,
+            threads,
+            seed,
+        },
+        csv,
+        json,
+    }))
+}
+
+/// Reads `<level>[:<engine>][@<ms>]`. The spec itself becomes the bot's name.
+fn parse_bot(spec: String, default_think_time: Duration) -> Result<Bot, String> {
+    let (rest, think_time) = match spec.split_once('@') {
+        Some((rest, ms)) => (rest, Duration::from_millis(parse_number(&spec, ms)?)),
+        None => (spec.as_str(), default_think_time),
+    };
+    let (level, engine) = rest.split_once(':').unwrap_or((rest, "minimax"));
+
+    let difficulty = if level.eq_ignore_ascii_case("random") {
+        Difficulty::Easy
+    } else {
+        Difficulty::ALL
+            .into_iter()
+            .find(|d| d.name().eq_ignore_ascii_case(level))
+            .ok_or_else(|| format!("unknown AI level `{level}` in `{spec}`"))?
+    };
+    let engine = Engine::ALL
+        .into_iter()
+        .find(|e| e.name().eq_ignore_ascii_case(engine))
+        .ok_or_else(|| format!("unknown engine `{engine}` in `{spec}`"))?;
+
+    Ok(Bot {
+        name: spec,
+        difficulty,
+        engine,
+        think_time,
+    })
+}
+
+fn parse_number<T: std::str::FromStr>(arg: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("{arg} needs a number, not `{value}`"))
+}
+
+/// Wins-draws-losses of each row bot against each column bot, then totals
+/// and ratings, strongest first.
+fn print_table(results: &Results, elo: &[f64]) {
+    let count = results.bots.len();
+    // Rows are labelled with their number too, which the columns go by
+    let labels: Vec<String> = (results.bots.iter().enumerate())
+        .map(|(i, bot)| format!("{} {}", i + 1, bot.name))
+        .collect();
+    let name_width = labels.iter().map(|label| label.len()).max().unwrap_or(0);
+    let cell_width = results
+        .matrix
+        .iter()
+        .flatten()
+        .map(|r| format!("{}-{}-{}", r.wins, r.draws, r.losses).len())
+        .max()
+        .unwrap_or(0)
+        .max(3);
+
+    print!("{:name_width$}", "");
+    for j in 0..count {
+        print!("  {:>cell_width$}", j + 1);
+    }
+    println!();
+    for (i, label) in labels.iter().enumerate() {
+        print!("{label:name_width$}");
+        for j in 0..count {
+            let record = results.matrix[i][j];
+            let cell = if record.games() == 0 {
+                "-".to_string()
+            } else {
+                format!("{}-{}-{}", record.wins, record.draws, record.losses)
+            };
+            print!("  {cell:>cell_width$}");
+        }
+        println!();
+    }
+
+    let mut order: Vec<usize> = (0..count).collect();
+    order.sort_by(|&a, &b| elo[b].total_cmp(&elo[a]));
+    println!();
+    println!("{:name_width$}     Elo    W    D    L", "Bot");
+    for i in order {
+        let total = results.total(i);
+        println!(
+            "{:name_width$}  {:6.0} {:4} {:4} {:4}",
+            results.bots[i].name, elo[i], total.wins, total.draws, total.losses
+        );
+    }
+}
+
+/// One row per bot: its rating and totals, then its record against each
+/// bot as wins-draws-losses.
+fn to_csv(results: &Results, elo: &[f64]) -> String {
+    let mut out = String::from("bot,elo,wins,draws,losses");
+    for bot in &results.bots {
+        out.push(',');
+        out.push_str(&csv_field(&bot.name));
+    }
+    out.push('\n');
+
+    for (i, bot) in results.bots.iter().enumerate() {
+        let total = results.total(i);
+        out.push_str(&format!(
+            "{},{:.1},{},{},{}",
+            csv_field(&bot.name),
+            elo[i],
+            total.wins,
+            total.draws,
+            total.losses
+        ));
+        for record in &results.matrix[i] {
+            // Left empty for pairs that never met
+            out.push(',');
+            if record.games() > 0 {
+                out.push_str(&format!(
+                    "{}-{}-{}",
+                    record.wins, record.draws, record.losses
+                ));
+            }
+        }
+        out.push('\n');
+    }
+    out
+}
+
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+fn to_json(results: &Results, elo: &[f64]) -> String {
+    let bots: Vec<String> = results
+        .bots
+        .iter()
+        .enumerate()
+        .map(|(i, bot)| {
+            let total = results.total(i);
+            format!(
+                "    {{\"name\": {}, \"level\": \"{}\", \"engine\": \"{}\", \"think_ms\": {}, \
+                 \"elo\": {:.1}, \"wins\": {}, \"draws\": {}, \"losses\": {}}}",
+                json_string(&bot.name),
+                bot.difficulty.name(),
+                bot.engine.name(),
+                bot.think_time.as_millis(),
+                elo[i],
+                total.wins,
+                total.draws,
+                total.losses
+            )
+        })
+        .collect();
+    let rows: Vec<String> = results
+        .matrix
+        .iter()
+        .map(|row| {
+            let cells: Vec<String> = row
+                .iter()
+                .map(|r| {
+                    format!(
+                        "{{\"wins\": {}, \"draws\": {}, \"losses\": {}}}",
+                        r.wins, r.draws, r.losses
+                    )
+                })
+                .collect();
+            format!("    [{}]", cells.join(", "))
+        })
+        .collect();
+
+    format!(
+        "{{\n  \"bots\": [\n{}\n  ],\n  \"matrix\": [\n{}\n  ]\n}}\n",
+        bots.join(",\n"),
+        rows.join(",\n")
+    )
+}
+
+fn json_string(text: &str) -> String {
+    let mut out = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn write_output(path: &str, contents: &str) {
+    let written = if path == "-" {
+        std::io::stdout().write_all(contents.as_bytes())
+    } else {
+        std::fs::write(path, contents)
+    };
+    if let Err(e) = written {
+        eprintln!("Could not write {path}: {e}");
+        std::process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tictactoe_core::tournament::Record;
+
+    fn args(line: &str) -> impl Iterator<Item = String> {
+        line.split_whitespace()
+            .map(str::to_string)
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    fn sample_results() -> Results {
+        let default_think = Duration::from_millis(100);
+        let bots = vec![
+            parse_bot("perfect".to_string(), default_think).unwrap(),
+            parse_bot("hard:mcts@50".to_string(), default_think).unwrap(),
+        ];
+        let none = Record::default();
+        let won = Record {
+            wins: 3,
+            draws: 1,
+            losses: 0,
+        };
+        let lost = Record {
+            wins: 0,
+            draws: 1,
+            losses: 3,
+        };
+        Results {
+            bots,
+            matrix: vec![vec![none, won], vec![lost, none]],
+        }
+    }
+
+    #[test]
+    fn reads_bot_specs() {
+        let bot = parse_bot("hard:mcts@50".to_string(), DEFAULT_THINK_TIME).unwrap();
+        assert_eq!(bot.name, "hard:mcts@50");
+        assert_eq!(bot.difficulty, Difficulty::Hard);
+        assert_eq!(bot.engine, Engine::Mcts);
+        assert_eq!(bot.think_time, Duration::from_millis(50));
+
+        let bot = parse_bot("random".to_string(), DEFAULT_THINK_TIME).unwrap();
+        assert_eq!(bot.difficulty, Difficulty::Easy);
+        assert_eq!(bot.engine, Engine::Minimax);
+        assert_eq!(bot.think_time, DEFAULT_THINK_TIME);
+
+        for spec in ["genius", "hard:alphazero", "hard@soon"] {
+            assert!(
+                parse_bot(spec.to_string(), DEFAULT_THINK_TIME).is_err(),
+                "{spec}"
+            );
+        }
+    }
+
+    #[test]
+    fn reads_options() {
+        let options = parse_args(args(
+            "--gauntlet --games 4 --threads 2 --seed 7 --size 5 --win 9 --think 20 --csv - perfect easy medium",
+        ))
+        .unwrap()
+        .unwrap();
+        let tournament = options.tournament;
+        assert_eq!(tournament.format, Format::Gauntlet);
+        assert_eq!(
+            (tournament.games, tournament.threads, tournament.seed),
+            (4, 2, 7)
+        );
+        // The win length is clamped to the board
+        assert_eq!((tournament.size, tournament.win_length), (5, 5));
+        assert_eq!(tournament.bots.len(), 3);
+        assert_eq!(tournament.bots[1].think_time, Duration::from_millis(20));
+        assert_eq!(options.csv.as_deref(), Some("-"));
+        assert!(options.json.is_none());
+
+        assert!(parse_args(args("--help")).unwrap().is_none());
+        for line in [
+            "perfect",
+            "--games perfect easy",
+            "--color red perfect easy",
+            "perfect easy --seed",
+        ] {
+            assert!(parse_args(args(line)).is_err(), "accepted {line:?}");
+        }
+    }
+
+    #[test]
+    fn writes_csv() {
+        let results = sample_results();
+        let csv = to_csv(&results, &[1600.0, 1400.0]);
+        assert_eq!(
+            csv,
+            "bot,elo,wins,draws,losses,perfect,hard:mcts@50\n\
+             perfect,1600.0,3,1,0,,3-1-0\n\
+             hard:mcts@50,1400.0,0,1,3,0-1-3,\n"
+        );
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+
+    #[test]
+    fn writes_json() {
+        let results = sample_results();
+        let json = to_json(&results, &[1600.0, 1400.0]);
+        assert!(json.contains(
+            "{\"name\": \"hard:mcts@50\", \"level\": \"Hard\", \"engine\": \"MCTS\", \
+             \"think_ms\": 50, \"elo\": 1400.0, \"wins\": 0, \"draws\": 1, \"losses\": 3}"
+        ));
+        assert!(json.contains(
+            "[{\"wins\": 0, \"draws\": 0, \"losses\": 0}, {\"wins\": 3, \"draws\": 1, \"losses\": 0}]"
+        ));
+        assert_eq!(json_string("a \"b\"\\\n"), "\"a \\\"b\\\"\\\\\\u000a\"");
+    }
+}
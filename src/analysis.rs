@@ -0,0 +1,419 @@
+use crate::board::{Board, LOSS_COLOR};
+use crate::config::*;
+use crate::notation::GameRecord;
+use crate::state::GameState;
+use crate::utils::draw_label;
+use macroquad::prelude::*;
+use std::sync::mpsc::{self, Receiver};
+use tictactoe_core::CellState;
+use tictactoe_core::ai::Evaluation;
+use tictactoe_core::analysis::{self, MoveReview, Verdict};
+
+const INACCURACY_COLOR: Color = Color::new(0.92, 0.62, 0.2, 1.0);
+
+// Move list in the space left of the board
+const LIST_X: f32 = 20.0;
+const LIST_Y: f32 = 130.0;
+const LIST_ROWS: usize = 17;
+const ROW_HEIGHT: f32 = 24.0;
+
+// Details in the space right of the board
+const PANEL_X: f32 = 640.0;
+
+struct Analysis {
+    board: Board,
+    record: GameRecord,
+    winner: Option<CellState>,
+    reviews: Vec<MoveReview>,
+    // Reviews arrive here one move at a time from the worker thread
+    search: Option<Receiver<MoveReview>>,
+    critical: Option<usize>,
+    selected: usize, // the move under review; the board shows the position before it
+    status: Option<(String, f32)>,
+    return_to: GameState,
+}
+
+impl Analysis {
+    fn new(record: GameRecord, return_to: GameState) -> Self {
+        let (size, win_length) = (record.size, record.win_length);
+        let (rule, topology) = (record.rule, record.topology);
+        let mut board = Board::new(size, win_length);
+        board.position.set_rule(rule);
+        board.position.set_topology(topology);
+
+        let start = board.position.clone();
+        let moves = record.moves.clone();
+        let think_time = crate::game::think_time();
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            for ply in 0..moves.len() {
+                // Stop once the screen has been closed
+                if let Some(review) = analysis::review_move(&start, &moves, ply, think_time)
+                    && tx.send(review).is_err()
+                {
+                    break;
+                }
+            }
+        });
+
+        let winner =
+            tictactoe_core::Game::from_moves(size, win_length, rule, topology, &record.moves)
+                .ok()
+                .and_then(|game| game.winner());
+        let mut analysis = Self {
+            board,
+            record,
+            winner,
+            reviews: Vec::new(),
+            search: Some(rx),
+            critical: None,
+            selected: 0,
+            status: None,
+            return_to,
+        };
+        analysis.select(0);
+        analysis
+    }
+
+    fn is_done(&self) -> bool {
+        self.search.is_none()
+    }
+
+    /// Shows the position before move `index`, with the best move marked
+    /// and every other move's evaluation written on its cell.
+    fn select(&mut self, index: usize) {
+        let index = index.min(self.record.moves.len().saturating_sub(1));
+        self.selected = index;
+
+        self.board.reset();
+        for (ply, &(row, col)) in self.record.moves[..index].iter().enumerate() {
+            let symbol = if ply.is_multiple_of(2) {
+                CellState::X
+            } else {
+                CellState::O
+            };
+            self.board.set_cell(row, col, symbol);
+            self.board.anim_timers[row][col] = 1.0;
+        }
+        if let Some(review) = self.reviews.get(index) {
+            self.board.hint = Some(review.best_move);
+            self.board.evaluations = review.evaluations.clone();
+        }
+    }
+
+    fn receive(&mut self) {
+        let Some(search) = &self.search else {
+            return;
+        };
+        let mut received = false;
+        while let Ok(review) = search.try_recv() {
+            self.reviews.push(review);
+            received = true;
+        }
+        if self.reviews.len() == self.record.moves.len() {
+            self.search = None;
+            self.critical = analysis::critical_move(&self.reviews, self.winner);
+            // Open on the moment that decided the game
+            if let Some(critical) = self.critical {
+                self.select(critical);
+                return;
+            }
+        }
+        if received && self.selected < self.reviews.len() {
+            self.select(self.selected);
+        }
+    }
+
+    /// The analysis as comment lines for the save file.
+    fn notes(&self) -> Vec<String> {
+        let mut notes = vec![format!(
+            "Analysis, {} ms per position",
+            crate::game::think_time().as_millis()
+        )];
+        for (index, review) in self.reviews.iter().enumerate() {
+            let mut line = format!(
+                "{}. {} {} {}",
+                index + 1,
+                symbol_name(review.side),
+                move_name(review.mv),
+                evaluation_name(review.played)
+            );
+            if review.verdict != Verdict::Best {
+                line.push_str(&format!(
+                    " - {}, {} was {}",
+                    review.verdict.name(),
+                    move_name(review.best_move),
+                    evaluation_name(review.best)
+                ));
+            }
+            notes.push(line);
+        }
+        if let Some(critical) = self.critical {
+            let review = &self.reviews[critical];
+            notes.push(format!(
+                "Critical position: before move {}, where {} played {}",
+                critical + 1,
+                symbol_name(review.side),
+                move_name(review.mv)
+            ));
+        }
+        notes
+    }
+}
+
+static mut ANALYSIS: Option<Analysis> = None;
+
+/// Opens the analysis screen for a game. ESC goes back to `return_to`.
+pub fn start(record: GameRecord, return_to: GameState) {
+    unsafe {
+        ANALYSIS = Some(Analysis::new(record, return_to));
+    }
+}
+
+#[allow(static_mut_refs)]
+pub fn update() -> Option<GameState> {
+    let Some(analysis) = (unsafe { ANALYSIS.as_mut() }) else {
+        return Some(GameState::Menu);
+    };
+
+    let font = crate::config::get_font();
+    let theme = crate::theme::get_current_theme();
+
+    analysis.receive();
+
+    // ---- Draw UI ----
+    draw_label("Analysis", 20.0, 40.0, 24, theme.text);
+    draw_label(
+        "ESC: Back | Left/Right: Move | C: Critical position | F5: Export",
+        20.0,
+        70.0,
+        18,
+        theme.muted,
+    );
+
+    let total = analysis.record.moves.len();
+    let progress_text = if analysis.is_done() {
+        format!("Move {} / {}", analysis.selected + 1, total)
+    } else {
+        format!("Analyzing {} / {}...", analysis.reviews.len(), total)
+    };
+    let progress_dim = measure_text(&progress_text, font, 24, 1.0);
+    draw_label(
+        &progress_text,
+        VIRTUAL_WIDTH - progress_dim.width - 20.0,
+        40.0,
+        24,
+        theme.text,
+    );
+
+    // ---- Draw Board ----
+    analysis.board.draw();
+
+    // The move actually played, outlined in its verdict's color
+    if let Some(review) = analysis.reviews.get(analysis.selected) {
+        let board = &analysis.board;
+        let (row, col) = review.mv;
+        let inset = board.cell_size * 0.12;
+        draw_rectangle_lines(
+            board.x + col as f32 * board.cell_size + inset,
+            board.y + row as f32 * board.cell_size + inset,
+            board.cell_size - inset * 2.0,
+            board.cell_size - inset * 2.0,
+            (4.0 * board.cell_size / 150.0).max(2.0),
+            verdict_color(review.verdict, theme.text),
+        );
+    }
+
+    // ---- Move List ----
+    // Scrolls to keep the selected move in view
+    let first = analysis
+        .selected
+        .saturating_sub(LIST_ROWS / 2)
+        .min(total.saturating_sub(LIST_ROWS));
+    for index in first..total.min(first + LIST_ROWS) {
+        let (row, col) = analysis.record.moves[index];
+        let y = LIST_Y + (index - first) as f32 * ROW_HEIGHT;
+        let side = if index.is_multiple_of(2) { "X" } else { "O" };
+        let (mark, color) = match analysis.reviews.get(index) {
+            Some(review) => (
+                match review.verdict {
+                    Verdict::Best => "",
+                    Verdict::Inaccuracy => " ?!",
+                    Verdict::Blunder => " ??",
+                    Verdict::Unclear => " ?",
+                },
+                verdict_color(review.verdict, theme.muted),
+            ),
+            None => (" ...", theme.muted),
+        };
+        let cursor = if index == analysis.selected { ">" } else { " " };
+        let line = format!(
+            "{cursor}{:>3}. {side} {}{mark}",
+            index + 1,
+            move_name((row, col))
+        );
+        draw_label(&line, LIST_X, y, 18, color);
+    }
+
+    // ---- Details ----
+    if let Some(review) = analysis.reviews.get(analysis.selected) {
+        let mut y = 150.0;
+        let mut line = |line: &str, color: Color| {
+            draw_label(line, PANEL_X, y, 18, color);
+            y += 26.0;
+        };
+        line(
+            &format!(
+                "{} played {}",
+                symbol_name(review.side),
+                move_name(review.mv)
+            ),
+            theme.text,
+        );
+        line(&evaluation_name(review.played), theme.muted);
+        line(
+            review.verdict.name(),
+            verdict_color(review.verdict, theme.text),
+        );
+        if review.verdict != Verdict::Best {
+            line(
+                &format!("Best: {}", move_name(review.best_move)),
+                theme.text,
+            );
+            line(&evaluation_name(review.best), theme.muted);
+        }
+    }
+
+    // Where it went wrong, and how often each side slipped
+    let mut y = 360.0;
+    if analysis.is_done() {
+        let summary = match (analysis.critical, analysis.winner) {
+            (Some(critical), _) => format!("Lost at move {}", critical + 1),
+            (None, Some(_)) => "No way back".to_string(),
+            (None, None) => "Draw".to_string(),
+        };
+        draw_label(&summary, PANEL_X, y, 18, theme.text);
+        y += 34.0;
+    }
+    for side in [CellState::X, CellState::O] {
+        let count = |verdict| {
+            analysis
+                .reviews
+                .iter()
+                .filter(|review| review.side == side && review.verdict == verdict)
+                .count()
+        };
+        let color = if side == CellState::X {
+            theme.primary
+        } else {
+            theme.secondary
+        };
+        draw_label(symbol_name(side), PANEL_X, y, 18, color);
+        draw_label(
+            &format!(
+                "{} ?!  {} ??",
+                count(Verdict::Inaccuracy),
+                count(Verdict::Blunder)
+            ),
+            PANEL_X + 30.0,
+            y,
+            18,
+            theme.muted,
+        );
+        y += 26.0;
+    }
+
+    // Export feedback
+    if let Some((message, time_left)) = &mut analysis.status {
+        *time_left -= get_frame_time();
+        let mut color = theme.muted;
+        color.a = time_left.clamp(0.0, 1.0);
+        let dim = measure_text(message, font, 18, 1.0);
+        draw_label(
+            message,
+            VIRTUAL_WIDTH / 2.0 - dim.width / 2.0,
+            VIRTUAL_HEIGHT - 15.0,
+            18,
+            color,
+        );
+        if *time_left <= 0.0 {
+            analysis.status = None;
+        }
+    }
+
+    // ---- Controls ----
+    let analyzed = analysis.reviews.len();
+    if is_key_pressed(KeyCode::Right) && analysis.selected + 1 < analyzed {
+        analysis.select(analysis.selected + 1);
+    }
+    if is_key_pressed(KeyCode::Left) && analysis.selected > 0 {
+        analysis.select(analysis.selected - 1);
+    }
+    if is_key_pressed(KeyCode::Home) {
+        analysis.select(0);
+    }
+    if is_key_pressed(KeyCode::End) && analyzed > 0 {
+        analysis.select(analyzed - 1);
+    }
+    if is_key_pressed(KeyCode::C)
+        && let Some(critical) = analysis.critical
+    {
+        analysis.select(critical);
+    }
+    if is_key_pressed(KeyCode::F5) {
+        let message = if !analysis.is_done() {
+            "Still analyzing...".to_string()
+        } else {
+            let path = crate::game::SAVE_FILE;
+            match crate::notation::save_annotated(path, &analysis.record, &analysis.notes()) {
+                Ok(()) => format!("Saved game and analysis to {path}"),
+                Err(e) => format!("Save failed: {e}"),
+            }
+        };
+        analysis.status = Some((message, 3.0));
+    }
+
+    // Theme switching
+    if is_key_pressed(KeyCode::T) {
+        crate::theme::cycle_theme();
+    }
+
+    if is_key_pressed(KeyCode::Escape) {
+        let return_to = analysis.return_to;
+        unsafe {
+            ANALYSIS = None;
+        }
+        return Some(return_to);
+    }
+
+    None
+}
+
+fn verdict_color(verdict: Verdict, best: Color) -> Color {
+    match verdict {
+        Verdict::Best => best,
+        Verdict::Inaccuracy => INACCURACY_COLOR,
+        Verdict::Blunder => LOSS_COLOR,
+        Verdict::Unclear => best,
+    }
+}
+
+fn evaluation_name(evaluation: Evaluation) -> String {
+    match evaluation {
+        Evaluation::Win(1) => "wins now".to_string(),
+        Evaluation::Win(moves) => format!("wins in {moves} moves"),
+        Evaluation::Loss(0) => "loses on the spot".to_string(),
+        Evaluation::Loss(1) => "loses next move".to_string(),
+        Evaluation::Loss(moves) => format!("loses in {moves} moves"),
+        Evaluation::Draw => "draws".to_string(),
+        Evaluation::Unknown => "unclear".to_string(),
+    }
+}
+
+fn move_name((row, col): (usize, usize)) -> String {
+    crate::notation::format_move(row, col)
+}
+
+fn symbol_name(side: CellState) -> &'static str {
+    if side == CellState::O { "O" } else { "X" }
+}
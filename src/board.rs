@@ -1,93 +1,180 @@
 use crate::config::*;
 use macroquad::prelude::*;
+use tictactoe_core::ai::Evaluation;
+use tictactoe_core::{CellState, Move, Position};
 
-#[derive(Clone, Copy, PartialEq)]
-pub enum CellState {
-    Empty,
-    X,
-    O,
-}
+// Side length of the whole grid in virtual pixels, shared by every board size;
+// a rectangular grid fits its longer side to it
+const BOARD_PIXEL_SIZE: f32 = 450.0;
+
+// How fast a dropped piece speeds up, in virtual pixels per second squared at
+// the 150px cell size
+const FALL_ACCELERATION: f32 = 6000.0;
 
+// Evaluation overlay colors, readable on both light and dark themes
+const WIN_COLOR: Color = Color::new(0.24, 0.7, 0.36, 1.0);
+pub const LOSS_COLOR: Color = Color::new(0.86, 0.28, 0.28, 1.0);
+
+/// A piece taken off the board, by undo or by a variant's rules, kept around
+/// just long enough to shrink away.
 #[derive(Clone, Copy)]
-pub struct Cell {
+pub struct RemovedPiece {
+    pub row: usize,
+    pub col: usize,
     pub state: CellState,
-    pub anim_timer: f32, // 0.0 to 1.0 for scale/alpha animation
+    pub anim_timer: f32, // 0.0 to 1.0, piece is gone at 1.0
 }
 
+/// A piece dropping down its column into its cell, as in gravity games.
 #[derive(Clone, Copy)]
+pub struct FallingPiece {
+    pub row: usize,
+    pub col: usize,
+    pub height: f32, // pixels above its cell, landed at 0.0
+    pub speed: f32,  // pixels per second, downwards
+}
+
+/// On-screen view of a `Position`: where it is drawn and how its pieces are
+/// animated. The rules themselves live in `tictactoe_core`.
+#[derive(Clone)]
 pub struct Board {
-    pub cells: [[Cell; 3]; 3],
+    pub position: Position,
+    pub anim_timers: Vec<Vec<f32>>, // per cell, 0.0 to 1.0 for scale/alpha animation
     pub cell_size: f32,
     pub x: f32,
     pub y: f32,
-    pub winning_cells: Option<[(usize, usize); 3]>,
+    pub winning_cells: Option<Vec<(usize, usize)>>,
     pub win_anim_timer: f32,
+    pub removed: Vec<RemovedPiece>,
+    pub hint: Option<Move>, // suggested move for the side to move
+    pub evaluations: Vec<(Move, Evaluation)>, // shown on empty cells when non-empty
+    pub vanishing: Option<Move>, // a piece about to be taken off, drawn fading
+    pub falling: Option<FallingPiece>,
+    pub preview: Option<(Move, CellState)>, // a piece drawn faintly where it would go
 }
 
 impl Board {
-    pub fn new() -> Self {
-        let cell_size = 150.0;
-        let board_total_size = cell_size * 3.0;
+    pub fn new(size: usize, win_length: usize) -> Self {
+        Self::rectangular(size, size, win_length)
+    }
+
+    /// A board `rows` high and `cols` wide, centered where the square one goes.
+    pub fn rectangular(rows: usize, cols: usize, win_length: usize) -> Self {
+        let position = Position::rectangular(rows, cols, win_length);
+        let (rows, cols) = (position.rows(), position.cols());
+        let cell_size = BOARD_PIXEL_SIZE / rows.max(cols) as f32;
 
         Self {
-            cells: [[Cell {
-                state: CellState::Empty,
-                anim_timer: 0.0,
-            }; 3]; 3],
+            position,
+            anim_timers: vec![vec![0.0; cols]; rows],
             cell_size,
-            x: VIRTUAL_WIDTH / 2.0 - board_total_size / 2.0,
-            y: VIRTUAL_HEIGHT / 2.0 - board_total_size / 2.0 + 30.0,
+            x: VIRTUAL_WIDTH / 2.0 - cell_size * cols as f32 / 2.0,
+            y: VIRTUAL_HEIGHT / 2.0 - cell_size * rows as f32 / 2.0 + 30.0,
             winning_cells: None,
             win_anim_timer: 0.0,
+            removed: Vec::new(),
+            hint: None,
+            evaluations: Vec::new(),
+            vanishing: None,
+            falling: None,
+            preview: None,
         }
     }
 
+    /// Moves the board to `(x, y)` and scales its longer side to
+    /// `pixel_size`, for boards that aren't the single centered one.
+    pub fn set_layout(&mut self, x: f32, y: f32, pixel_size: f32) {
+        self.x = x;
+        self.y = y;
+        self.cell_size = pixel_size / self.rows().max(self.cols()) as f32;
+    }
+
     pub fn draw(&mut self) {
         let theme = crate::theme::get_current_theme();
         let dt = get_frame_time();
         let time = get_time() as f32;
 
+        // Everything was tuned for 150px cells on the classic 3x3 board
+        let draw_scale = self.cell_size / 150.0;
+        let (rows, cols) = (self.rows(), self.cols());
+        let (width, height) = (self.width(), self.height());
+
         // Draw grid lines
         let color = theme.accent;
-        let thickness = 4.0;
+        let thickness = (4.0 * draw_scale).max(1.5);
 
-        for i in 1..3 {
-            let offset = i as f32 * self.cell_size;
+        for col in 1..cols {
+            let offset = col as f32 * self.cell_size;
             draw_line(
                 self.x + offset,
                 self.y,
                 self.x + offset,
-                self.y + self.cell_size * 3.0,
+                self.y + height,
                 thickness,
                 color,
             );
+        }
+        for row in 1..rows {
+            let offset = row as f32 * self.cell_size;
             draw_line(
                 self.x,
                 self.y + offset,
-                self.x + self.cell_size * 3.0,
+                self.x + width,
                 self.y + offset,
                 thickness,
                 color,
             );
         }
 
+        self.draw_evaluations(draw_scale);
+
+        // Hinted cell, pulsing gently
+        if let Some((row, col)) = self.hint {
+            let pulse = 0.5 + 0.5 * (time * 4.0).sin();
+            let inset = self.cell_size * 0.06;
+            let (cell_x, cell_y) = (
+                self.x + col as f32 * self.cell_size + inset,
+                self.y + row as f32 * self.cell_size + inset,
+            );
+            let mut fill = theme.text;
+            fill.a = 0.08 + 0.1 * pulse;
+            draw_rectangle(
+                cell_x,
+                cell_y,
+                self.cell_size - inset * 2.0,
+                self.cell_size - inset * 2.0,
+                fill,
+            );
+            draw_rectangle_lines(
+                cell_x,
+                cell_y,
+                self.cell_size - inset * 2.0,
+                self.cell_size - inset * 2.0,
+                (4.0 * draw_scale).max(2.0),
+                theme.text,
+            );
+        }
+
+        // Piece that would be placed, drawn faintly on its empty cell
+        if let Some(((row, col), state)) = self.preview
+            && self.position.get(row, col) == CellState::Empty
+        {
+            self.draw_piece(row, col, state, 1.0, 0.3);
+        }
+
         // Draw cells
-        for row in 0..3 {
-            for col in 0..3 {
-                let cell = &mut self.cells[row][col];
-                if cell.state != CellState::Empty {
+        for row in 0..rows {
+            for col in 0..cols {
+                let state = self.position.get(row, col);
+                if state != CellState::Empty {
                     // Update animation
-                    if cell.anim_timer < 1.0 {
-                        cell.anim_timer = (cell.anim_timer + dt * 5.0).min(1.0);
+                    let anim_timer = &mut self.anim_timers[row][col];
+                    if *anim_timer < 1.0 {
+                        *anim_timer = (*anim_timer + dt * 5.0).min(1.0);
                     }
 
-                    let cell_x = self.x + col as f32 * self.cell_size;
-                    let cell_y = self.y + row as f32 * self.cell_size;
-
-                    let font = crate::config::get_inter_font();
-
                     // Base entry animation
-                    let t = cell.anim_timer;
+                    let t = *anim_timer;
                     let mut scale = if t < 1.0 {
                         let overshoot = 0.4;
                         let s =
@@ -98,96 +185,290 @@ impl Board {
                     };
 
                     // Victory pulse
-                    if let Some(wins) = self.winning_cells {
-                        if wins.contains(&(row, col)) {
-                            scale *= 1.0 + (time * 8.0).sin() * 0.1;
-                        }
+                    if let Some(wins) = &self.winning_cells
+                        && wins.contains(&(row, col))
+                    {
+                        scale *= 1.0 + (time * 8.0).sin() * 0.1;
                     }
 
-                    let font_size = (120.0 * scale) as u16;
-                    let (text, color) = match cell.state {
-                        CellState::X => ("X", theme.primary),
-                        _ => ("O", theme.secondary),
+                    // A piece on its way out flickers
+                    let alpha = if self.vanishing == Some((row, col)) {
+                        0.45 + 0.25 * (time * 5.0).sin()
+                    } else {
+                        1.0
                     };
 
-                    let text_dim = measure_text(text, font, font_size, 1.0);
-                    draw_text_ex(
-                        text,
-                        cell_x + self.cell_size / 2.0 - text_dim.width / 2.0,
-                        cell_y + self.cell_size / 2.0 + text_dim.height / 2.0 - 5.0,
-                        TextParams {
-                            font,
-                            font_size,
-                            color,
-                            ..Default::default()
-                        },
-                    );
+                    // A dropped piece is drawn where it has fallen to so far
+                    if let Some(piece) = &mut self.falling
+                        && (piece.row, piece.col) == (row, col)
+                    {
+                        piece.speed += FALL_ACCELERATION * draw_scale * dt;
+                        piece.height = (piece.height - piece.speed * dt).max(0.0);
+                        let cell_x = self.x + col as f32 * self.cell_size;
+                        let cell_y = self.y + row as f32 * self.cell_size - piece.height;
+                        self.draw_piece_at(cell_x, cell_y, state, scale, alpha);
+                        continue;
+                    }
+
+                    self.draw_piece(row, col, state, scale, alpha);
                 }
             }
         }
 
-        // Draw winning line
-        if let Some(wins) = self.winning_cells {
+        if self.falling.is_some_and(|piece| piece.height <= 0.0) {
+            self.falling = None;
+        }
+
+        // Draw pieces taken off the board, shrinking and fading out
+        for piece in self.removed.iter_mut() {
+            piece.anim_timer = (piece.anim_timer + dt * 5.0).min(1.0);
+        }
+        self.removed.retain(|piece| piece.anim_timer < 1.0);
+        for piece in &self.removed {
+            let fade = 1.0 - piece.anim_timer;
+            self.draw_piece(piece.row, piece.col, piece.state, fade, fade);
+        }
+
+        // Draw winning line, in pieces where it wraps round an edge
+        if let Some(wins) = &self.winning_cells {
             if self.win_anim_timer < 1.0 {
                 self.win_anim_timer = (self.win_anim_timer + dt * 2.0).min(1.0);
             }
 
-            let start = self.get_cell_center(wins[0].0, wins[0].1);
-            let end = self.get_cell_center(wins[2].0, wins[2].1);
-
-            let current_end = start + (end - start) * self.win_anim_timer;
-
-            // Get color from the winning piece
-            let winning_state = self.cells[wins[0].0][wins[0].1].state;
+            // Color of whoever the line won for, which in misère is the
+            // side that didn't complete it
+            let first = wins[0];
+            let winning_state = self
+                .position
+                .rule()
+                .winner(self.position.get(first.0, first.1));
             let line_color = if winning_state == CellState::X {
                 theme.primary
             } else {
                 theme.secondary
             };
-
-            draw_line(
-                start.x,
-                start.y,
-                current_end.x,
-                current_end.y,
-                8.0,
-                line_color,
-            );
+            let line_width = (8.0 * draw_scale).max(3.0);
 
             // Subtle glow around the line
             let mut glow_color = line_color;
             glow_color.a = 0.3;
-            draw_line(
-                start.x,
-                start.y,
-                current_end.x,
-                current_end.y,
-                16.0,
-                glow_color,
+
+            let pieces = self.win_pieces(wins);
+            let total: f32 = pieces.iter().map(|(start, end)| start.distance(*end)).sum();
+            let mut left = total * self.win_anim_timer;
+            for (start, end) in pieces {
+                if left <= 0.0 {
+                    break;
+                }
+                let length = start.distance(end);
+                let current_end = if length > left {
+                    start + (end - start) * (left / length)
+                } else {
+                    end
+                };
+                left -= length;
+
+                draw_line(
+                    start.x,
+                    start.y,
+                    current_end.x,
+                    current_end.y,
+                    line_width,
+                    line_color,
+                );
+                draw_line(
+                    start.x,
+                    start.y,
+                    current_end.x,
+                    current_end.y,
+                    line_width * 2.0,
+                    glow_color,
+                );
+            }
+        }
+    }
+
+    /// Writes each evaluated move on its cell: W3 is a win on the mover's
+    /// third move, L2 a loss to the opponent's second, D a draw.
+    fn draw_evaluations(&self, draw_scale: f32) {
+        let theme = crate::theme::get_current_theme();
+        let font = crate::config::get_font();
+        let font_size = (44.0 * draw_scale).max(10.0) as u16;
+
+        for &((row, col), evaluation) in &self.evaluations {
+            if self.position.get(row, col) != CellState::Empty {
+                continue;
+            }
+            let (text, color) = match evaluation {
+                Evaluation::Win(moves) => (format!("W{moves}"), WIN_COLOR),
+                Evaluation::Loss(moves) => (format!("L{moves}"), LOSS_COLOR),
+                Evaluation::Draw => ("D".to_string(), theme.text),
+                Evaluation::Unknown => ("?".to_string(), theme.muted),
+            };
+
+            let center = self.get_cell_center(row, col);
+            let text_dim = measure_text(&text, font, font_size, 1.0);
+            draw_text_ex(
+                &text,
+                center.x - text_dim.width / 2.0,
+                center.y + text_dim.height / 2.0,
+                TextParams {
+                    font,
+                    font_size,
+                    color,
+                    ..Default::default()
+                },
             );
         }
     }
 
+    fn draw_piece(&self, row: usize, col: usize, state: CellState, scale: f32, alpha: f32) {
+        let cell_x = self.x + col as f32 * self.cell_size;
+        let cell_y = self.y + row as f32 * self.cell_size;
+        self.draw_piece_at(cell_x, cell_y, state, scale, alpha);
+    }
+
+    /// Draws a piece in the cell-sized square whose top left is `(cell_x, cell_y)`.
+    fn draw_piece_at(&self, cell_x: f32, cell_y: f32, state: CellState, scale: f32, alpha: f32) {
+        let theme = crate::theme::get_current_theme();
+        let font = crate::config::get_inter_font();
+        let draw_scale = self.cell_size / 150.0;
+
+        let font_size = (120.0 * draw_scale * scale) as u16;
+        let (text, mut color) = match state {
+            CellState::X => ("X", theme.primary),
+            _ => ("O", theme.secondary),
+        };
+        color.a *= alpha;
+
+        let text_dim = measure_text(text, font, font_size, 1.0);
+        draw_text_ex(
+            text,
+            cell_x + self.cell_size / 2.0 - text_dim.width / 2.0,
+            cell_y + self.cell_size / 2.0 + text_dim.height / 2.0 - 5.0 * draw_scale,
+            TextParams {
+                font,
+                font_size,
+                color,
+                ..Default::default()
+            },
+        );
+    }
+
+    /// The win line through `wins` as pieces to draw. Where it wraps round
+    /// an edge it runs off the board there and comes back on at the
+    /// opposite edge.
+    fn win_pieces(&self, wins: &[(usize, usize)]) -> Vec<(Vec2, Vec2)> {
+        let mut pieces = Vec::new();
+        let mut from = self.get_cell_center(wins[0].0, wins[0].1);
+        for pair in wins.windows(2) {
+            let (a, b) = (pair[0], pair[1]);
+            let dr = b.0 as isize - a.0 as isize;
+            let dc = b.1 as isize - a.1 as isize;
+            if dr.abs() <= 1 && dc.abs() <= 1 {
+                continue;
+            }
+            // A jump across the board is really one step the other way
+            let step = |d: isize| if d.abs() > 1 { -d.signum() } else { d };
+            let half = vec2(step(dc) as f32, step(dr) as f32) * self.cell_size / 2.0;
+            pieces.push((from, self.get_cell_center(a.0, a.1) + half));
+            from = self.get_cell_center(b.0, b.1) - half;
+        }
+        let last = wins[wins.len() - 1];
+        pieces.push((from, self.get_cell_center(last.0, last.1)));
+        pieces
+    }
+
+    /// `get_cell_at` for screens made of several boards: the index of the
+    /// board under `mouse` and the cell on it.
+    pub fn get_cell_among(boards: &[Board], mouse: Vec2) -> Option<(usize, (usize, usize))> {
+        boards
+            .iter()
+            .enumerate()
+            .find_map(|(index, board)| Some((index, board.get_cell_at(mouse)?)))
+    }
+
     pub fn get_cell_at(&self, mouse: Vec2) -> Option<(usize, usize)> {
         let relative_x = mouse.x - self.x;
         let relative_y = mouse.y - self.y;
 
         if relative_x >= 0.0
-            && relative_x < self.cell_size * 3.0
+            && relative_x < self.width()
             && relative_y >= 0.0
-            && relative_y < self.cell_size * 3.0
+            && relative_y < self.height()
         {
-            let col = (relative_x / self.cell_size) as usize;
-            let row = (relative_y / self.cell_size) as usize;
+            let col = ((relative_x / self.cell_size) as usize).min(self.cols() - 1);
+            let row = ((relative_y / self.cell_size) as usize).min(self.rows() - 1);
             return Some((row, col));
         }
         None
     }
 
+    pub fn rows(&self) -> usize {
+        self.position.rows()
+    }
+
+    pub fn cols(&self) -> usize {
+        self.position.cols()
+    }
+
+    /// Width of the whole grid in virtual pixels.
+    pub fn width(&self) -> f32 {
+        self.cell_size * self.cols() as f32
+    }
+
+    /// Height of the whole grid in virtual pixels.
+    pub fn height(&self) -> f32 {
+        self.cell_size * self.rows() as f32
+    }
+
+    pub fn state(&self, row: usize, col: usize) -> CellState {
+        self.position.get(row, col)
+    }
+
     pub fn set_cell(&mut self, row: usize, col: usize, state: CellState) {
-        if self.cells[row][col].state == CellState::Empty {
-            self.cells[row][col].state = state;
-            self.cells[row][col].anim_timer = 0.0;
+        if self.position.get(row, col) == CellState::Empty {
+            self.position.set(row, col, state);
+            self.anim_timers[row][col] = 0.0;
+            self.removed
+                .retain(|piece| (piece.row, piece.col) != (row, col));
+        }
+    }
+
+    /// Like `set_cell`, but the piece drops in from the top of its column
+    /// instead of popping up in place.
+    pub fn drop_cell(&mut self, row: usize, col: usize, state: CellState) {
+        if self.position.get(row, col) == CellState::Empty {
+            self.set_cell(row, col, state);
+            self.anim_timers[row][col] = 1.0;
+            self.falling = Some(FallingPiece {
+                row,
+                col,
+                height: row as f32 * self.cell_size,
+                speed: 0.0,
+            });
+        }
+    }
+
+    /// Whether a dropped piece is still on its way down.
+    pub fn is_falling(&self) -> bool {
+        self.falling.is_some()
+    }
+
+    /// Empties a cell and animates the old piece away. Any win line is
+    /// dropped, since taking a piece back can only ever undo a win.
+    pub fn clear_cell(&mut self, row: usize, col: usize) {
+        let state = self.position.get(row, col);
+        if state != CellState::Empty {
+            self.position.set(row, col, CellState::Empty);
+            self.removed.push(RemovedPiece {
+                row,
+                col,
+                state,
+                anim_timer: 0.0,
+            });
+            self.winning_cells = None;
+            self.win_anim_timer = 0.0;
         }
     }
 
@@ -198,70 +479,25 @@ impl Board {
         )
     }
 
+    /// Marks a completed line for drawing and returns who won by it.
     pub fn check_winner(&mut self) -> Option<CellState> {
-        if let Some((state, cells)) = self.check_winner_pure() {
+        if let Some((state, cells)) = self.position.winner() {
             self.winning_cells = Some(cells);
             self.win_anim_timer = 0.0;
-            return Some(state);
+            return Some(self.position.rule().winner(state));
         }
         None
     }
 
-    pub fn check_winner_pure(&self) -> Option<(CellState, [(usize, usize); 3])> {
-        // Rows
-        for row in 0..3 {
-            if self.cells[row][0].state != CellState::Empty
-                && self.cells[row][0].state == self.cells[row][1].state
-                && self.cells[row][1].state == self.cells[row][2].state
-            {
-                return Some((self.cells[row][0].state, [(row, 0), (row, 1), (row, 2)]));
-            }
-        }
-
-        // Columns
-        for col in 0..3 {
-            if self.cells[0][col].state != CellState::Empty
-                && self.cells[0][col].state == self.cells[1][col].state
-                && self.cells[1][col].state == self.cells[2][col].state
-            {
-                return Some((self.cells[0][col].state, [(0, col), (1, col), (2, col)]));
-            }
-        }
-
-        // Diagonals
-        if self.cells[0][0].state != CellState::Empty
-            && self.cells[0][0].state == self.cells[1][1].state
-            && self.cells[1][1].state == self.cells[2][2].state
-        {
-            return Some((self.cells[0][0].state, [(0, 0), (1, 1), (2, 2)]));
-        }
-        if self.cells[0][2].state != CellState::Empty
-            && self.cells[0][2].state == self.cells[1][1].state
-            && self.cells[1][1].state == self.cells[2][0].state
-        {
-            return Some((self.cells[0][2].state, [(0, 2), (1, 1), (2, 0)]));
-        }
-
-        None
-    }
-
-    pub fn is_full(&self) -> bool {
-        for row in 0..3 {
-            for col in 0..3 {
-                if self.cells[row][col].state == CellState::Empty {
-                    return false;
-                }
-            }
-        }
-        true
-    }
-
     pub fn reset(&mut self) {
-        self.cells = [[Cell {
-            state: CellState::Empty,
-            anim_timer: 0.0,
-        }; 3]; 3];
+        self.position.clear();
         self.winning_cells = None;
         self.win_anim_timer = 0.0;
+        self.removed.clear();
+        self.hint = None;
+        self.evaluations.clear();
+        self.vanishing = None;
+        self.falling = None;
+        self.preview = None;
     }
 }
@@ -0,0 +1,223 @@
+use crate::board::Board;
+use crate::config::*;
+use crate::player::AiWorker;
+use crate::state::GameState;
+use crate::utils::*;
+use macroquad::prelude::*;
+use tictactoe_core::ai::Difficulty;
+use tictactoe_core::connect_four::{self, COLS, ConnectFour, ROWS, WIN_LENGTH};
+use tictactoe_core::position::{MAX_BOARD_SIZE, MIN_BOARD_SIZE, MIN_WIN_LENGTH};
+use tictactoe_core::rng::Rng;
+use tictactoe_core::{CellState, Move, Outcome};
+
+// Grid for the next game: rows, columns, pieces in a row
+static mut GRID: (usize, usize, usize) = (ROWS, COLS, WIN_LENGTH);
+
+struct ConnectFourGame {
+    game: ConnectFour,
+    board: Board,
+    ai: Option<Difficulty>,
+    player_symbol: CellState, // the human's side against the AI
+    ai_worker: AiWorker<usize>,
+    landing: Option<(Move, CellState)>, // the dropped piece, until it lands
+}
+
+impl ConnectFourGame {
+    fn new(ai: Option<Difficulty>, player_symbol: CellState) -> Self {
+        let (rows, cols, win_length) = grid();
+        Self {
+            game: ConnectFour::new(rows, cols, win_length),
+            board: Board::rectangular(rows, cols, win_length),
+            ai,
+            player_symbol,
+            ai_worker: AiWorker::new(Rng::from_time()),
+            landing: None,
+        }
+    }
+
+    fn is_ai_turn(&self) -> bool {
+        self.ai.is_some() && !self.game.is_over() && self.game.turn() != self.player_symbol
+    }
+
+    /// Drops a piece into `col`. Sound, particles and any win line wait
+    /// until it lands; see `land`.
+    fn play(&mut self, col: usize) {
+        let mover = self.game.turn();
+        let Ok((row, col)) = self.game.play(col) else {
+            return;
+        };
+        self.board.drop_cell(row, col, mover);
+        self.landing = Some(((row, col), mover));
+    }
+
+    /// The effects `game::apply_move` plays straight away, once the dropped
+    /// piece has reached its cell.
+    fn land(&mut self) {
+        let Some(((row, col), mover)) = self.landing.take() else {
+            return;
+        };
+        self.board.check_winner();
+
+        let theme = crate::theme::get_current_theme();
+        let color = if mover == CellState::X {
+            theme.primary
+        } else {
+            theme.secondary
+        };
+        crate::config::play_move();
+        crate::particles::spawn_move(self.board.get_cell_center(row, col), color);
+
+        if let Some(Outcome::Win(_, line)) = self.game.outcome() {
+            crate::config::play_win();
+            for &(row, col) in line {
+                crate::particles::spawn_win(self.board.get_cell_center(row, col), color);
+            }
+        }
+    }
+}
+
+static mut CONNECT_FOUR: Option<ConnectFourGame> = None;
+
+/// Rows, columns and pieces in a row for the next game.
+pub fn grid() -> (usize, usize, usize) {
+    unsafe { GRID }
+}
+
+pub fn set_grid(rows: usize, cols: usize, win_length: usize) {
+    let rows = rows.clamp(MIN_BOARD_SIZE, MAX_BOARD_SIZE);
+    let cols = cols.clamp(MIN_BOARD_SIZE, MAX_BOARD_SIZE);
+    unsafe {
+        GRID = (rows, cols, win_length.clamp(MIN_WIN_LENGTH, rows.max(cols)));
+    }
+}
+
+/// Starts a new game on the current grid, against the AI at `ai` or between
+/// two people.
+pub fn start(ai: Option<Difficulty>, player_symbol: CellState) {
+    unsafe {
+        CONNECT_FOUR = Some(ConnectFourGame::new(ai, player_symbol));
+    }
+}
+
+#[allow(static_mut_refs)]
+pub fn update(scale: &ScreenScale) -> Option<GameState> {
+    let Some(four) = (unsafe { CONNECT_FOUR.as_mut() }) else {
+        return Some(GameState::Menu);
+    };
+
+    let mouse = mouse_to_virtual(scale);
+    let font = crate::config::get_font();
+    let theme = crate::theme::get_current_theme();
+    let side_color = |side: CellState| {
+        if side == CellState::X {
+            theme.primary
+        } else {
+            theme.secondary
+        }
+    };
+
+    // ---- Draw UI ----
+    let mode_text = match four.ai {
+        Some(difficulty) => format!("Connect Four vs AI ({})", difficulty.name()),
+        None => "Connect Four: Player vs Player".to_string(),
+    };
+    draw_label(&mode_text, 20.0, 40.0, 24, theme.text);
+    draw_label(
+        "ESC: Menu | R: Reset | T: Theme",
+        20.0,
+        70.0,
+        18,
+        theme.muted,
+    );
+
+    // The result waits for the last piece to land
+    match four.game.outcome().filter(|_| four.landing.is_none()) {
+        Some(outcome) => {
+            let (msg, color) = match outcome {
+                Outcome::Win(CellState::X, _) => ("PLAYER X WINS!", theme.primary),
+                Outcome::Win(_, _) => ("PLAYER O WINS!", theme.secondary),
+                Outcome::Draw => ("IT'S A DRAW!", theme.muted),
+            };
+            draw_end_banner(msg, color);
+        }
+        None => {
+            let turn_text = if four.ai_worker.is_thinking() {
+                "AI is thinking...".to_string()
+            } else {
+                format!(
+                    "Turn: {}",
+                    if four.game.turn() == CellState::X {
+                        "X"
+                    } else {
+                        "O"
+                    }
+                )
+            };
+            let turn_dim = measure_text(&turn_text, font, 24, 1.0);
+            draw_label(
+                &turn_text,
+                VIRTUAL_WIDTH - turn_dim.width - 20.0,
+                40.0,
+                24,
+                theme.text,
+            );
+
+            let rules_text = format!(
+                "Click a column to drop a piece | {} in a row wins",
+                four.game.position().win_length()
+            );
+            draw_label(&rules_text, 20.0, 92.0, 16, theme.muted);
+        }
+    }
+
+    // ---- Column Preview ----
+    // The column under the mouse glows, with the piece shown where it would land
+    let human_to_move = !four.game.is_over() && !four.is_ai_turn() && four.landing.is_none();
+    let hovered = four
+        .board
+        .get_cell_at(mouse)
+        .map(|(_, col)| col)
+        .filter(|_| human_to_move);
+    four.board.preview = hovered.and_then(|col| {
+        let row = four.game.landing_row(col)?;
+        Some(((row, col), four.game.turn()))
+    });
+    if let Some(col) = hovered {
+        let mut glow = side_color(four.game.turn());
+        glow.a = 0.1;
+        draw_rectangle(
+            four.board.x + col as f32 * four.board.cell_size,
+            four.board.y,
+            four.board.cell_size,
+            four.board.height(),
+            glow,
+        );
+    }
+
+    // ---- Draw Board ----
+    four.board.draw();
+    if four.landing.is_some() && !four.board.is_falling() {
+        four.land();
+    }
+
+    // ---- Moves ----
+    // Nobody moves until the last piece has landed
+    if four.landing.is_none() && four.is_ai_turn() {
+        if let Some(col) = four.ai_worker.drive(
+            &four.game,
+            four.ai.unwrap_or(Difficulty::Perfect),
+            connect_four::find_best_move,
+        ) {
+            four.play(col);
+        }
+    } else if let Some(col) = hovered
+        && is_mouse_button_pressed(MouseButton::Left)
+    {
+        four.play(col);
+    }
+
+    // ---- Controls ----
+    variant_controls(unsafe { &mut CONNECT_FOUR }, |four| {
+        ConnectFourGame::new(four.ai, four.player_symbol)
+    })
+}
@@ -1,15 +1,42 @@
-use crate::board::{Board, CellState};
+use crate::board::Board;
 use crate::config::*;
+use crate::net::{Message, NetEvent};
+use crate::notation::GameRecord;
+use crate::player::{AiPlayer, KeyboardHuman, MouseHuman, NetworkPlayer, Player, RandomPlayer};
 use crate::state::GameState;
+use crate::stats::Outcome;
 use crate::utils::*;
 use macroquad::prelude::*;
+use std::time::Duration;
+use tictactoe_core::ai::{DEFAULT_THINK_TIME, Difficulty, Engine};
+use tictactoe_core::position::{MAX_BOARD_SIZE, MIN_BOARD_SIZE, MIN_WIN_LENGTH};
+use tictactoe_core::rng::Rng;
+use tictactoe_core::{CellState, Game, Rule, Topology};
 
 static mut BOARD: Option<Board> = None;
 static mut CURRENT_TURN: CellState = CellState::X;
 static mut GAME_OVER: bool = false;
 static mut WINNER: Option<CellState> = None;
 static mut PLAYER_SYMBOL: CellState = CellState::X;
-static mut AI_TIMER: f32 = 0.0;
+static mut BOARD_SIZE: usize = 3;
+static mut WIN_LENGTH: usize = 3;
+static mut RULE: Rule = Rule::Standard;
+static mut TOPOLOGY: Topology = Topology::Flat;
+static mut THINK_TIME: Duration = DEFAULT_THINK_TIME;
+static mut ENGINE: Engine = Engine::Minimax;
+static mut KEYBOARD_INPUT: bool = false;
+// Whoever plays X and O, built from the mode when a game starts
+static mut PLAYERS: Option<[Box<dyn Player>; 2]> = None;
+static mut HISTORY: Vec<(usize, usize)> = Vec::new();
+static mut REDO_STACK: Vec<(usize, usize)> = Vec::new();
+static mut STATUS: Option<(String, f32)> = None; // message, seconds left
+static mut RESULT_RECORDED: bool = false;
+static mut RESIGNED: Option<CellState> = None;
+static mut REMATCH_OFFERS: (bool, bool) = (false, false); // (ours, opponent's)
+// The menu's board, rule and topology while an online game overrides them
+static mut MENU_SETUP: Option<(usize, usize, Rule, Topology)> = None;
+
+pub const SAVE_FILE: &str = "savegame.ttt";
 
 pub fn set_player_symbol(symbol: CellState) {
     unsafe {
@@ -17,15 +44,190 @@ pub fn set_player_symbol(symbol: CellState) {
     }
 }
 
+pub fn set_board_config(size: usize, win_length: usize) {
+    unsafe {
+        BOARD_SIZE = size.clamp(MIN_BOARD_SIZE, MAX_BOARD_SIZE);
+        WIN_LENGTH = win_length.clamp(MIN_WIN_LENGTH, BOARD_SIZE);
+        // Rebuilt with the new dimensions on the next update
+        BOARD = None;
+    }
+}
+
+pub fn board_config() -> (usize, usize) {
+    unsafe { (BOARD_SIZE, WIN_LENGTH) }
+}
+
+pub fn set_rule(rule: Rule) {
+    unsafe {
+        RULE = rule;
+        BOARD = None;
+    }
+}
+
+pub fn rule() -> Rule {
+    unsafe { RULE }
+}
+
+pub fn set_topology(topology: Topology) {
+    unsafe {
+        TOPOLOGY = topology;
+        BOARD = None;
+    }
+}
+
+pub fn topology() -> Topology {
+    unsafe { TOPOLOGY }
+}
+
+/// An empty board with the current size, win length, rule and topology.
+fn new_board() -> Board {
+    let mut board = unsafe { Board::new(BOARD_SIZE, WIN_LENGTH) };
+    board.position.set_rule(rule());
+    board.position.set_topology(topology());
+    board
+}
+
+pub fn set_think_time(think_time: Duration) {
+    unsafe {
+        THINK_TIME = think_time;
+    }
+}
+
+pub fn think_time() -> Duration {
+    unsafe { THINK_TIME }
+}
+
+pub fn set_engine(engine: Engine) {
+    unsafe {
+        ENGINE = engine;
+    }
+}
+
+pub fn engine() -> Engine {
+    unsafe { ENGINE }
+}
+
+/// Local players use the arrow keys and Enter instead of the mouse.
+pub fn set_keyboard_input(enabled: bool) {
+    unsafe {
+        KEYBOARD_INPUT = enabled;
+    }
+}
+
 pub fn is_game_over() -> bool {
     unsafe { GAME_OVER }
 }
 
+/// Loads a saved game and replays its moves. Returns the mode to switch to.
+#[allow(static_mut_refs)]
+pub fn load_game(path: &str) -> Result<GameState, String> {
+    let record = crate::notation::load(path)?;
+
+    // Make sure every move is legal before touching the live game
+    let mut scratch =
+        Game::with_rule(record.size, record.win_length, record.rule).with_topology(record.topology);
+    for &(row, col) in &record.moves {
+        scratch.play((row, col)).map_err(|e| {
+            let notation = crate::notation::format_move(row, col);
+            format!("move {notation} is illegal: {e}")
+        })?;
+    }
+
+    unsafe {
+        set_board_config(record.size, record.win_length);
+        set_rule(record.rule);
+        set_topology(record.topology);
+        PLAYER_SYMBOL = record.player_symbol;
+
+        let mut board = new_board();
+        reset_game(&mut board);
+        for &(row, col) in &record.moves {
+            commit_move(&mut board, row, col);
+        }
+        BOARD = Some(board);
+        // A game that was already finished when saved has been counted before
+        RESULT_RECORDED = GAME_OVER;
+
+        set_status(format!("Loaded {path}"));
+    }
+
+    match (record.mode, record.sides) {
+        (GameState::AiVsAi, Some(sides)) => {
+            crate::spectator::set_sides(sides);
+            Ok(GameState::AiVsAi)
+        }
+        // There's no connection to pick up again, so the game goes on here
+        (GameState::Online, _) => Ok(GameState::PvP),
+        (mode, _) => Ok(mode),
+    }
+}
+
+/// Starts a fresh game against a network opponent, playing `local_symbol`.
+#[allow(static_mut_refs)]
+pub fn start_online(size: usize, win_length: usize, local_symbol: CellState) {
+    unsafe {
+        MENU_SETUP.get_or_insert((BOARD_SIZE, WIN_LENGTH, RULE, TOPOLOGY));
+    }
+    set_board_config(size, win_length);
+    // The handshake only agrees on the board, so online games are standard
+    // and flat
+    set_rule(Rule::Standard);
+    set_topology(Topology::Flat);
+    unsafe {
+        PLAYER_SYMBOL = local_symbol;
+        let mut board = new_board();
+        reset_game(&mut board);
+        BOARD = Some(board);
+    }
+}
+
+/// Puts back the menu settings an online game replaced.
+#[allow(static_mut_refs)]
+fn end_online() {
+    if let Some((size, win_length, rule, topology)) = unsafe { MENU_SETUP.take() } {
+        set_board_config(size, win_length);
+        set_rule(rule);
+        set_topology(topology);
+    }
+}
+
+/// The game played so far, in the form used for saving and replays.
+#[allow(static_mut_refs)]
+pub fn current_record(mode: GameState) -> GameRecord {
+    unsafe {
+        GameRecord {
+            mode,
+            sides: (mode == GameState::AiVsAi).then(crate::spectator::sides),
+            player_symbol: PLAYER_SYMBOL,
+            size: BOARD_SIZE,
+            win_length: WIN_LENGTH,
+            rule: RULE,
+            topology: TOPOLOGY,
+            moves: HISTORY.clone(),
+        }
+    }
+}
+
+fn save_game(mode: GameState, path: &str) {
+    let record = current_record(mode);
+
+    match crate::notation::save(path, &record) {
+        Ok(()) => set_status(format!("Saved to {path}")),
+        Err(e) => set_status(format!("Save failed: {e}")),
+    }
+}
+
+fn set_status(message: String) {
+    unsafe {
+        STATUS = Some((message, 3.0));
+    }
+}
+
 #[allow(static_mut_refs)]
 pub fn update(mode: GameState, scale: &ScreenScale) -> Option<GameState> {
     let board = unsafe {
         if BOARD.is_none() {
-            BOARD = Some(Board::new());
+            BOARD = Some(new_board());
         }
         BOARD.as_mut().unwrap()
     };
@@ -34,13 +236,45 @@ pub fn update(mode: GameState, scale: &ScreenScale) -> Option<GameState> {
     let font = crate::config::get_font();
     let theme = crate::theme::get_current_theme();
 
+    let is_online = mode == GameState::Online;
+    if is_online {
+        unsafe {
+            handle_network(board);
+        }
+    }
+    // Taken after the network, since a rematch there starts a new game
+    let players = unsafe { PLAYERS.get_or_insert_with(|| create_players(mode)) };
+
     // ---- Draw UI ----
+    let mut mode_text = match mode {
+        GameState::PvP => "Player vs Player".to_string(),
+        GameState::PvAI(difficulty) if engine() == Engine::Minimax => {
+            format!("Player vs AI ({})", difficulty.name())
+        }
+        GameState::PvAI(difficulty) => {
+            format!("Player vs AI ({}, {})", difficulty.name(), engine().name())
+        }
+        GameState::Online => unsafe { format!("Online (you are {})", symbol_name(PLAYER_SYMBOL)) },
+        GameState::AiVsAi => {
+            let [(x_level, x_engine), (o_level, o_engine)] = crate::spectator::sides();
+            format!(
+                "AI vs AI: {} {} vs {} {}",
+                x_level.name(),
+                x_engine.name(),
+                o_level.name(),
+                o_engine.name()
+            )
+        }
+        _ => String::new(),
+    };
+    if rule() == Rule::Misere {
+        mode_text.push_str(" - Misere");
+    }
+    if topology() != Topology::Flat {
+        mode_text.push_str(&format!(" - {}", topology().name()));
+    }
     draw_text_ex(
-        match mode {
-            GameState::PvP => "Player vs Player",
-            GameState::PvAI => "Player vs AI",
-            _ => "",
-        },
+        &mode_text,
         20.0,
         40.0,
         TextParams {
@@ -51,8 +285,15 @@ pub fn update(mode: GameState, scale: &ScreenScale) -> Option<GameState> {
         },
     );
 
+    let help_text = if is_online {
+        "ESC: Leave | Q: Resign | R: Rematch | T: Theme | F5: Save"
+    } else if mode == GameState::AiVsAi {
+        "ESC: Menu | Space/N: Pause/Step | A: Auto-restart | R: Reset | T: Theme | F5/F9: Save/Load"
+    } else {
+        "ESC: Menu | R: Reset | T: Theme | Ctrl+Z/Y: Undo/Redo | F5/F9: Save/Load"
+    };
     draw_text_ex(
-        "ESC: Menu | R: Reset | T: Theme",
+        help_text,
         20.0,
         70.0,
         TextParams {
@@ -62,12 +303,36 @@ pub fn update(mode: GameState, scale: &ScreenScale) -> Option<GameState> {
             ..Default::default()
         },
     );
+    if !is_online && !is_game_over() {
+        let overlay_help = if crate::hints::overlay_shown() {
+            "H: Hint | E: Hide evaluations"
+        } else {
+            "H: Hint | E: Evaluate every move"
+        };
+        draw_text_ex(
+            overlay_help,
+            20.0,
+            92.0,
+            TextParams {
+                font,
+                font_size: 16,
+                color: theme.muted,
+                ..Default::default()
+            },
+        );
+    }
 
     unsafe {
         if GAME_OVER {
-            let msg = match WINNER {
-                Some(CellState::X) => "PLAYER X WINS!",
-                Some(CellState::O) => "PLAYER O WINS!",
+            // In misère the winner is whoever didn't complete the line
+            let misere = RULE == Rule::Misere;
+            let msg = match (RESIGNED, WINNER) {
+                (Some(CellState::X), _) => "X RESIGNED - O WINS!",
+                (Some(CellState::O), _) => "O RESIGNED - X WINS!",
+                (_, Some(CellState::X)) if misere => "O COMPLETED A LINE - X WINS!",
+                (_, Some(CellState::O)) if misere => "X COMPLETED A LINE - O WINS!",
+                (_, Some(CellState::X)) => "PLAYER X WINS!",
+                (_, Some(CellState::O)) => "PLAYER O WINS!",
                 _ => "IT'S A DRAW!",
             };
             let color = match WINNER {
@@ -90,7 +355,17 @@ pub fn update(mode: GameState, scale: &ScreenScale) -> Option<GameState> {
             );
 
             // RESTART MESSAGE
-            let restart_msg = "PRESS R TO RESTART";
+            let restart_msg = if !is_online {
+                "PRESS R TO RESTART, V TO REPLAY, G TO ANALYZE"
+            } else if !crate::net::is_connected() {
+                "OPPONENT GONE - ESC FOR MENU"
+            } else {
+                match REMATCH_OFFERS {
+                    (true, _) => "WAITING FOR OPPONENT...",
+                    (false, true) => "REMATCH OFFERED - PRESS R",
+                    (false, false) => "PRESS R FOR A REMATCH",
+                }
+            };
             let r_dim = measure_text(restart_msg, font, 24, 1.0);
             draw_text_ex(
                 restart_msg,
@@ -104,14 +379,21 @@ pub fn update(mode: GameState, scale: &ScreenScale) -> Option<GameState> {
                 },
             );
         } else {
-            let turn_text = match CURRENT_TURN {
-                CellState::X => "Turn: X",
-                CellState::O => "Turn: O",
-                _ => "",
+            let turn_text = if is_online && !crate::net::is_connected() {
+                "Disconnected"
+            } else if players[side_index(CURRENT_TURN)].is_thinking() {
+                "AI is thinking..."
+            } else {
+                match CURRENT_TURN {
+                    CellState::X => "Turn: X",
+                    CellState::O => "Turn: O",
+                    _ => "",
+                }
             };
+            let turn_dim = measure_text(turn_text, font, 24, 1.0);
             draw_text_ex(
                 turn_text,
-                VIRTUAL_WIDTH - 120.0,
+                VIRTUAL_WIDTH - turn_dim.width - 20.0,
                 40.0,
                 TextParams {
                     font,
@@ -123,33 +405,126 @@ pub fn update(mode: GameState, scale: &ScreenScale) -> Option<GameState> {
         }
     }
 
+    // Save / load feedback
+    unsafe {
+        if let Some((message, time_left)) = &mut STATUS {
+            *time_left -= get_frame_time();
+            let mut color = theme.muted;
+            color.a = time_left.clamp(0.0, 1.0);
+
+            let dim = measure_text(message, font, 18, 1.0);
+            draw_text_ex(
+                message,
+                VIRTUAL_WIDTH / 2.0 - dim.width / 2.0,
+                VIRTUAL_HEIGHT - 15.0,
+                TextParams {
+                    font,
+                    font_size: 18,
+                    color,
+                    ..Default::default()
+                },
+            );
+
+            if *time_left <= 0.0 {
+                STATUS = None;
+            }
+        }
+    }
+
     // ---- Draw Board ----
+    // No hints online, where they'd be help from outside the game
+    let hint_side = (!is_online && !is_game_over()).then_some(unsafe { CURRENT_TURN });
+    crate::hints::update(board, hint_side);
     board.draw();
 
     // ---- Gameplay Logic ----
     unsafe {
-        if !GAME_OVER {
-            let is_ai_mode = mode == GameState::PvAI;
-            let is_ai_turn = is_ai_mode && CURRENT_TURN != PLAYER_SYMBOL;
+        // Nobody moves online once the connection is gone, and spectated
+        // games wait for their speed setting or a step
+        let may_move = if is_online {
+            crate::net::is_connected()
+        } else {
+            mode != GameState::AiVsAi || crate::spectator::may_move()
+        };
+        if !GAME_OVER && may_move {
+            let side = side_index(CURRENT_TURN);
+            players[side].draw(board, CURRENT_TURN);
+            if let Some((row, col)) = players[side].poll_move(board, CURRENT_TURN, mouse)
+                && board.position.contains(row, col)
+                && board.state(row, col) == CellState::Empty
+            {
+                make_move(board, row, col);
+                players[1 - side].opponent_moved((row, col));
+                crate::spectator::moved();
+            }
+        }
+    }
 
-            if is_ai_turn {
-                AI_TIMER += get_frame_time();
-                if AI_TIMER >= 0.6 {
-                    // Artificial delay for feel
-                    if let Some((row, col)) = crate::ai::find_best_move(board, CURRENT_TURN) {
-                        apply_move(board, row, col);
-                    }
-                    AI_TIMER = 0.0;
-                }
-            } else {
-                if is_mouse_button_pressed(MouseButton::Left) {
-                    if let Some((row, col)) = board.get_cell_at(mouse) {
-                        if board.cells[row][col].state == CellState::Empty {
-                            apply_move(board, row, col);
-                        }
+    // Count each finished game once, even if it gets undone and replayed
+    unsafe {
+        if GAME_OVER && !RESULT_RECORDED && mode == GameState::AiVsAi {
+            // Engine matches only count towards the score on screen
+            crate::spectator::record_result(WINNER);
+            RESULT_RECORDED = true;
+        } else if GAME_OVER && !RESULT_RECORDED {
+            let outcome = match WINNER {
+                None => Outcome::Draw,
+                // In PvP, wins and losses are counted from X's side
+                Some(winner) if mode == GameState::PvP => {
+                    if winner == CellState::X {
+                        Outcome::Win
+                    } else {
+                        Outcome::Loss
                     }
                 }
-            }
+                Some(winner) if winner == PLAYER_SYMBOL => Outcome::Win,
+                Some(_) => Outcome::Loss,
+            };
+            crate::stats::record_game(
+                &crate::stats::mode_key(mode, PLAYER_SYMBOL, RULE, TOPOLOGY),
+                outcome,
+                HISTORY.len(),
+            );
+            RESULT_RECORDED = true;
+        }
+    }
+
+    // Undo / redo
+    let ctrl_down = is_key_down(KeyCode::LeftControl)
+        || is_key_down(KeyCode::RightControl)
+        || is_key_down(KeyCode::LeftSuper)
+        || is_key_down(KeyCode::RightSuper);
+    if !is_online && ctrl_down && is_key_pressed(KeyCode::Z) {
+        unsafe {
+            undo(board);
+        }
+    }
+    if !is_online && ctrl_down && is_key_pressed(KeyCode::Y) {
+        unsafe {
+            redo(board);
+        }
+    }
+
+    // Watch the finished game again
+    if is_game_over() && is_key_pressed(KeyCode::V) {
+        crate::replay::start(&current_record(mode), mode);
+        return Some(GameState::Replay);
+    }
+
+    // Go over the finished game move by move
+    if is_game_over() && is_key_pressed(KeyCode::G) {
+        crate::analysis::start(current_record(mode), mode);
+        return Some(GameState::Analysis);
+    }
+
+    // Save / load
+    if is_key_pressed(KeyCode::F5) {
+        save_game(mode, SAVE_FILE);
+    }
+    if !is_online && is_key_pressed(KeyCode::F9) {
+        match load_game(SAVE_FILE) {
+            Ok(loaded_mode) => return Some(loaded_mode),
+            Err(e) => set_status(format!("Load failed: {e}")),
         }
     }
 
@@ -158,24 +533,45 @@ pub fn update(mode: GameState, scale: &ScreenScale) -> Option<GameState> {
         crate::theme::cycle_theme();
     }
 
-    // Reset game
-    if is_key_pressed(KeyCode::R) {
-        board.reset();
+    if is_online {
         unsafe {
-            GAME_OVER = false;
-            WINNER = None;
-            CURRENT_TURN = CellState::X;
-            AI_TIMER = 0.0;
+            // Resign
+            if is_key_pressed(KeyCode::Q) && !GAME_OVER && crate::net::is_connected() {
+                crate::net::send(&Message::Resign);
+                resign(PLAYER_SYMBOL);
+            }
+
+            // Offer (or accept) a rematch
+            if is_key_pressed(KeyCode::R)
+                && GAME_OVER
+                && !REMATCH_OFFERS.0
+                && crate::net::is_connected()
+            {
+                crate::net::send(&Message::Rematch);
+                REMATCH_OFFERS.0 = true;
+                start_rematch_if_agreed(board);
+            }
+        }
+    } else if is_key_pressed(KeyCode::R) {
+        // Reset game
+        unsafe {
+            reset_game(board);
+        }
+    }
+
+    if mode == GameState::AiVsAi && crate::spectator::update(is_game_over(), mouse) {
+        unsafe {
+            reset_game(board);
         }
     }
 
     if is_key_pressed(KeyCode::Escape) {
-        board.reset();
         unsafe {
-            GAME_OVER = false;
-            WINNER = None;
-            CURRENT_TURN = CellState::X;
-            AI_TIMER = 0.0;
+            reset_game(board);
+        }
+        if is_online {
+            crate::net::close();
+            end_online();
         }
         return Some(GameState::Menu);
     }
@@ -183,22 +579,235 @@ pub fn update(mode: GameState, scale: &ScreenScale) -> Option<GameState> {
     None
 }
 
-unsafe fn apply_move(board: &mut Board, row: usize, col: usize) {
+/// Applies everything the opponent sent since the last frame.
+unsafe fn handle_network(board: &mut Board) {
+    while let Some(event) = crate::net::receive() {
+        match event {
+            NetEvent::Message(Message::Move(row, col)) => unsafe {
+                let legal = !GAME_OVER
+                    && CURRENT_TURN != PLAYER_SYMBOL
+                    && !crate::player::remote_move_pending()
+                    && board.position.contains(row, col)
+                    && board.state(row, col) == CellState::Empty;
+                if legal {
+                    crate::player::deliver_remote_move((row, col));
+                } else {
+                    crate::net::abort("Illegal move");
+                    set_status("Opponent made an illegal move".to_string());
+                }
+            },
+            NetEvent::Message(Message::Resign) => unsafe {
+                if !GAME_OVER {
+                    resign(PLAYER_SYMBOL.opponent());
+                }
+            },
+            NetEvent::Message(Message::Rematch) => unsafe {
+                REMATCH_OFFERS.1 = true;
+                start_rematch_if_agreed(board);
+            },
+            NetEvent::Message(Message::Bye) => set_status("Opponent left".to_string()),
+            NetEvent::Message(Message::Error(text)) => set_status(format!("Opponent: {text}")),
+            NetEvent::Message(Message::Hello { .. }) => {
+                crate::net::abort("Unexpected HELLO");
+            }
+            NetEvent::Disconnected(reason) => set_status(reason),
+        }
+    }
+}
+
+unsafe fn resign(symbol: CellState) {
     unsafe {
-        board.set_cell(row, col, CURRENT_TURN);
+        RESIGNED = Some(symbol);
+        WINNER = Some(symbol.opponent());
+        GAME_OVER = true;
+    }
+}
+
+/// Once both sides asked for a rematch, swap sides and start over so the
+/// other player gets to move first.
+unsafe fn start_rematch_if_agreed(board: &mut Board) {
+    unsafe {
+        if GAME_OVER && REMATCH_OFFERS == (true, true) {
+            PLAYER_SYMBOL = PLAYER_SYMBOL.opponent();
+            reset_game(board);
+        }
+    }
+}
+
+fn symbol_name(symbol: CellState) -> &'static str {
+    if symbol == CellState::O { "O" } else { "X" }
+}
+
+#[allow(static_mut_refs)]
+unsafe fn reset_game(board: &mut Board) {
+    board.reset();
+    unsafe {
+        GAME_OVER = false;
+        WINNER = None;
+        CURRENT_TURN = CellState::X;
+        // Rebuilt for the next game, which may have swapped sides or settings
+        PLAYERS = None;
+        crate::player::clear_remote_moves();
+        HISTORY.clear();
+        REDO_STACK.clear();
+        RESULT_RECORDED = false;
+        RESIGNED = None;
+        REMATCH_OFFERS = (false, false);
+    }
+}
+
+/// Builds the two sides for `mode`, X first.
+unsafe fn create_players(mode: GameState) -> [Box<dyn Player>; 2] {
+    let (size, player_symbol) = unsafe { (BOARD_SIZE, PLAYER_SYMBOL) };
+    let human = || -> Box<dyn Player> {
+        if unsafe { KEYBOARD_INPUT } {
+            Box::new(KeyboardHuman::new(size))
+        } else {
+            Box::new(MouseHuman)
+        }
+    };
+    let opponent: Box<dyn Player> = match mode {
+        GameState::PvAI(difficulty) => ai_player(difficulty, engine()),
+        GameState::Online => Box::new(NetworkPlayer),
+        GameState::AiVsAi => {
+            let [(x_level, x_engine), (o_level, o_engine)] = crate::spectator::sides();
+            return [ai_player(x_level, x_engine), ai_player(o_level, o_engine)];
+        }
+        _ => human(),
+    };
+
+    if player_symbol == CellState::O {
+        [opponent, human()]
+    } else {
+        [human(), opponent]
+    }
+}
+
+fn ai_player(difficulty: Difficulty, engine: Engine) -> Box<dyn Player> {
+    if difficulty == Difficulty::Easy {
+        Box::new(RandomPlayer::new(Rng::from_time()))
+    } else {
+        Box::new(AiPlayer::new(
+            difficulty,
+            engine,
+            think_time(),
+            Rng::from_time(),
+        ))
     }
+}
+
+fn side_index(symbol: CellState) -> usize {
+    if symbol == CellState::O { 1 } else { 0 }
+}
+
+/// A fresh move from a player or the AI. Anything that was undone before is
+/// no longer reachable, so the redo stack is dropped.
+#[allow(static_mut_refs)]
+unsafe fn make_move(board: &mut Board, row: usize, col: usize) {
+    unsafe {
+        REDO_STACK.clear();
+        apply_move(board, row, col);
+    }
+}
+
+/// Takes back the last move. Moves by anyone other than a local human (an
+/// AI reply, say) are taken back too, until a human's own move comes off.
+#[allow(static_mut_refs)]
+unsafe fn undo(board: &mut Board) {
+    unsafe {
+        let Some(players) = PLAYERS.as_mut() else {
+            return;
+        };
+        let human_moved = HISTORY
+            .iter()
+            .any(|&(row, col)| players[side_index(board.state(row, col))].is_local_human());
+        if !human_moved {
+            return;
+        }
+
+        for player in players.iter_mut() {
+            player.cancel();
+        }
+        while let Some((row, col)) = HISTORY.pop() {
+            let symbol = board.state(row, col);
+            board.clear_cell(row, col);
+            REDO_STACK.push((row, col));
+
+            // The side whose piece came off is the one to move again
+            CURRENT_TURN = symbol;
+            GAME_OVER = false;
+            WINNER = None;
+
+            if players[side_index(symbol)].is_local_human() {
+                break;
+            }
+        }
+    }
+}
+
+/// Replays undone moves through `apply_move`, mirroring `undo`: it stops
+/// once it is a local human's turn again.
+#[allow(static_mut_refs)]
+unsafe fn redo(board: &mut Board) {
+    unsafe {
+        let Some(players) = PLAYERS.as_mut() else {
+            return;
+        };
+        for player in players.iter_mut() {
+            player.cancel();
+        }
+        while !GAME_OVER && let Some((row, col)) = REDO_STACK.pop() {
+            apply_move(board, row, col);
+
+            if players[side_index(CURRENT_TURN)].is_local_human() {
+                break;
+            }
+        }
+    }
+}
+
+unsafe fn apply_move(board: &mut Board, row: usize, col: usize) {
+    let mover = unsafe { CURRENT_TURN };
+    let won = unsafe { commit_move(board, row, col) };
     crate::config::play_move();
 
     // Spawn move particles
     let center = board.get_cell_center(row, col);
-    let color = unsafe {
-        if CURRENT_TURN == CellState::X {
+    let color = if mover == CellState::X {
+        crate::theme::get_current_theme().primary
+    } else {
+        crate::theme::get_current_theme().secondary
+    };
+    crate::particles::spawn_move(center, color);
+
+    if won {
+        crate::config::play_win();
+
+        // In the winner's color, which in misère isn't the mover's
+        let color = if unsafe { WINNER } == Some(CellState::X) {
             crate::theme::get_current_theme().primary
         } else {
             crate::theme::get_current_theme().secondary
+        };
+
+        // Spawn win particles for all winning cells
+        if let Some(winning_coords) = &board.winning_cells {
+            for &coords in winning_coords {
+                let win_center = board.get_cell_center(coords.0, coords.1);
+                crate::particles::spawn_win(win_center, color);
+            }
         }
-    };
-    crate::particles::spawn_move(center, color);
+    }
+}
+
+/// Places the current player's piece and updates turn, winner and game over,
+/// without any sound or particles. Returns true if the move won the game.
+#[allow(static_mut_refs)]
+unsafe fn commit_move(board: &mut Board, row: usize, col: usize) -> bool {
+    unsafe {
+        board.set_cell(row, col, CURRENT_TURN);
+        HISTORY.push((row, col));
+    }
 
     // Check winner/draw
     if let Some(winner) = board.check_winner() {
@@ -206,27 +815,16 @@ unsafe fn apply_move(board: &mut Board, row: usize, col: usize) {
             WINNER = Some(winner);
             GAME_OVER = true;
         }
-        crate::config::play_win();
-
-        // Spawn win particles for all winning cells
-        if let Some(winning_coords) = board.winning_cells {
-            for coords in winning_coords {
-                let win_center = board.get_cell_center(coords.0, coords.1);
-                crate::particles::spawn_win(win_center, color);
-            }
-        }
-    } else if board.is_full() {
+        return true;
+    } else if board.position.is_full() {
         unsafe {
             GAME_OVER = true;
         }
     } else {
         // Switch turn
         unsafe {
-            CURRENT_TURN = if CURRENT_TURN == CellState::X {
-                CellState::O
-            } else {
-                CellState::X
-            };
+            CURRENT_TURN = CURRENT_TURN.opponent();
         }
     }
+    false
 }
@@ -0,0 +1,372 @@
+use crate::board::Board;
+use crate::config::*;
+use crate::player::AiWorker;
+use crate::state::GameState;
+use crate::utils::*;
+use macroquad::prelude::*;
+use tictactoe_core::ai::Difficulty;
+use tictactoe_core::gomoku::{self, FiveRule, Gomoku, Opening, SIZE, WIN_LENGTH};
+use tictactoe_core::rng::Rng;
+use tictactoe_core::{CellState, Move, Outcome};
+
+// The part of the screen the board shows through, where the normal board goes
+const VIEW: Rect = Rect {
+    x: 175.0,
+    y: 105.0,
+    w: 450.0,
+    h: 450.0,
+};
+
+// Cell sizes from the whole board in view up to a few cells across
+const MIN_CELL_SIZE: f32 = VIEW.w / SIZE as f32;
+const MAX_CELL_SIZE: f32 = 75.0;
+const ZOOM_STEP: f32 = 1.2;
+const PAN_SPEED: f32 = 400.0; // virtual pixels per second, for the arrow keys
+
+// Swap2 choice buttons, down the right of the board
+const CHOICE_X: f32 = 640.0;
+const CHOICE_Y: f32 = 200.0;
+const CHOICE_WIDTH: f32 = 145.0;
+const CHOICE_HEIGHT: f32 = 44.0;
+const CHOICE_STEP: f32 = 56.0;
+
+// Rules for the next game
+static mut RULES: (FiveRule, Opening) = (FiveRule::Freestyle, Opening::Free);
+
+struct GomokuGame {
+    game: Gomoku,
+    board: Board,
+    ai: Option<Difficulty>,
+    human: usize, // the human's player number against the AI; 0 places first
+    ai_worker: AiWorker<Move>,
+    drag_from: Option<Vec2>, // last mouse position while panning with a drag
+}
+
+impl GomokuGame {
+    fn new(ai: Option<Difficulty>, human: usize) -> Self {
+        let (five_rule, opening) = rules();
+        let mut board = Board::new(SIZE, WIN_LENGTH);
+        board.set_layout(VIEW.x, VIEW.y, VIEW.w);
+        Self {
+            game: Gomoku::new(five_rule, opening),
+            board,
+            ai,
+            human,
+            ai_worker: AiWorker::new(Rng::from_time()),
+            drag_from: None,
+        }
+    }
+
+    fn is_ai_turn(&self) -> bool {
+        self.ai.is_some() && !self.game.is_over() && self.game.mover() != self.human
+    }
+
+    fn player_name(&self, player: usize) -> &'static str {
+        match (self.ai, player == self.human) {
+            (Some(_), true) => "You",
+            (Some(_), false) => "AI",
+            (None, _) if player == 0 => "Player 1",
+            (None, _) => "Player 2",
+        }
+    }
+
+    /// Places a legal stone. Particles only show for cells in view.
+    fn play(&mut self, (row, col): Move) {
+        let mover = self.game.turn();
+        if self.game.play((row, col)).is_err() {
+            return;
+        }
+
+        self.board.set_cell(row, col, mover);
+        self.board.winning_cells = match self.game.outcome() {
+            Some(Outcome::Win(_, line)) => Some(line.clone()),
+            _ => None,
+        };
+
+        let theme = crate::theme::get_current_theme();
+        let color = if mover == CellState::X {
+            theme.primary
+        } else {
+            theme.secondary
+        };
+        crate::config::play_move();
+        let center = self.board.get_cell_center(row, col);
+        if VIEW.contains(center) {
+            crate::particles::spawn_move(center, color);
+        }
+
+        if let Some(Outcome::Win(_, line)) = self.game.outcome() {
+            crate::config::play_win();
+            for &(row, col) in line {
+                let center = self.board.get_cell_center(row, col);
+                if VIEW.contains(center) {
+                    crate::particles::spawn_win(center, color);
+                }
+            }
+        }
+    }
+
+    /// Scales the board by `factor` about `anchor`, which stays put.
+    fn zoom(&mut self, anchor: Vec2, factor: f32) {
+        let old = self.board.cell_size;
+        let new = (old * factor).clamp(MIN_CELL_SIZE, MAX_CELL_SIZE);
+        let origin = anchor - (anchor - vec2(self.board.x, self.board.y)) * (new / old);
+        self.board.set_layout(origin.x, origin.y, new * SIZE as f32);
+        self.pan(Vec2::ZERO);
+    }
+
+    /// Moves the board by `delta`, never so far that it stops filling the view.
+    fn pan(&mut self, delta: Vec2) {
+        let size = self.board.width();
+        self.board.x = (self.board.x + delta.x).clamp(VIEW.right() - size, VIEW.x);
+        self.board.y = (self.board.y + delta.y).clamp(VIEW.bottom() - size, VIEW.y);
+    }
+}
+
+static mut GOMOKU: Option<GomokuGame> = None;
+
+/// Line rule and opening for the next game.
+pub fn rules() -> (FiveRule, Opening) {
+    unsafe { RULES }
+}
+
+pub fn set_rules(five_rule: FiveRule, opening: Opening) {
+    unsafe {
+        RULES = (five_rule, opening);
+    }
+}
+
+/// Starts a new game with the current rules, against the AI at `ai` or
+/// between two people. `human` is 0 to place first against the AI, 1 to
+/// place second.
+pub fn start(ai: Option<Difficulty>, human: usize) {
+    unsafe {
+        GOMOKU = Some(GomokuGame::new(ai, human));
+    }
+}
+
+#[allow(static_mut_refs)]
+pub fn update(scale: &ScreenScale) -> Option<GameState> {
+    let Some(gomoku) = (unsafe { GOMOKU.as_mut() }) else {
+        return Some(GameState::Menu);
+    };
+
+    let mouse = mouse_to_virtual(scale);
+    let font = crate::config::get_font();
+    let theme = crate::theme::get_current_theme();
+    let side_color = |side: CellState| {
+        if side == CellState::X {
+            theme.primary
+        } else {
+            theme.secondary
+        }
+    };
+
+    // ---- Draw UI ----
+    let mode_text = match gomoku.ai {
+        Some(difficulty) => format!("Gomoku vs AI ({})", difficulty.name()),
+        None => "Gomoku: Player vs Player".to_string(),
+    };
+    draw_label(&mode_text, 20.0, 40.0, 24, theme.text);
+    draw_label(
+        "ESC: Menu | R: Reset | T: Theme | Wheel: Zoom | Right-drag/Arrows: Pan | F: Fit",
+        20.0,
+        70.0,
+        18,
+        theme.muted,
+    );
+
+    match gomoku.game.outcome() {
+        Some(outcome) => {
+            let (msg, color) = match outcome {
+                Outcome::Win(winner, _) => {
+                    let player = gomoku.game.player_of(*winner).unwrap_or(0);
+                    let msg = if gomoku.ai.is_some() && player == gomoku.human {
+                        "YOU WIN!".to_string()
+                    } else {
+                        format!("{} WINS!", gomoku.player_name(player).to_uppercase())
+                    };
+                    (msg, side_color(*winner))
+                }
+                Outcome::Draw => ("IT'S A DRAW!".to_string(), theme.muted),
+            };
+            draw_end_banner(&msg, color);
+        }
+        None => {
+            let mover = gomoku.game.mover();
+            let turn_text = if gomoku.ai_worker.is_thinking() {
+                "AI is thinking...".to_string()
+            } else if gomoku.ai.is_some() && mover == gomoku.human {
+                "Your turn".to_string()
+            } else {
+                format!("Turn: {}", gomoku.player_name(mover))
+            };
+            let turn_dim = measure_text(&turn_text, font, 24, 1.0);
+            draw_label(
+                &turn_text,
+                VIRTUAL_WIDTH - turn_dim.width - 20.0,
+                40.0,
+                24,
+                theme.text,
+            );
+
+            let stones = gomoku.game.moves().len();
+            let hint = match (gomoku.game.opening(), stones) {
+                _ if !gomoku.game.choices().is_empty() => "Choose a color, or place two more",
+                (Opening::Pro, 0) => "X's first stone goes in the center",
+                (Opening::Pro, 2) => "X's second stone goes 3 or more cells from the center",
+                (Opening::Swap2, 0..3) => "Place the first three stones: X, O, X",
+                (Opening::Swap2, 3..5) if gomoku.game.player_of(CellState::X).is_none() => {
+                    "Place two more stones: O, X"
+                }
+                _ => "",
+            };
+            let rules_text = format!(
+                "{} wins | {} opening",
+                gomoku.game.five_rule().name(),
+                gomoku.game.opening().name(),
+            );
+            let line = if hint.is_empty() {
+                rules_text
+            } else {
+                format!("{rules_text} | {hint}")
+            };
+            draw_label(&line, 20.0, 92.0, 16, theme.muted);
+        }
+    }
+
+    // ---- Players ----
+    // Colors are only known once a swap2 opening settles them
+    for player in 0..2 {
+        let side = [CellState::X, CellState::O]
+            .into_iter()
+            .find(|&side| gomoku.game.player_of(side) == Some(player));
+        let (symbol, color) = match side {
+            Some(CellState::X) => ("X", theme.primary),
+            Some(_) => ("O", theme.secondary),
+            None => ("?", theme.muted),
+        };
+        let y = 200.0 + player as f32 * 40.0;
+        draw_label(gomoku.player_name(player), 20.0, y, 22, theme.text);
+        draw_label(symbol, 130.0, y, 22, color);
+    }
+
+    // ---- View Controls ----
+    let (_, wheel) = mouse_wheel();
+    if wheel != 0.0 && VIEW.contains(mouse) {
+        let factor = if wheel > 0.0 {
+            ZOOM_STEP
+        } else {
+            1.0 / ZOOM_STEP
+        };
+        gomoku.zoom(mouse, factor);
+    }
+    let dragging =
+        is_mouse_button_down(MouseButton::Right) || is_mouse_button_down(MouseButton::Middle);
+    gomoku.drag_from = match gomoku.drag_from {
+        Some(from) if dragging => {
+            gomoku.pan(mouse - from);
+            Some(mouse)
+        }
+        None if dragging && VIEW.contains(mouse) => Some(mouse),
+        _ => None,
+    };
+    let mut arrows = Vec2::ZERO;
+    if is_key_down(KeyCode::Left) {
+        arrows.x += 1.0;
+    }
+    if is_key_down(KeyCode::Right) {
+        arrows.x -= 1.0;
+    }
+    if is_key_down(KeyCode::Up) {
+        arrows.y += 1.0;
+    }
+    if is_key_down(KeyCode::Down) {
+        arrows.y -= 1.0;
+    }
+    gomoku.pan(arrows * PAN_SPEED * get_frame_time());
+    if is_key_pressed(KeyCode::F) {
+        gomoku.board.set_layout(VIEW.x, VIEW.y, VIEW.w);
+    }
+
+    // ---- Draw Board ----
+    let human_to_move = !gomoku.game.is_over() && !gomoku.is_ai_turn();
+    let hovered = gomoku
+        .board
+        .get_cell_at(mouse)
+        .filter(|&cell| VIEW.contains(mouse) && gomoku.game.is_allowed(cell));
+    gomoku.board.preview = hovered
+        .filter(|_| human_to_move)
+        .map(|cell| (cell, gomoku.game.turn()));
+
+    clip_to(scale, Some(VIEW));
+    gomoku.board.draw();
+    clip_to(scale, None);
+    let mut frame = theme.muted;
+    frame.a = 0.5;
+    draw_rectangle_lines(VIEW.x, VIEW.y, VIEW.w, VIEW.h, 2.0, frame);
+
+    // ---- Swap2 Choices ----
+    let choices = if human_to_move {
+        gomoku.game.choices()
+    } else {
+        &[]
+    };
+    let mut chosen = None;
+    for (i, &choice) in choices.iter().enumerate() {
+        let rect = Rect::new(
+            CHOICE_X,
+            CHOICE_Y + i as f32 * CHOICE_STEP,
+            CHOICE_WIDTH,
+            CHOICE_HEIGHT,
+        );
+        let hover = rect.contains(mouse);
+        let mut fill = theme.text;
+        fill.a = if hover { 0.15 } else { 0.06 };
+        draw_rectangle(rect.x, rect.y, rect.w, rect.h, fill);
+        draw_rectangle_lines(rect.x, rect.y, rect.w, rect.h, 2.0, theme.text);
+        let dim = measure_text(choice.name(), font, 18, 1.0);
+        draw_label(
+            choice.name(),
+            rect.x + rect.w / 2.0 - dim.width / 2.0,
+            rect.y + rect.h / 2.0 + dim.height / 2.0,
+            18,
+            theme.text,
+        );
+        if hover && is_mouse_button_pressed(MouseButton::Left) {
+            chosen = Some(choice);
+        }
+    }
+    if let Some(choice) = chosen {
+        crate::config::play_click();
+        let _ = gomoku.game.choose(choice);
+    }
+
+    // ---- Moves ----
+    if gomoku.is_ai_turn() {
+        if !gomoku.game.choices().is_empty() {
+            // Choosing is just a look at the position, no search needed
+            if let Some(choice) = gomoku::find_best_choice(&gomoku.game) {
+                let _ = gomoku.game.choose(choice);
+            }
+        } else {
+            if let Some(mv) = gomoku.ai_worker.drive(
+                &gomoku.game,
+                gomoku.ai.unwrap_or(Difficulty::Perfect),
+                gomoku::find_best_move,
+            ) {
+                gomoku.play(mv);
+            }
+        }
+    } else if human_to_move
+        && let Some(cell) = hovered
+        && is_mouse_button_pressed(MouseButton::Left)
+    {
+        gomoku.play(cell);
+    }
+
+    // ---- Controls ----
+    variant_controls(unsafe { &mut GOMOKU }, |gomoku| {
+        GomokuGame::new(gomoku.ai, gomoku.human)
+    })
+}
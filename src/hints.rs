@@ -0,0 +1,106 @@
+use crate::board::Board;
+use macroquad::prelude::*;
+use std::sync::mpsc::{self, Receiver};
+use tictactoe_core::ai::{self, Difficulty, Evaluation};
+use tictactoe_core::rng::Rng;
+use tictactoe_core::{CellState, Move, Position};
+
+// Searches run on worker threads, like the AI's, and report back here
+struct Hints {
+    position: Option<Position>, // what the hint and evaluations are for
+    hint_wanted: bool,
+    overlay: bool,
+    hint_search: Option<Receiver<Option<Move>>>,
+    overlay_search: Option<Receiver<Vec<(Move, Evaluation)>>>,
+}
+
+static mut HINTS: Hints = Hints {
+    position: None,
+    hint_wanted: false,
+    overlay: false,
+    hint_search: None,
+    overlay_search: None,
+};
+
+pub fn overlay_shown() -> bool {
+    unsafe { HINTS.overlay }
+}
+
+/// Handles H (hint for this turn) and E (evaluation overlay on or off) and
+/// puts finished results on `board` for `Board::draw`. `side` is whoever
+/// moves next; `None` once the game is over or hints aren't allowed.
+#[allow(static_mut_refs)]
+pub fn update(board: &mut Board, side: Option<CellState>) {
+    let hints = unsafe { &mut HINTS };
+
+    // A hint is only for the turn it was asked on
+    if hints.position.as_ref() != Some(&board.position) {
+        hints.position = Some(board.position.clone());
+        hints.hint_wanted = false;
+        hints.hint_search = None;
+        hints.overlay_search = None;
+        board.hint = None;
+        board.evaluations.clear();
+    }
+
+    let Some(side) = side else {
+        hints.hint_search = None;
+        hints.overlay_search = None;
+        board.hint = None;
+        board.evaluations.clear();
+        return;
+    };
+
+    if is_key_pressed(KeyCode::H) {
+        hints.hint_wanted = true;
+    }
+    if is_key_pressed(KeyCode::E) {
+        hints.overlay = !hints.overlay;
+        if !hints.overlay {
+            hints.overlay_search = None;
+            board.evaluations.clear();
+        }
+    }
+
+    let think_time = crate::game::think_time();
+    if hints.hint_wanted && board.hint.is_none() && hints.hint_search.is_none() {
+        let position = board.position.clone();
+        let engine = crate::game::engine();
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            let mut rng = Rng::from_time();
+            let best_move = ai::find_best_move(
+                &position,
+                side,
+                Difficulty::Perfect,
+                engine,
+                think_time,
+                &mut rng,
+            );
+            let _ = tx.send(best_move);
+        });
+        hints.hint_search = Some(rx);
+    }
+    if hints.overlay && board.evaluations.is_empty() && hints.overlay_search.is_none() {
+        let position = board.position.clone();
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            let _ = tx.send(ai::evaluate_moves(&position, side, think_time));
+        });
+        hints.overlay_search = Some(rx);
+    }
+
+    if let Some(search) = &hints.hint_search
+        && let Ok(best_move) = search.try_recv()
+    {
+        board.hint = best_move;
+        hints.hint_wanted = false;
+        hints.hint_search = None;
+    }
+    if let Some(search) = &hints.overlay_search
+        && let Ok(evaluations) = search.try_recv()
+    {
+        board.evaluations = evaluations;
+        hints.overlay_search = None;
+    }
+}
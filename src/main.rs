@@ -2,16 +2,30 @@
 
 use macroquad::prelude::*;
 
-mod ai;
+mod analysis;
 mod background;
 mod board;
 mod config;
+mod connect_four;
 mod game;
+mod gomoku;
+mod hints;
 mod menu;
+mod net;
+mod notation;
+mod order_chaos;
 mod particles;
+mod player;
+mod qubic;
+mod replay;
+mod spectator;
 mod state;
+mod stats;
 mod theme;
+mod three_piece;
+mod ultimate;
 mod utils;
+mod wild;
 
 use config::*;
 use state::GameState;
@@ -39,6 +53,56 @@ async fn main() {
     config::load_sounds().await;
 
     let mut game_state = GameState::Menu;
+
+    // `--load <file>` jumps straight into a saved game
+    let args: Vec<String> = std::env::args().collect();
+    if let Some(index) = args.iter().position(|arg| arg == "--load") {
+        match args.get(index + 1) {
+            Some(path) => match game::load_game(path) {
+                Ok(mode) => game_state = mode,
+                Err(e) => eprintln!("Could not load game: {e}"),
+            },
+            None => eprintln!("--load needs a file path"),
+        }
+    }
+
+    // `--keyboard` plays local games with the arrow keys and Enter
+    if args.iter().any(|arg| arg == "--keyboard") {
+        game::set_keyboard_input(true);
+    }
+
+    // `--host [port]` and `--join <address>` go straight to the online lobby
+    if let Some(index) = args.iter().position(|arg| arg == "--host") {
+        let port = args
+            .get(index + 1)
+            .and_then(|port| port.parse().ok())
+            .unwrap_or(net::DEFAULT_PORT);
+        let (size, win_length) = game::board_config();
+        match net::host(port, size, win_length, tictactoe_core::CellState::X) {
+            Ok(()) => game_state = GameState::OnlineLobby,
+            Err(e) => eprintln!("{e}"),
+        }
+    }
+    if let Some(index) = args.iter().position(|arg| arg == "--join") {
+        match args.get(index + 1) {
+            Some(address) => match net::join(address) {
+                Ok(()) => game_state = GameState::OnlineLobby,
+                Err(e) => eprintln!("{e}"),
+            },
+            None => eprintln!("--join needs an address"),
+        }
+    }
+
+    // `--replay <file>` opens a saved game in the replay viewer
+    if let Some(index) = args.iter().position(|arg| arg == "--replay") {
+        match args.get(index + 1) {
+            Some(path) => match replay::load(path, GameState::Menu) {
+                Ok(()) => game_state = GameState::Replay,
+                Err(e) => eprintln!("Could not load replay: {e}"),
+            },
+            None => eprintln!("--replay needs a file path"),
+        }
+    }
     let mut bg = background::Background::new();
 
     loop {
@@ -66,7 +130,26 @@ async fn main() {
         let next_state = match game_state {
             GameState::Menu => menu::update(&scale),
             GameState::ChooseSymbol => menu::choose_symbol(&scale),
-            GameState::PvP | GameState::PvAI => game::update(game_state, &scale),
+            GameState::ChooseBoard => menu::choose_board(&scale),
+            GameState::Stats => menu::statistics(&scale),
+            GameState::OnlineLobby => menu::online_lobby(&scale),
+            GameState::ChooseAiVsAi => menu::choose_ai_vs_ai(&scale),
+            GameState::ChooseVariant => menu::choose_variant(&scale),
+            GameState::PvP | GameState::PvAI(_) | GameState::Online | GameState::AiVsAi => {
+                game::update(game_state, &scale)
+            }
+            GameState::Replay => replay::update(&scale),
+            GameState::Analysis => analysis::update(),
+            GameState::Ultimate => ultimate::update(&scale),
+            GameState::Wild => wild::update(&scale),
+            GameState::ThreePiece => three_piece::update(&scale),
+            GameState::ChooseConnectFour => menu::choose_connect_four(&scale),
+            GameState::ConnectFour => connect_four::update(&scale),
+            GameState::ChooseGomoku => menu::choose_gomoku(&scale),
+            GameState::Gomoku => gomoku::update(&scale),
+            GameState::Qubic => qubic::update(&scale),
+            GameState::ChooseOrderChaos => menu::choose_order_chaos(&scale),
+            GameState::OrderChaos => order_chaos::update(&scale),
         };
 
         if let Some(state) = next_state {
@@ -2,14 +2,32 @@ use crate::config::*;
 use crate::state::GameState;
 use crate::utils::*;
 use macroquad::prelude::*;
+use tictactoe_core::ai::{Difficulty, Engine};
+use tictactoe_core::gomoku::{FiveRule, Opening};
+use tictactoe_core::order_chaos::Role;
+use tictactoe_core::{CellState, Rule, Topology};
+
+static mut DIFFICULTY: Difficulty = Difficulty::Perfect;
+static mut ADDRESS: String = String::new();
+static mut LOBBY_ERROR: Option<String> = None;
+
+// Variant settings: `None` is two people at one screen
+static mut VARIANT_OPPONENT: Option<Difficulty> = None;
+static mut VARIANT_SYMBOL: CellState = CellState::X;
+
+const ADDRESS_PLACEHOLDER: &str = "127.0.0.1:7878";
+
+// Choices for how long the AI may think per move, in milliseconds
+const THINK_TIMES_MS: [u64; 6] = [100, 250, 500, 1000, 2000, 5000];
 
 pub fn update(scale: &ScreenScale) -> Option<GameState> {
     draw_decorations();
 
     let button_width = 280.0;
-    let button_height = 70.0;
+    let button_height = 50.0;
     let center_x = VIRTUAL_WIDTH / 2.0 - button_width / 2.0;
-    let start_y = 250.0;
+    let start_y = 178.0;
+    let button_step = 58.0;
     let mouse = mouse_to_virtual(scale);
     let font = crate::config::get_font();
     let theme = crate::theme::get_current_theme();
@@ -51,9 +69,56 @@ pub fn update(scale: &ScreenScale) -> Option<GameState> {
     draw_button(pvp_rect, "Player vs Player", ButtonType::Blue, mouse, None);
 
     // ---- PvAI Button ----
-    let ai_rect = Rect::new(center_x, start_y + 100.0, button_width, button_height);
+    let ai_rect = Rect::new(center_x, start_y + button_step, button_width, button_height);
     draw_button(ai_rect, "Play vs AI", ButtonType::Green, mouse, None);
 
+    // ---- Online Button ----
+    let online_rect = Rect::new(
+        center_x,
+        start_y + button_step * 2.0,
+        button_width,
+        button_height,
+    );
+    draw_button(online_rect, "Play Online", ButtonType::Blue, mouse, None);
+
+    // ---- Board Size Button ----
+    let (size, win_length) = crate::game::board_config();
+    let board_label = format!("Board: {size}x{size} / {win_length}");
+    let board_rect = Rect::new(
+        center_x,
+        start_y + button_step * 3.0,
+        button_width,
+        button_height,
+    );
+    draw_button(board_rect, &board_label, ButtonType::Green, mouse, None);
+
+    // ---- Statistics Button ----
+    let stats_rect = Rect::new(
+        center_x,
+        start_y + button_step * 4.0,
+        button_width,
+        button_height,
+    );
+    draw_button(stats_rect, "Statistics", ButtonType::Blue, mouse, None);
+
+    // ---- AI vs AI Button ----
+    let watch_rect = Rect::new(
+        center_x,
+        start_y + button_step * 5.0,
+        button_width,
+        button_height,
+    );
+    draw_button(watch_rect, "Watch AI vs AI", ButtonType::Green, mouse, None);
+
+    // ---- Variants Button ----
+    let variants_rect = Rect::new(
+        center_x,
+        start_y + button_step * 6.0,
+        button_width,
+        button_height,
+    );
+    draw_button(variants_rect, "Variants", ButtonType::Blue, mouse, None);
+
     if is_mouse_button_pressed(MouseButton::Left) {
         if pvp_rect.contains(mouse) {
             crate::config::play_click();
@@ -63,6 +128,26 @@ pub fn update(scale: &ScreenScale) -> Option<GameState> {
             crate::config::play_click();
             return Some(GameState::ChooseSymbol);
         }
+        if online_rect.contains(mouse) {
+            crate::config::play_click();
+            return Some(GameState::OnlineLobby);
+        }
+        if board_rect.contains(mouse) {
+            crate::config::play_click();
+            return Some(GameState::ChooseBoard);
+        }
+        if stats_rect.contains(mouse) {
+            crate::config::play_click();
+            return Some(GameState::Stats);
+        }
+        if watch_rect.contains(mouse) {
+            crate::config::play_click();
+            return Some(GameState::ChooseAiVsAi);
+        }
+        if variants_rect.contains(mouse) {
+            crate::config::play_click();
+            return Some(GameState::ChooseVariant);
+        }
     }
 
     if is_key_pressed(KeyCode::T) {
@@ -77,7 +162,7 @@ pub fn choose_symbol(scale: &ScreenScale) -> Option<GameState> {
 
     let button_width = 180.0;
     let button_height = 180.0;
-    let center_y = VIRTUAL_HEIGHT / 2.0 - button_height / 2.0 + 50.0;
+    let center_y = VIRTUAL_HEIGHT / 2.0 - button_height / 2.0;
     let spacing = 100.0;
     let start_x = VIRTUAL_WIDTH / 2.0 - button_width - spacing / 2.0;
 
@@ -115,26 +200,975 @@ pub fn choose_symbol(scale: &ScreenScale) -> Option<GameState> {
     );
     draw_button(o_rect, "O", ButtonType::Green, mouse, inter_font);
 
+    // ---- Difficulty Buttons ----
+    let selected = unsafe { DIFFICULTY };
+    let diff_width = 150.0;
+    let diff_height = 55.0;
+    let diff_spacing = 20.0;
+    let diff_count = Difficulty::ALL.len() as f32;
+    let diff_start_x =
+        VIRTUAL_WIDTH / 2.0 - (diff_width * diff_count + diff_spacing * (diff_count - 1.0)) / 2.0;
+    let diff_y = center_y + button_height + 50.0;
+
+    let mut diff_rects = Vec::new();
+    for (i, difficulty) in Difficulty::ALL.into_iter().enumerate() {
+        let rect = Rect::new(
+            diff_start_x + i as f32 * (diff_width + diff_spacing),
+            diff_y,
+            diff_width,
+            diff_height,
+        );
+        // The selected level is drawn green, the rest blue
+        let button_type = if difficulty == selected {
+            ButtonType::Green
+        } else {
+            ButtonType::Blue
+        };
+        draw_button(rect, difficulty.name(), button_type, mouse, None);
+        diff_rects.push((rect, difficulty));
+    }
+
     if is_mouse_button_pressed(MouseButton::Left) {
         if x_rect.contains(mouse) {
             crate::config::play_click();
-            crate::game::set_player_symbol(crate::board::CellState::X);
-            return Some(GameState::PvAI);
+            crate::game::set_player_symbol(CellState::X);
+            return Some(GameState::PvAI(selected));
         }
         if o_rect.contains(mouse) {
             crate::config::play_click();
-            crate::game::set_player_symbol(crate::board::CellState::O);
-            return Some(GameState::PvAI);
+            crate::game::set_player_symbol(CellState::O);
+            return Some(GameState::PvAI(selected));
+        }
+        for (rect, difficulty) in diff_rects {
+            if rect.contains(mouse) {
+                crate::config::play_click();
+                unsafe {
+                    DIFFICULTY = difficulty;
+                }
+            }
+        }
+    }
+
+    if is_key_pressed(KeyCode::Escape) {
+        return Some(GameState::Menu);
+    }
+
+    None
+}
+
+pub fn choose_board(scale: &ScreenScale) -> Option<GameState> {
+    draw_decorations();
+
+    let mouse = mouse_to_virtual(scale);
+    let font = crate::config::get_font();
+    let theme = crate::theme::get_current_theme();
+    let (size, win_length) = crate::game::board_config();
+    let think_ms = crate::game::think_time().as_millis() as u64;
+    let engine = crate::game::engine();
+    let rule = crate::game::rule();
+    let topology = crate::game::topology();
+
+    // Draw Title
+    let title_text = "BOARD & AI";
+    let title_size = 48;
+    let title_dim = measure_text(title_text, font, title_size, 1.0);
+    draw_text_ex(
+        title_text,
+        VIRTUAL_WIDTH / 2.0 - title_dim.width / 2.0,
+        130.0,
+        TextParams {
+            font,
+            font_size: title_size,
+            color: theme.text,
+            ..Default::default()
+        },
+    );
+
+    // ---- Size Row ----
+    let size_label = format!("Grid: {size}x{size}");
+    let (size_minus, size_plus) = draw_stepper(&size_label, 150.0, mouse);
+
+    // ---- Win Length Row ----
+    let win_label = format!("{win_length} in a row");
+    let (win_minus, win_plus) = draw_stepper(&win_label, 225.0, mouse);
+
+    // ---- Rule Row ----
+    let rule_label = match rule {
+        Rule::Standard => "Line wins".to_string(),
+        Rule::Misere => "Line loses (misere)".to_string(),
+    };
+    let (rule_minus, rule_plus) = draw_stepper(&rule_label, 300.0, mouse);
+
+    // ---- Topology Row ----
+    let topology_label = match topology {
+        Topology::Flat => "Flat board".to_string(),
+        Topology::Cylinder => "Cylinder (sides wrap)".to_string(),
+        Topology::Torus => "Torus (all edges wrap)".to_string(),
+    };
+    let (topology_minus, topology_plus) = draw_stepper(&topology_label, 375.0, mouse);
+
+    // ---- AI Think Time Row ----
+    let think_label = if think_ms >= 1000 {
+        format!("AI thinks {} s", think_ms as f32 / 1000.0)
+    } else {
+        format!("AI thinks {think_ms} ms")
+    };
+    let (think_minus, think_plus) = draw_stepper(&think_label, 450.0, mouse);
+
+    // ---- AI Engine Row ----
+    let engine_label = format!("AI engine: {}", engine.name());
+    let (engine_minus, engine_plus) = draw_stepper(&engine_label, 525.0, mouse);
+
+    // ---- Done Button ----
+    // Beside the last row, as the rows fill the screen
+    let done_rect = Rect::new(VIRTUAL_WIDTH - 160.0, 525.0, 140.0, 70.0);
+    draw_button(done_rect, "Done", ButtonType::Green, mouse, None);
+
+    if is_mouse_button_pressed(MouseButton::Left) {
+        let mut new_config = None;
+        if size_minus.contains(mouse) {
+            // Shrinking the grid also pulls the win length down with it
+            new_config = Some((size - 1, win_length.min(size - 1)));
+        }
+        if size_plus.contains(mouse) {
+            new_config = Some((size + 1, win_length));
+        }
+        if win_minus.contains(mouse) {
+            new_config = Some((size, win_length - 1));
+        }
+        if win_plus.contains(mouse) {
+            new_config = Some((size, win_length + 1));
+        }
+        if let Some((new_size, new_win_length)) = new_config {
+            crate::config::play_click();
+            crate::game::set_board_config(new_size, new_win_length);
+        }
+
+        // Step through the preset times, from wherever the current one falls
+        let slower = THINK_TIMES_MS.iter().find(|&&ms| ms > think_ms);
+        let faster = THINK_TIMES_MS.iter().rev().find(|&&ms| ms < think_ms);
+        let new_think_ms = if think_minus.contains(mouse) {
+            faster
+        } else if think_plus.contains(mouse) {
+            slower
+        } else {
+            None
+        };
+        if let Some(&ms) = new_think_ms {
+            crate::config::play_click();
+            crate::game::set_think_time(std::time::Duration::from_millis(ms));
+        }
+
+        if let Some(new_rule) = cycle(&Rule::ALL, rule, rule_minus, rule_plus, mouse) {
+            crate::config::play_click();
+            crate::game::set_rule(new_rule);
+        }
+
+        if let Some(new_topology) = cycle(
+            &Topology::ALL,
+            topology,
+            topology_minus,
+            topology_plus,
+            mouse,
+        ) {
+            crate::config::play_click();
+            crate::game::set_topology(new_topology);
+        }
+
+        if let Some(new_engine) = cycle(&Engine::ALL, engine, engine_minus, engine_plus, mouse) {
+            crate::config::play_click();
+            crate::game::set_engine(new_engine);
+        }
+        if done_rect.contains(mouse) {
+            crate::config::play_click();
+            return Some(GameState::Menu);
+        }
+    }
+
+    if is_key_pressed(KeyCode::Escape) {
+        return Some(GameState::Menu);
+    }
+
+    None
+}
+
+pub fn choose_ai_vs_ai(scale: &ScreenScale) -> Option<GameState> {
+    draw_decorations();
+
+    let mouse = mouse_to_virtual(scale);
+    let font = crate::config::get_font();
+    let theme = crate::theme::get_current_theme();
+    let [(x_level, x_engine), (o_level, o_engine)] = crate::spectator::sides();
+
+    // Draw Title
+    let title_text = "AI VS AI";
+    let title_size = 48;
+    let title_dim = measure_text(title_text, font, title_size, 1.0);
+    draw_text_ex(
+        title_text,
+        VIRTUAL_WIDTH / 2.0 - title_dim.width / 2.0,
+        130.0,
+        TextParams {
+            font,
+            font_size: title_size,
+            color: theme.text,
+            ..Default::default()
+        },
+    );
+
+    // ---- One Difficulty and Engine Row per Side ----
+    let x_level_label = format!("X: {}", x_level.name());
+    let (x_level_minus, x_level_plus) = draw_stepper(&x_level_label, 170.0, mouse);
+    let x_engine_label = format!("X engine: {}", x_engine.name());
+    let (x_engine_minus, x_engine_plus) = draw_stepper(&x_engine_label, 250.0, mouse);
+    let o_level_label = format!("O: {}", o_level.name());
+    let (o_level_minus, o_level_plus) = draw_stepper(&o_level_label, 330.0, mouse);
+    let o_engine_label = format!("O engine: {}", o_engine.name());
+    let (o_engine_minus, o_engine_plus) = draw_stepper(&o_engine_label, 410.0, mouse);
+
+    // ---- Start Button ----
+    let start_rect = Rect::new(VIRTUAL_WIDTH / 2.0 - 140.0, 500.0, 280.0, 70.0);
+    draw_button(start_rect, "Start", ButtonType::Green, mouse, None);
+
+    if is_mouse_button_pressed(MouseButton::Left) {
+        let new_sides = [
+            (
+                cycle(
+                    &Difficulty::ALL,
+                    x_level,
+                    x_level_minus,
+                    x_level_plus,
+                    mouse,
+                )
+                .unwrap_or(x_level),
+                cycle(&Engine::ALL, x_engine, x_engine_minus, x_engine_plus, mouse)
+                    .unwrap_or(x_engine),
+            ),
+            (
+                cycle(
+                    &Difficulty::ALL,
+                    o_level,
+                    o_level_minus,
+                    o_level_plus,
+                    mouse,
+                )
+                .unwrap_or(o_level),
+                cycle(&Engine::ALL, o_engine, o_engine_minus, o_engine_plus, mouse)
+                    .unwrap_or(o_engine),
+            ),
+        ];
+        if new_sides != crate::spectator::sides() {
+            crate::config::play_click();
+            crate::spectator::set_sides(new_sides);
+        }
+        if start_rect.contains(mouse) {
+            crate::config::play_click();
+            // A fresh match, so the score starts from zero
+            crate::spectator::set_sides(new_sides);
+            return Some(GameState::AiVsAi);
+        }
+    }
+
+    if is_key_pressed(KeyCode::Escape) {
+        return Some(GameState::Menu);
+    }
+
+    None
+}
+
+pub fn choose_variant(scale: &ScreenScale) -> Option<GameState> {
+    draw_decorations();
+
+    let mouse = mouse_to_virtual(scale);
+    let font = crate::config::get_font();
+    let theme = crate::theme::get_current_theme();
+    let (opponent, symbol) = unsafe { (VARIANT_OPPONENT, VARIANT_SYMBOL) };
+
+    // Draw Title
+    let title_text = "VARIANTS";
+    let title_size = 48;
+    let title_dim = measure_text(title_text, font, title_size, 1.0);
+    draw_text_ex(
+        title_text,
+        VIRTUAL_WIDTH / 2.0 - title_dim.width / 2.0,
+        130.0,
+        TextParams {
+            font,
+            font_size: title_size,
+            color: theme.text,
+            ..Default::default()
+        },
+    );
+
+    // ---- Opponent and Side Rows ----
+    let opponents: Vec<Option<Difficulty>> = std::iter::once(None)
+        .chain(Difficulty::ALL.map(Some))
+        .collect();
+    let opponent_label = match opponent {
+        Some(difficulty) => format!("vs AI: {}", difficulty.name()),
+        None => "vs Friend".to_string(),
+    };
+    let (opponent_minus, opponent_plus) = draw_stepper(&opponent_label, 170.0, mouse);
+    // X moves first in every variant; in Wild, where both place either
+    // symbol, it just means moving first
+    let symbol_label = if symbol == CellState::X {
+        "You move first"
+    } else {
+        "You move second"
+    };
+    let (symbol_minus, symbol_plus) = draw_stepper(symbol_label, 250.0, mouse);
+
+    // ---- One Button per Variant ----
+    let ultimate_rect = variant_rect(0);
+    draw_button(ultimate_rect, "Ultimate", ButtonType::Green, mouse, None);
+    let wild_rect = variant_rect(1);
+    draw_button(wild_rect, "Wild", ButtonType::Blue, mouse, None);
+    let three_piece_rect = variant_rect(2);
+    draw_button(
+        three_piece_rect,
+        "Three Pieces",
+        ButtonType::Green,
+        mouse,
+        None,
+    );
+    let connect_four_rect = variant_rect(3);
+    draw_button(
+        connect_four_rect,
+        "Connect Four",
+        ButtonType::Blue,
+        mouse,
+        None,
+    );
+    let gomoku_rect = variant_rect(4);
+    draw_button(gomoku_rect, "Gomoku", ButtonType::Green, mouse, None);
+    let qubic_rect = variant_rect(5);
+    draw_button(qubic_rect, "Qubic", ButtonType::Blue, mouse, None);
+    let order_chaos_rect = variant_rect(6);
+    draw_button(
+        order_chaos_rect,
+        "Order & Chaos",
+        ButtonType::Green,
+        mouse,
+        None,
+    );
+
+    if is_mouse_button_pressed(MouseButton::Left) {
+        if let Some(new_opponent) =
+            cycle(&opponents, opponent, opponent_minus, opponent_plus, mouse)
+        {
+            crate::config::play_click();
+            unsafe { VARIANT_OPPONENT = new_opponent };
+        }
+        if let Some(new_symbol) = cycle(
+            &[CellState::X, CellState::O],
+            symbol,
+            symbol_minus,
+            symbol_plus,
+            mouse,
+        ) {
+            crate::config::play_click();
+            unsafe { VARIANT_SYMBOL = new_symbol };
+        }
+        if ultimate_rect.contains(mouse) {
+            crate::config::play_click();
+            crate::ultimate::start(opponent, symbol);
+            return Some(GameState::Ultimate);
+        }
+        if wild_rect.contains(mouse) {
+            crate::config::play_click();
+            let human = if symbol == CellState::X { 0 } else { 1 };
+            crate::wild::start(opponent, human);
+            return Some(GameState::Wild);
+        }
+        if three_piece_rect.contains(mouse) {
+            crate::config::play_click();
+            crate::three_piece::start(opponent, symbol);
+            return Some(GameState::ThreePiece);
+        }
+        if connect_four_rect.contains(mouse) {
+            crate::config::play_click();
+            return Some(GameState::ChooseConnectFour);
+        }
+        if gomoku_rect.contains(mouse) {
+            crate::config::play_click();
+            return Some(GameState::ChooseGomoku);
+        }
+        if qubic_rect.contains(mouse) {
+            crate::config::play_click();
+            crate::qubic::start(opponent, symbol);
+            return Some(GameState::Qubic);
+        }
+        if order_chaos_rect.contains(mouse) {
+            crate::config::play_click();
+            return Some(GameState::ChooseOrderChaos);
+        }
+    }
+
+    if is_key_pressed(KeyCode::Escape) {
+        return Some(GameState::Menu);
+    }
+
+    None
+}
+
+/// Grid setup for Connect Four, which then starts with the opponent and side
+/// picked on the variants screen.
+pub fn choose_connect_four(scale: &ScreenScale) -> Option<GameState> {
+    draw_decorations();
+
+    let mouse = mouse_to_virtual(scale);
+    let font = crate::config::get_font();
+    let theme = crate::theme::get_current_theme();
+    let (rows, cols, win_length) = crate::connect_four::grid();
+
+    // Draw Title
+    let title_text = "CONNECT FOUR";
+    let title_size = 48;
+    let title_dim = measure_text(title_text, font, title_size, 1.0);
+    draw_text_ex(
+        title_text,
+        VIRTUAL_WIDTH / 2.0 - title_dim.width / 2.0,
+        130.0,
+        TextParams {
+            font,
+            font_size: title_size,
+            color: theme.text,
+            ..Default::default()
+        },
+    );
+
+    // ---- Grid Rows ----
+    let cols_label = format!("{cols} columns");
+    let (cols_minus, cols_plus) = draw_stepper(&cols_label, 170.0, mouse);
+    let rows_label = format!("{rows} rows");
+    let (rows_minus, rows_plus) = draw_stepper(&rows_label, 250.0, mouse);
+    let win_label = format!("{win_length} in a row");
+    let (win_minus, win_plus) = draw_stepper(&win_label, 330.0, mouse);
+
+    // ---- Start Button ----
+    let start_rect = Rect::new(VIRTUAL_WIDTH / 2.0 - 140.0, 500.0, 280.0, 70.0);
+    draw_button(start_rect, "Start", ButtonType::Green, mouse, None);
+
+    if is_mouse_button_pressed(MouseButton::Left) {
+        let new_grid = if cols_minus.contains(mouse) {
+            Some((rows, cols - 1, win_length))
+        } else if cols_plus.contains(mouse) {
+            Some((rows, cols + 1, win_length))
+        } else if rows_minus.contains(mouse) {
+            Some((rows - 1, cols, win_length))
+        } else if rows_plus.contains(mouse) {
+            Some((rows + 1, cols, win_length))
+        } else if win_minus.contains(mouse) {
+            Some((rows, cols, win_length - 1))
+        } else if win_plus.contains(mouse) {
+            Some((rows, cols, win_length + 1))
+        } else {
+            None
+        };
+        if let Some((new_rows, new_cols, new_win_length)) = new_grid {
+            crate::config::play_click();
+            crate::connect_four::set_grid(new_rows, new_cols, new_win_length);
+        }
+        if start_rect.contains(mouse) {
+            crate::config::play_click();
+            let (opponent, symbol) = unsafe { (VARIANT_OPPONENT, VARIANT_SYMBOL) };
+            crate::connect_four::start(opponent, symbol);
+            return Some(GameState::ConnectFour);
+        }
+    }
+
+    if is_key_pressed(KeyCode::Escape) {
+        return Some(GameState::ChooseVariant);
+    }
+
+    None
+}
+
+/// Line rule and opening for Gomoku, which then starts with the opponent and
+/// side picked on the variants screen.
+pub fn choose_gomoku(scale: &ScreenScale) -> Option<GameState> {
+    draw_decorations();
+
+    let mouse = mouse_to_virtual(scale);
+    let font = crate::config::get_font();
+    let theme = crate::theme::get_current_theme();
+    let (five_rule, opening) = crate::gomoku::rules();
+
+    // Draw Title
+    let title_text = "GOMOKU";
+    let title_size = 48;
+    let title_dim = measure_text(title_text, font, title_size, 1.0);
+    draw_text_ex(
+        title_text,
+        VIRTUAL_WIDTH / 2.0 - title_dim.width / 2.0,
+        130.0,
+        TextParams {
+            font,
+            font_size: title_size,
+            color: theme.text,
+            ..Default::default()
+        },
+    );
+
+    // ---- Rule Rows ----
+    let five_label = format!("{} wins", five_rule.name());
+    let (five_minus, five_plus) = draw_stepper(&five_label, 170.0, mouse);
+    let opening_label = format!("{} opening", opening.name());
+    let (opening_minus, opening_plus) = draw_stepper(&opening_label, 250.0, mouse);
+
+    // ---- Start Button ----
+    let start_rect = Rect::new(VIRTUAL_WIDTH / 2.0 - 140.0, 500.0, 280.0, 70.0);
+    draw_button(start_rect, "Start", ButtonType::Green, mouse, None);
+
+    if is_mouse_button_pressed(MouseButton::Left) {
+        if let Some(new_rule) = cycle(&FiveRule::ALL, five_rule, five_minus, five_plus, mouse) {
+            crate::config::play_click();
+            crate::gomoku::set_rules(new_rule, opening);
+        }
+        if let Some(new_opening) = cycle(&Opening::ALL, opening, opening_minus, opening_plus, mouse)
+        {
+            crate::config::play_click();
+            crate::gomoku::set_rules(five_rule, new_opening);
+        }
+        if start_rect.contains(mouse) {
+            crate::config::play_click();
+            let (opponent, symbol) = unsafe { (VARIANT_OPPONENT, VARIANT_SYMBOL) };
+            let human = if symbol == CellState::X { 0 } else { 1 };
+            crate::gomoku::start(opponent, human);
+            return Some(GameState::Gomoku);
+        }
+    }
+
+    if is_key_pressed(KeyCode::Escape) {
+        return Some(GameState::ChooseVariant);
+    }
+
+    None
+}
+
+/// Side for Order and Chaos, where Order wants five in a row of either
+/// symbol and Chaos wants to stop it. The opponent comes from the variants
+/// screen.
+pub fn choose_order_chaos(scale: &ScreenScale) -> Option<GameState> {
+    draw_decorations();
+
+    let mouse = mouse_to_virtual(scale);
+    let font = crate::config::get_font();
+    let theme = crate::theme::get_current_theme();
+    let role = crate::order_chaos::role();
+
+    // Draw Title
+    let title_text = "ORDER & CHAOS";
+    let title_size = 48;
+    let title_dim = measure_text(title_text, font, title_size, 1.0);
+    draw_text_ex(
+        title_text,
+        VIRTUAL_WIDTH / 2.0 - title_dim.width / 2.0,
+        130.0,
+        TextParams {
+            font,
+            font_size: title_size,
+            color: theme.text,
+            ..Default::default()
+        },
+    );
+
+    // ---- Role Row ----
+    let role_label = format!("You play {}", role.name());
+    let (role_minus, role_plus) = draw_stepper(&role_label, 170.0, mouse);
+    let about = match role {
+        Role::Order => "Make five in a row of X or O. You move first.",
+        Role::Chaos => "Fill the board without five in a row.",
+    };
+    let about_dim = measure_text(about, font, 20, 1.0);
+    draw_text_ex(
+        about,
+        VIRTUAL_WIDTH / 2.0 - about_dim.width / 2.0,
+        290.0,
+        TextParams {
+            font,
+            font_size: 20,
+            color: theme.muted,
+            ..Default::default()
+        },
+    );
+
+    // ---- Start Button ----
+    let start_rect = Rect::new(VIRTUAL_WIDTH / 2.0 - 140.0, 500.0, 280.0, 70.0);
+    draw_button(start_rect, "Start", ButtonType::Green, mouse, None);
+
+    if is_mouse_button_pressed(MouseButton::Left) {
+        if let Some(new_role) = cycle(&Role::ALL, role, role_minus, role_plus, mouse) {
+            crate::config::play_click();
+            crate::order_chaos::set_role(new_role);
+        }
+        if start_rect.contains(mouse) {
+            crate::config::play_click();
+            let opponent = unsafe { VARIANT_OPPONENT };
+            crate::order_chaos::start(opponent, role);
+            return Some(GameState::OrderChaos);
         }
     }
 
     if is_key_pressed(KeyCode::Escape) {
+        return Some(GameState::ChooseVariant);
+    }
+
+    None
+}
+
+/// Where the variant button at `slot` goes, three to a row.
+fn variant_rect(slot: usize) -> Rect {
+    let (width, height, gap) = (230.0, 60.0, 15.0);
+    let left = VIRTUAL_WIDTH / 2.0 - (width * 3.0 + gap * 2.0) / 2.0;
+    Rect::new(
+        left + (slot % 3) as f32 * (width + gap),
+        350.0 + (slot / 3) as f32 * (height + gap),
+        width,
+        height,
+    )
+}
+
+#[allow(static_mut_refs)]
+pub fn online_lobby(scale: &ScreenScale) -> Option<GameState> {
+    draw_decorations();
+
+    let mouse = mouse_to_virtual(scale);
+    let font = crate::config::get_font();
+    let theme = crate::theme::get_current_theme();
+    let address = unsafe { &mut ADDRESS };
+
+    // Draw Title
+    let title_text = "PLAY ONLINE";
+    let title_size = 48;
+    let title_dim = measure_text(title_text, font, title_size, 1.0);
+    draw_text_ex(
+        title_text,
+        VIRTUAL_WIDTH / 2.0 - title_dim.width / 2.0,
+        120.0,
+        TextParams {
+            font,
+            font_size: title_size,
+            color: theme.text,
+            ..Default::default()
+        },
+    );
+
+    // ---- Address Field ----
+    while let Some(c) = get_char_pressed() {
+        if (c.is_ascii_alphanumeric() || c == '.' || c == ':' || c == '-') && address.len() < 40 {
+            address.push(c);
+        }
+    }
+    if is_key_pressed(KeyCode::Backspace) {
+        address.pop();
+    }
+
+    let field_rect = Rect::new(VIRTUAL_WIDTH / 2.0 - 200.0, 190.0, 400.0, 50.0);
+    draw_rectangle_lines(
+        field_rect.x,
+        field_rect.y,
+        field_rect.w,
+        field_rect.h,
+        3.0,
+        theme.accent,
+    );
+    let (field_text, field_color) = if address.is_empty() {
+        (ADDRESS_PLACEHOLDER, theme.muted)
+    } else {
+        (address.as_str(), theme.text)
+    };
+    let field_dim = measure_text(field_text, font, 24, 1.0);
+    draw_text_ex(
+        field_text,
+        VIRTUAL_WIDTH / 2.0 - field_dim.width / 2.0,
+        field_rect.y + field_rect.h / 2.0 + field_dim.height / 2.0,
+        TextParams {
+            font,
+            font_size: 24,
+            color: field_color,
+            ..Default::default()
+        },
+    );
+
+    // ---- Host / Join Buttons ----
+    let host_rect = Rect::new(VIRTUAL_WIDTH / 2.0 - 200.0, 280.0, 180.0, 70.0);
+    let join_rect = Rect::new(VIRTUAL_WIDTH / 2.0 + 20.0, 280.0, 180.0, 70.0);
+    draw_button(host_rect, "Host", ButtonType::Blue, mouse, None);
+    draw_button(join_rect, "Join", ButtonType::Green, mouse, None);
+
+    if is_mouse_button_pressed(MouseButton::Left) {
+        let target = if address.is_empty() {
+            ADDRESS_PLACEHOLDER.to_string()
+        } else {
+            address.clone()
+        };
+        let result = if host_rect.contains(mouse) {
+            crate::config::play_click();
+            // Only the port part of the address matters when hosting
+            let port = target
+                .rsplit_once(':')
+                .and_then(|(_, port)| port.parse().ok())
+                .unwrap_or(crate::net::DEFAULT_PORT);
+            let (size, win_length) = crate::game::board_config();
+            Some(crate::net::host(port, size, win_length, CellState::X))
+        } else if join_rect.contains(mouse) {
+            crate::config::play_click();
+            Some(crate::net::join(&target))
+        } else {
+            None
+        };
+        if let Some(result) = result {
+            unsafe {
+                LOBBY_ERROR = result.err();
+            }
+        }
+    }
+
+    // ---- Connection Status ----
+    let status = match crate::net::poll() {
+        crate::net::SessionStatus::Started(size, win_length, symbol) => {
+            crate::game::start_online(size, win_length, symbol);
+            unsafe {
+                LOBBY_ERROR = None;
+            }
+            return Some(GameState::Online);
+        }
+        crate::net::SessionStatus::Waiting(text) => text,
+        crate::net::SessionStatus::Closed(reason) => reason,
+        crate::net::SessionStatus::Idle => unsafe {
+            LOBBY_ERROR
+                .clone()
+                .unwrap_or_else(|| "Host a game, or type the host's address and join".to_string())
+        },
+    };
+    let status_dim = measure_text(&status, font, 20, 1.0);
+    draw_text_ex(
+        &status,
+        VIRTUAL_WIDTH / 2.0 - status_dim.width / 2.0,
+        410.0,
+        TextParams {
+            font,
+            font_size: 20,
+            color: theme.muted,
+            ..Default::default()
+        },
+    );
+
+    let (size, win_length) = crate::game::board_config();
+    let board_text = format!("Hosting uses your board setting: {size}x{size} / {win_length}");
+    let board_dim = measure_text(&board_text, font, 16, 1.0);
+    draw_text_ex(
+        &board_text,
+        VIRTUAL_WIDTH / 2.0 - board_dim.width / 2.0,
+        450.0,
+        TextParams {
+            font,
+            font_size: 16,
+            color: theme.muted,
+            ..Default::default()
+        },
+    );
+
+    if is_key_pressed(KeyCode::Escape) {
+        crate::net::close();
+        unsafe {
+            LOBBY_ERROR = None;
+        }
         return Some(GameState::Menu);
     }
 
     None
 }
 
+pub fn statistics(scale: &ScreenScale) -> Option<GameState> {
+    draw_decorations();
+
+    let mouse = mouse_to_virtual(scale);
+    let font = crate::config::get_font();
+    let theme = crate::theme::get_current_theme();
+    let stats = crate::stats::get_stats();
+
+    // Draw Title
+    let title_text = "STATISTICS";
+    let title_size = 48;
+    let title_dim = measure_text(title_text, font, title_size, 1.0);
+    draw_text_ex(
+        title_text,
+        VIRTUAL_WIDTH / 2.0 - title_dim.width / 2.0,
+        90.0,
+        TextParams {
+            font,
+            font_size: title_size,
+            color: theme.text,
+            ..Default::default()
+        },
+    );
+
+    // Stats are kept apart for each rule and topology; show the current ones
+    let (rule, topology) = (crate::game::rule(), crate::game::topology());
+    let variant_text = format!("{} rules, {} board", rule.name(), topology.name());
+    let variant_dim = measure_text(&variant_text, font, 16, 1.0);
+    draw_text_ex(
+        &variant_text,
+        VIRTUAL_WIDTH / 2.0 - variant_dim.width / 2.0,
+        115.0,
+        TextParams {
+            font,
+            font_size: 16,
+            color: theme.muted,
+            ..Default::default()
+        },
+    );
+
+    // ---- Table ----
+    let columns = [
+        ("Mode", 40.0),
+        ("W", 300.0),
+        ("L", 370.0),
+        ("D", 440.0),
+        ("Streak", 510.0),
+        ("Best", 610.0),
+        ("Avg", 690.0),
+    ];
+    let row_height = 32.0;
+    let header_y = 140.0;
+
+    for (name, x) in columns {
+        draw_text_ex(
+            name,
+            x,
+            header_y,
+            TextParams {
+                font,
+                font_size: 18,
+                color: theme.muted,
+                ..Default::default()
+            },
+        );
+    }
+
+    for (i, (key, label)) in crate::stats::all_mode_keys(rule, topology)
+        .iter()
+        .enumerate()
+    {
+        let entry = stats.get(key);
+        let y = header_y + row_height * (i + 1) as f32;
+        let streak = match entry.streak {
+            0 => "-".to_string(),
+            n if n > 0 => format!("{n}W"),
+            n => format!("{}L", -n),
+        };
+        let average = entry
+            .average_length()
+            .map(|avg| format!("{avg:.1}"))
+            .unwrap_or_else(|| "-".to_string());
+        let cells = [
+            label.clone(),
+            entry.wins.to_string(),
+            entry.losses.to_string(),
+            entry.draws.to_string(),
+            streak,
+            entry.best_streak.to_string(),
+            average,
+        ];
+
+        // Modes that were never played are dimmed
+        let color = if entry.games() > 0 {
+            theme.text
+        } else {
+            theme.muted
+        };
+        for (text, (_, x)) in cells.iter().zip(columns) {
+            draw_text_ex(
+                text,
+                x,
+                y,
+                TextParams {
+                    font,
+                    font_size: 18,
+                    color,
+                    ..Default::default()
+                },
+            );
+        }
+    }
+
+    // ---- Back Button ----
+    let back_rect = Rect::new(VIRTUAL_WIDTH / 2.0 - 140.0, 500.0, 280.0, 70.0);
+    draw_button(back_rect, "Back", ButtonType::Blue, mouse, None);
+
+    if is_mouse_button_pressed(MouseButton::Left) && back_rect.contains(mouse) {
+        crate::config::play_click();
+        return Some(GameState::Menu);
+    }
+
+    if is_key_pressed(KeyCode::Escape) {
+        return Some(GameState::Menu);
+    }
+
+    None
+}
+
+/// The option before or after `current` in `all`, wrapping around, if one of
+/// the stepper buttons was clicked.
+fn cycle<T: Copy + PartialEq>(
+    all: &[T],
+    current: T,
+    minus: Rect,
+    plus: Rect,
+    mouse: Vec2,
+) -> Option<T> {
+    let index = all.iter().position(|&item| item == current).unwrap_or(0);
+    if minus.contains(mouse) {
+        Some(all[(index + all.len() - 1) % all.len()])
+    } else if plus.contains(mouse) {
+        Some(all[(index + 1) % all.len()])
+    } else {
+        None
+    }
+}
+
+/// Draws a centered label with `-` and `+` buttons on either side and returns
+/// the two button rects.
+fn draw_stepper(label: &str, y: f32, mouse: Vec2) -> (Rect, Rect) {
+    let font = crate::config::get_font();
+    let theme = crate::theme::get_current_theme();
+    let button_size = 70.0;
+    let label_width = 300.0;
+
+    let minus_rect = Rect::new(
+        VIRTUAL_WIDTH / 2.0 - label_width / 2.0 - button_size,
+        y,
+        button_size,
+        button_size,
+    );
+    let plus_rect = Rect::new(
+        VIRTUAL_WIDTH / 2.0 + label_width / 2.0,
+        y,
+        button_size,
+        button_size,
+    );
+    draw_button(minus_rect, "-", ButtonType::Blue, mouse, None);
+    draw_button(plus_rect, "+", ButtonType::Blue, mouse, None);
+
+    let label_size = 28;
+    let label_dim = measure_text(label, font, label_size, 1.0);
+    draw_text_ex(
+        label,
+        VIRTUAL_WIDTH / 2.0 - label_dim.width / 2.0,
+        y + button_size / 2.0 + label_dim.height / 2.0,
+        TextParams {
+            font,
+            font_size: label_size,
+            color: theme.text,
+            ..Default::default()
+        },
+    );
+
+    (minus_rect, plus_rect)
+}
+
 fn draw_decorations() {
     // Draw Background Decorations (X and O icons using Font for sharpness)
     let theme = crate::theme::get_current_theme();
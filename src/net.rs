@@ -0,0 +1,519 @@
+//! LAN play over TCP.
+//!
+//! One instance hosts on a port, the other joins by address. Both sides send
+//! newline-terminated text messages:
+//!
+//! ```text
+//! HELLO <version> <size> <win> <x|o>   host -> guest, board setup and host side
+//! HELLO <version>                      guest -> host, accepts the setup
+//! MOVE <cell>                          a move, in save file notation (e.g. b2)
+//! RESIGN                               the sender gives up the current game
+//! REMATCH                              offer (or accept) another game
+//! BYE                                  the sender is leaving
+//! ERROR <text>                         the sender is closing because of <text>
+//! ```
+//!
+//! The host speaks first. If the protocol versions differ, or the host's
+//! board setup is one this build can't play, the guest answers with `ERROR`
+//! and both sides close. Moves are checked against the local
+//! board, and a move out of turn or onto a taken cell ends the session.
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::mpsc::{Receiver, TryRecvError, channel};
+use std::time::Duration;
+use tictactoe_core::CellState;
+use tictactoe_core::position::{MAX_BOARD_SIZE, MIN_BOARD_SIZE, MIN_WIN_LENGTH};
+
+pub const PROTOCOL_VERSION: u32 = 1;
+pub const DEFAULT_PORT: u16 = 7878;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Clone, PartialEq)]
+pub enum Message {
+    Hello {
+        version: u32,
+        // Only sent by the host: size, win length and the host's side
+        setup: Option<(usize, usize, CellState)>,
+    },
+    Move(usize, usize),
+    Resign,
+    Rematch,
+    Bye,
+    Error(String),
+}
+
+impl Message {
+    pub fn encode(&self) -> String {
+        match self {
+            Message::Hello {
+                version,
+                setup: Some((size, win_length, symbol)),
+            } => {
+                let symbol = if *symbol == CellState::O { "o" } else { "x" };
+                format!("HELLO {version} {size} {win_length} {symbol}")
+            }
+            Message::Hello {
+                version,
+                setup: None,
+            } => format!("HELLO {version}"),
+            Message::Move(row, col) => {
+                format!("MOVE {}", crate::notation::format_move(*row, *col))
+            }
+            Message::Resign => "RESIGN".to_string(),
+            Message::Rematch => "REMATCH".to_string(),
+            Message::Bye => "BYE".to_string(),
+            Message::Error(text) => format!("ERROR {text}"),
+        }
+    }
+
+    pub fn decode(line: &str) -> Result<Self, String> {
+        let mut words = line.split_whitespace();
+        let command = words.next().unwrap_or("");
+        let args: Vec<&str> = words.collect();
+
+        match (command, args.as_slice()) {
+            ("HELLO", [version]) => Ok(Message::Hello {
+                version: parse_field(version)?,
+                setup: None,
+            }),
+            ("HELLO", [version, size, win_length, symbol]) => {
+                let symbol = match *symbol {
+                    "x" => CellState::X,
+                    "o" => CellState::O,
+                    _ => return Err(format!("bad side `{symbol}`")),
+                };
+                Ok(Message::Hello {
+                    version: parse_field(version)?,
+                    setup: Some((parse_field(size)?, parse_field(win_length)?, symbol)),
+                })
+            }
+            ("MOVE", [cell]) => {
+                let (row, col) = crate::notation::parse_move(cell)?;
+                Ok(Message::Move(row, col))
+            }
+            ("RESIGN", []) => Ok(Message::Resign),
+            ("REMATCH", []) => Ok(Message::Rematch),
+            ("BYE", []) => Ok(Message::Bye),
+            ("ERROR", _) => {
+                let text = line.trim_start().strip_prefix("ERROR").unwrap_or("");
+                Ok(Message::Error(text.trim().to_string()))
+            }
+            _ => Err(format!("unknown message `{line}`")),
+        }
+    }
+}
+
+fn parse_field<T: std::str::FromStr>(text: &str) -> Result<T, String> {
+    text.parse().map_err(|_| format!("bad value `{text}`"))
+}
+
+/// Whether a host's board size and win length are ones the game can play.
+fn check_setup(size: usize, win_length: usize) -> Result<(), String> {
+    if !(MIN_BOARD_SIZE..=MAX_BOARD_SIZE).contains(&size) {
+        return Err(format!("Unsupported board size {size}"));
+    }
+    if !(MIN_WIN_LENGTH..=size).contains(&win_length) {
+        return Err(format!(
+            "Unsupported win length {win_length} on a {size}x{size} board"
+        ));
+    }
+    Ok(())
+}
+
+pub enum NetEvent {
+    Message(Message),
+    Disconnected(String),
+}
+
+/// An open connection. A background thread reads lines into a channel so the
+/// game loop never blocks on the socket.
+pub struct Connection {
+    stream: TcpStream,
+    incoming: Receiver<NetEvent>,
+}
+
+impl Connection {
+    fn new(stream: TcpStream) -> std::io::Result<Self> {
+        // Streams accepted from the non-blocking listener may inherit its mode
+        stream.set_nonblocking(false)?;
+        stream.set_nodelay(true)?;
+        let reader = BufReader::new(stream.try_clone()?);
+        let (sender, incoming) = channel();
+
+        std::thread::spawn(move || {
+            for line in reader.lines() {
+                let event = match line {
+                    Ok(line) if line.trim().is_empty() => continue,
+                    Ok(line) => match Message::decode(&line) {
+                        Ok(message) => NetEvent::Message(message),
+                        Err(e) => NetEvent::Disconnected(format!("Bad message: {e}")),
+                    },
+                    Err(e) => NetEvent::Disconnected(e.to_string()),
+                };
+                let stop = matches!(event, NetEvent::Disconnected(_));
+                if sender.send(event).is_err() || stop {
+                    return;
+                }
+            }
+            let _ = sender.send(NetEvent::Disconnected("Connection closed".to_string()));
+        });
+
+        Ok(Self { stream, incoming })
+    }
+
+    fn send(&mut self, message: &Message) -> std::io::Result<()> {
+        writeln!(self.stream, "{}", message.encode())
+    }
+
+    fn poll(&self) -> Option<NetEvent> {
+        match self.incoming.try_recv() {
+            Ok(event) => Some(event),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => {
+                Some(NetEvent::Disconnected("Connection closed".to_string()))
+            }
+        }
+    }
+
+    fn close(&mut self) {
+        let _ = self.stream.shutdown(std::net::Shutdown::Both);
+    }
+}
+
+enum Phase {
+    Listening(TcpListener),
+    Connecting(Receiver<std::io::Result<TcpStream>>),
+    // Host waiting for the guest's HELLO, or guest waiting for the host's
+    Handshake(Connection),
+    Playing(Connection),
+    Closed(String),
+}
+
+struct Session {
+    phase: Phase,
+    // Board size, win length and host side. Known up front by the host,
+    // learned from the HELLO by the guest.
+    setup: Option<(usize, usize, CellState)>,
+    is_host: bool,
+}
+
+/// What the lobby should show for the current session.
+pub enum SessionStatus {
+    Idle,
+    Waiting(String),
+    /// Handshake done. Size, win length and our own side.
+    Started(usize, usize, CellState),
+    Closed(String),
+}
+
+static mut SESSION: Option<Session> = None;
+
+/// Starts listening for a guest. The host plays `host_symbol` in the first game.
+pub fn host(
+    port: u16,
+    size: usize,
+    win_length: usize,
+    host_symbol: CellState,
+) -> Result<(), String> {
+    close();
+    let listener =
+        TcpListener::bind(("0.0.0.0", port)).map_err(|e| format!("Can't host on {port}: {e}"))?;
+    listener
+        .set_nonblocking(true)
+        .map_err(|e| format!("Can't host on {port}: {e}"))?;
+
+    unsafe {
+        SESSION = Some(Session {
+            phase: Phase::Listening(listener),
+            setup: Some((size, win_length, host_symbol)),
+            is_host: true,
+        });
+    }
+    Ok(())
+}
+
+/// Connects to a host. `address` is `ip:port` or just `ip` for the default port.
+pub fn join(address: &str) -> Result<(), String> {
+    close();
+    let address = if address.contains(':') {
+        address.to_string()
+    } else {
+        format!("{address}:{DEFAULT_PORT}")
+    };
+    let target: SocketAddr = address
+        .to_socket_addrs()
+        .ok()
+        .and_then(|mut addrs| addrs.next())
+        .ok_or_else(|| format!("Bad address `{address}`"))?;
+
+    // Connecting can take a while, so it runs off the main thread
+    let (sender, receiver) = channel();
+    std::thread::spawn(move || {
+        let _ = sender.send(TcpStream::connect_timeout(&target, CONNECT_TIMEOUT));
+    });
+
+    unsafe {
+        SESSION = Some(Session {
+            phase: Phase::Connecting(receiver),
+            setup: None,
+            is_host: false,
+        });
+    }
+    Ok(())
+}
+
+/// Advances the connection and handshake. Call once per frame from the lobby.
+#[allow(static_mut_refs)]
+pub fn poll() -> SessionStatus {
+    let Some(session) = (unsafe { SESSION.as_mut() }) else {
+        return SessionStatus::Idle;
+    };
+
+    let phase = std::mem::replace(&mut session.phase, Phase::Closed(String::new()));
+    session.phase = match phase {
+        Phase::Listening(listener) => match listener.accept() {
+            Ok((stream, _)) => start_handshake(session.is_host, session.setup, stream),
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => Phase::Listening(listener),
+            Err(e) => Phase::Closed(e.to_string()),
+        },
+        Phase::Connecting(receiver) => match receiver.try_recv() {
+            Ok(Ok(stream)) => start_handshake(session.is_host, session.setup, stream),
+            Ok(Err(e)) => Phase::Closed(format!("Can't connect: {e}")),
+            Err(TryRecvError::Empty) => Phase::Connecting(receiver),
+            Err(TryRecvError::Disconnected) => Phase::Closed("Can't connect".to_string()),
+        },
+        Phase::Handshake(mut connection) => match connection.poll() {
+            Some(NetEvent::Message(Message::Hello { version, setup })) => {
+                if version != PROTOCOL_VERSION {
+                    let reason = format!(
+                        "Version mismatch: we speak {PROTOCOL_VERSION}, they speak {version}"
+                    );
+                    let _ = connection.send(&Message::Error(reason.clone()));
+                    connection.close();
+                    Phase::Closed(reason)
+                } else if session.is_host {
+                    // The guest accepted our setup
+                    Phase::Playing(connection)
+                } else if let Some((size, win_length, _)) = setup
+                    && let Err(reason) = check_setup(size, win_length)
+                {
+                    let _ = connection.send(&Message::Error(reason.clone()));
+                    connection.close();
+                    Phase::Closed(reason)
+                } else if let Some(setup) = setup {
+                    session.setup = Some(setup);
+                    let reply = Message::Hello {
+                        version: PROTOCOL_VERSION,
+                        setup: None,
+                    };
+                    match connection.send(&reply) {
+                        Ok(()) => Phase::Playing(connection),
+                        Err(e) => Phase::Closed(e.to_string()),
+                    }
+                } else {
+                    connection.close();
+                    Phase::Closed("Host sent no board setup".to_string())
+                }
+            }
+            Some(NetEvent::Message(Message::Error(text))) => {
+                connection.close();
+                Phase::Closed(text)
+            }
+            Some(NetEvent::Message(_)) => {
+                connection.close();
+                Phase::Closed("Unexpected message during handshake".to_string())
+            }
+            Some(NetEvent::Disconnected(reason)) => Phase::Closed(reason),
+            None => Phase::Handshake(connection),
+        },
+        other => other,
+    };
+
+    match &session.phase {
+        Phase::Listening(listener) => {
+            let port = listener.local_addr().map(|a| a.port()).unwrap_or(0);
+            SessionStatus::Waiting(format!("Waiting for an opponent on port {port}..."))
+        }
+        Phase::Connecting(_) => SessionStatus::Waiting("Connecting...".to_string()),
+        Phase::Handshake(_) => SessionStatus::Waiting("Saying hello...".to_string()),
+        Phase::Playing(_) => match session.setup {
+            Some((size, win_length, host_symbol)) => {
+                let ours = if session.is_host {
+                    host_symbol
+                } else {
+                    host_symbol.opponent()
+                };
+                SessionStatus::Started(size, win_length, ours)
+            }
+            None => SessionStatus::Closed("No board setup".to_string()),
+        },
+        Phase::Closed(reason) => SessionStatus::Closed(reason.clone()),
+    }
+}
+
+/// Sends a message to the opponent. A failed write closes the session.
+#[allow(static_mut_refs)]
+pub fn send(message: &Message) {
+    unsafe {
+        if let Some(session) = SESSION.as_mut()
+            && let Phase::Playing(connection) = &mut session.phase
+            && let Err(e) = connection.send(message)
+        {
+            connection.close();
+            session.phase = Phase::Closed(e.to_string());
+        }
+    }
+}
+
+/// Next event from the opponent during a game, if any arrived.
+#[allow(static_mut_refs)]
+pub fn receive() -> Option<NetEvent> {
+    unsafe {
+        let session = SESSION.as_mut()?;
+        let Phase::Playing(connection) = &mut session.phase else {
+            return None;
+        };
+
+        let event = connection.poll()?;
+        let reason = match &event {
+            NetEvent::Message(Message::Bye) => Some("Opponent left".to_string()),
+            NetEvent::Message(Message::Error(text)) => Some(text.clone()),
+            NetEvent::Disconnected(reason) => Some(reason.clone()),
+            NetEvent::Message(_) => None,
+        };
+        if let Some(reason) = reason {
+            connection.close();
+            session.phase = Phase::Closed(reason);
+        }
+        Some(event)
+    }
+}
+
+/// Ends the session with an `ERROR`, for an opponent that broke the rules.
+#[allow(static_mut_refs)]
+pub fn abort(reason: &str) {
+    unsafe {
+        if let Some(session) = SESSION.as_mut()
+            && let Phase::Playing(connection) = &mut session.phase
+        {
+            let _ = connection.send(&Message::Error(reason.to_string()));
+            connection.close();
+            session.phase = Phase::Closed(reason.to_string());
+        }
+    }
+}
+
+#[allow(static_mut_refs)]
+pub fn is_connected() -> bool {
+    unsafe {
+        matches!(
+            SESSION.as_ref(),
+            Some(Session {
+                phase: Phase::Playing(_),
+                ..
+            })
+        )
+    }
+}
+
+/// Says goodbye if connected and drops the session.
+#[allow(static_mut_refs)]
+pub fn close() {
+    unsafe {
+        if let Some(mut session) = SESSION.take() {
+            match &mut session.phase {
+                Phase::Playing(connection) => {
+                    let _ = connection.send(&Message::Bye);
+                    connection.close();
+                }
+                Phase::Handshake(connection) => connection.close(),
+                _ => {}
+            }
+        }
+    }
+}
+
+fn start_handshake(
+    is_host: bool,
+    setup: Option<(usize, usize, CellState)>,
+    stream: TcpStream,
+) -> Phase {
+    let mut connection = match Connection::new(stream) {
+        Ok(connection) => connection,
+        Err(e) => return Phase::Closed(e.to_string()),
+    };
+
+    if is_host {
+        let hello = Message::Hello {
+            version: PROTOCOL_VERSION,
+            setup,
+        };
+        if let Err(e) = connection.send(&hello) {
+            return Phase::Closed(e.to_string());
+        }
+    }
+    Phase::Handshake(connection)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn messages_round_trip() {
+        let messages = [
+            Message::Hello {
+                version: PROTOCOL_VERSION,
+                setup: Some((4, 3, CellState::O)),
+            },
+            Message::Hello {
+                version: PROTOCOL_VERSION,
+                setup: Some((15, 5, CellState::X)),
+            },
+            Message::Hello {
+                version: PROTOCOL_VERSION,
+                setup: None,
+            },
+            Message::Move(0, 0),
+            Message::Move(14, 2),
+            Message::Resign,
+            Message::Rematch,
+            Message::Bye,
+            Message::Error("Version mismatch".to_string()),
+        ];
+        for message in messages {
+            let line = message.encode();
+            assert!(Message::decode(&line) == Ok(message), "{line}");
+        }
+    }
+
+    #[test]
+    fn rejects_bad_messages() {
+        for line in [
+            "",
+            "HI",
+            "HELLO",
+            "HELLO one",
+            "HELLO 1 3 3",
+            "HELLO 1 3 3 z",
+            "MOVE",
+            "MOVE 2b",
+            "MOVE a0",
+            "RESIGN now",
+        ] {
+            assert!(Message::decode(line).is_err(), "accepted {line:?}");
+        }
+    }
+
+    #[test]
+    fn checks_the_host_setup() {
+        assert!(check_setup(3, 3).is_ok());
+        assert!(check_setup(MAX_BOARD_SIZE, 5).is_ok());
+        assert!(check_setup(MIN_BOARD_SIZE - 1, MIN_WIN_LENGTH).is_err());
+        assert!(check_setup(MAX_BOARD_SIZE + 1, 5).is_err());
+        assert!(check_setup(4, 5).is_err());
+        assert!(check_setup(4, MIN_WIN_LENGTH - 1).is_err());
+    }
+}
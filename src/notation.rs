@@ -0,0 +1,334 @@
+//! Plain-text game notation used for save files.
+//!
+//! A saved game is a handful of `key: value` lines. Blank lines and lines
+//! starting with `#` are ignored, and keys may come in any order:
+//!
+//! ```text
+//! # Tic Tac Toe game
+//! mode: pvai hard
+//! player: o
+//! board: 4x4
+//! win: 4
+//! moves: b2 c3 a1
+//! ```
+//!
+//! - `mode` is `pvp`, `pvai` followed by `easy`, `medium`, `hard` or
+//!   `perfect`, `online`, or `aivsai` followed by X's level and engine and
+//!   then O's, e.g. `aivsai perfect minimax hard mcts`.
+//! - `player` is the human side in `pvai` games (`x` or `o`). Optional for `pvp`.
+//! - `board` is the grid size, `win` the number in a row needed to win.
+//! - `rule` is `standard` or `misere` (completing a line loses). Optional,
+//!   and only written for misère games, so other saves stay as they were.
+//! - `topology` is `flat`, `cylinder` (the left and right edges join) or
+//!   `torus` (the top and bottom join too). Optional, and only written when
+//!   lines can wrap.
+//! - `moves` lists every move in order, X first. A move is a column letter
+//!   (`a` is the leftmost column) followed by a row number (`1` is the top row).
+//!
+//! Games exported from the analysis screen carry the analysis as `#` lines
+//! after the game, so they load like any other save.
+
+use crate::spectator::Sides;
+use crate::state::GameState;
+use tictactoe_core::ai::{Difficulty, Engine};
+use tictactoe_core::position::{MAX_BOARD_SIZE, MIN_BOARD_SIZE, MIN_WIN_LENGTH};
+use tictactoe_core::{CellState, Rule, Topology};
+
+pub struct GameRecord {
+    pub mode: GameState,
+    pub sides: Option<Sides>, // level and engine of each AI in AI vs AI games
+    pub player_symbol: CellState,
+    pub size: usize,
+    pub win_length: usize,
+    pub rule: Rule,
+    pub topology: Topology,
+    pub moves: Vec<(usize, usize)>,
+}
+
+impl GameRecord {
+    pub fn to_notation(&self) -> String {
+        let mode = match (self.mode, self.sides) {
+            (GameState::PvAI(difficulty), _) => {
+                format!("pvai {}", difficulty.name().to_lowercase())
+            }
+            (GameState::Online, _) => "online".to_string(),
+            (GameState::AiVsAi, Some(sides)) => {
+                let side = |(level, engine): (Difficulty, Engine)| {
+                    format!("{} {}", level.name(), engine.name()).to_lowercase()
+                };
+                format!("aivsai {} {}", side(sides[0]), side(sides[1]))
+            }
+            _ => "pvp".to_string(),
+        };
+        let player = if self.player_symbol == CellState::O {
+            "o"
+        } else {
+            "x"
+        };
+        let moves: Vec<String> = self
+            .moves
+            .iter()
+            .map(|&(row, col)| format_move(row, col))
+            .collect();
+
+        let rule = match self.rule {
+            Rule::Standard => "",
+            Rule::Misere => "rule: misere\n",
+        };
+        let topology = match self.topology {
+            Topology::Flat => String::new(),
+            topology => format!("topology: {}\n", topology.name().to_lowercase()),
+        };
+
+        format!(
+            "# Tic Tac Toe game\nmode: {mode}\nplayer: {player}\nboard: {size}x{size}\nwin: {win}\n{rule}{topology}moves: {moves}\n",
+            size = self.size,
+            win = self.win_length,
+            moves = moves.join(" "),
+        )
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut mode = None;
+        let mut sides = None;
+        let mut player_symbol = CellState::X;
+        let mut size = None;
+        let mut win_length = None;
+        let mut rule = Rule::Standard;
+        let mut topology = Topology::Flat;
+        let mut moves = Vec::new();
+
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let Some((key, value)) = line.split_once(':') else {
+                return Err(format!("line {}: expected `key: value`", index + 1));
+            };
+            let value = value.trim().to_lowercase();
+
+            match key.trim().to_lowercase().as_str() {
+                "mode" => {
+                    let (parsed, parsed_sides) = parse_mode(&value)?;
+                    mode = Some(parsed);
+                    sides = parsed_sides;
+                }
+                "player" => {
+                    player_symbol = match value.as_str() {
+                        "x" => CellState::X,
+                        "o" => CellState::O,
+                        _ => return Err(format!("unknown player `{value}`")),
+                    }
+                }
+                "board" => {
+                    let (rows, cols) = value
+                        .split_once('x')
+                        .ok_or_else(|| format!("board should look like 3x3, got `{value}`"))?;
+                    let rows = parse_number(rows, "board")?;
+                    if rows != parse_number(cols, "board")? {
+                        return Err("only square boards are supported".to_string());
+                    }
+                    size = Some(rows);
+                }
+                "win" => win_length = Some(parse_number(&value, "win")?),
+                "rule" => {
+                    rule = Rule::ALL
+                        .into_iter()
+                        .find(|rule| rule.name().eq_ignore_ascii_case(&value))
+                        .ok_or_else(|| format!("unknown rule `{value}`"))?
+                }
+                "topology" => {
+                    topology = Topology::ALL
+                        .into_iter()
+                        .find(|topology| topology.name().eq_ignore_ascii_case(&value))
+                        .ok_or_else(|| format!("unknown topology `{value}`"))?
+                }
+                "moves" => {
+                    moves = value
+                        .split_whitespace()
+                        .map(parse_move)
+                        .collect::<Result<_, _>>()?;
+                }
+                other => return Err(format!("unknown key `{other}`")),
+            }
+        }
+
+        let mode = mode.ok_or("missing `mode`")?;
+        let size = size.ok_or("missing `board`")?;
+        let win_length = win_length.ok_or("missing `win`")?;
+
+        if !(MIN_BOARD_SIZE..=MAX_BOARD_SIZE).contains(&size) {
+            return Err(format!(
+                "board must be between {MIN_BOARD_SIZE}x{MIN_BOARD_SIZE} and {MAX_BOARD_SIZE}x{MAX_BOARD_SIZE}"
+            ));
+        }
+        if !(MIN_WIN_LENGTH..=size).contains(&win_length) {
+            return Err(format!("win must be between {MIN_WIN_LENGTH} and {size}"));
+        }
+        if let Some(&(row, col)) = moves.iter().find(|&&(row, col)| row >= size || col >= size) {
+            return Err(format!("move {} is off the board", format_move(row, col)));
+        }
+
+        Ok(Self {
+            mode,
+            sides,
+            player_symbol,
+            size,
+            win_length,
+            rule,
+            topology,
+            moves,
+        })
+    }
+}
+
+pub fn save(path: &str, record: &GameRecord) -> Result<(), String> {
+    std::fs::write(path, record.to_notation()).map_err(|e| format!("{path}: {e}"))
+}
+
+/// Saves the game followed by `notes`, one `#` comment line each.
+pub fn save_annotated(path: &str, record: &GameRecord, notes: &[String]) -> Result<(), String> {
+    let mut text = record.to_notation();
+    text.push('\n');
+    for note in notes {
+        text.push_str(&format!("# {note}\n"));
+    }
+    std::fs::write(path, text).map_err(|e| format!("{path}: {e}"))
+}
+
+pub fn load(path: &str) -> Result<GameRecord, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("{path}: {e}"))?;
+    GameRecord::parse(&text)
+}
+
+pub fn format_move(row: usize, col: usize) -> String {
+    format!("{}{}", (b'a' + col as u8) as char, row + 1)
+}
+
+pub fn parse_move(text: &str) -> Result<(usize, usize), String> {
+    let mut chars = text.chars();
+    let col = match chars.next() {
+        Some(c @ 'a'..='z') => c as usize - 'a' as usize,
+        _ => return Err(format!("bad move `{text}`")),
+    };
+    let row: usize = chars
+        .as_str()
+        .parse()
+        .map_err(|_| format!("bad move `{text}`"))?;
+    if row == 0 {
+        return Err(format!("bad move `{text}`"));
+    }
+    Ok((row - 1, col))
+}
+
+/// The mode, and for AI vs AI games what each side plays with.
+fn parse_mode(text: &str) -> Result<(GameState, Option<Sides>), String> {
+    let words: Vec<&str> = text.split_whitespace().collect();
+    match words.as_slice() {
+        ["pvp"] => Ok((GameState::PvP, None)),
+        ["online"] => Ok((GameState::Online, None)),
+        ["pvai"] => Ok((GameState::PvAI(Difficulty::Perfect), None)),
+        ["pvai", level] => Ok((GameState::PvAI(parse_difficulty(level)?), None)),
+        ["aivsai", x_level, x_engine, o_level, o_engine] => {
+            let sides = [
+                (parse_difficulty(x_level)?, parse_engine(x_engine)?),
+                (parse_difficulty(o_level)?, parse_engine(o_engine)?),
+            ];
+            Ok((GameState::AiVsAi, Some(sides)))
+        }
+        _ => Err(format!("unknown mode `{text}`")),
+    }
+}
+
+fn parse_difficulty(text: &str) -> Result<Difficulty, String> {
+    Difficulty::ALL
+        .into_iter()
+        .find(|d| d.name().eq_ignore_ascii_case(text))
+        .ok_or_else(|| format!("unknown difficulty `{text}`"))
+}
+
+fn parse_engine(text: &str) -> Result<Engine, String> {
+    Engine::ALL
+        .into_iter()
+        .find(|e| e.name().eq_ignore_ascii_case(text))
+        .ok_or_else(|| format!("unknown engine `{text}`"))
+}
+
+fn parse_number(text: &str, key: &str) -> Result<usize, String> {
+    text.trim()
+        .parse()
+        .map_err(|_| format!("`{key}` should be a number, got `{text}`"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips() {
+        let modes = [
+            (GameState::PvP, None),
+            (GameState::PvAI(Difficulty::Hard), None),
+            (GameState::Online, None),
+            (
+                GameState::AiVsAi,
+                Some([
+                    (Difficulty::Perfect, Engine::Minimax),
+                    (Difficulty::Medium, Engine::Mcts),
+                ]),
+            ),
+        ];
+        for (mode, sides) in modes {
+            let record = GameRecord {
+                mode,
+                sides,
+                player_symbol: CellState::O,
+                size: 5,
+                win_length: 4,
+                rule: Rule::Misere,
+                topology: Topology::Torus,
+                moves: vec![(1, 1), (2, 2), (0, 4), (4, 0)],
+            };
+            let parsed = GameRecord::parse(&record.to_notation()).unwrap();
+
+            assert!(parsed.mode == record.mode);
+            assert_eq!(parsed.sides, record.sides);
+            assert_eq!(parsed.player_symbol, record.player_symbol);
+            assert_eq!(parsed.size, record.size);
+            assert_eq!(parsed.win_length, record.win_length);
+            assert_eq!(parsed.rule, record.rule);
+            assert_eq!(parsed.topology, record.topology);
+            assert_eq!(parsed.moves, record.moves);
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_input() {
+        let valid = "mode: pvp\nboard: 3x3\nwin: 3\nmoves: b2 a1";
+        assert!(GameRecord::parse(valid).is_ok());
+
+        for text in [
+            "mode: pvp\nboard: 3x3\nwin: 3\nmoves: b2\njunk",
+            "mode: pvp\nboard: 3x3\nmoves: b2",
+            "mode: chess\nboard: 3x3\nwin: 3",
+            "mode: pvai impossible\nboard: 3x3\nwin: 3",
+            "mode: aivsai perfect minimax\nboard: 3x3\nwin: 3",
+            "mode: aivsai perfect minimax hard alphazero\nboard: 3x3\nwin: 3",
+            "mode: pvp\nboard: 3x4\nwin: 3",
+            "mode: pvp\nboard: 99x99\nwin: 3",
+            "mode: pvp\nboard: 3x3\nwin: 4",
+            "mode: pvp\nboard: 3x3\nwin: three",
+            "mode: pvp\nboard: 3x3\nwin: 3\nmoves: d1",
+            "mode: pvp\nboard: 3x3\nwin: 3\nmoves: a0",
+            "mode: pvp\nboard: 3x3\nwin: 3\nmoves: 2b",
+            "mode: pvp\nboard: 3x3\nwin: 3\nrule: backwards",
+            "mode: pvp\nboard: 3x3\nwin: 3\ntopology: sphere",
+            "mode: pvp\nboard: 3x3\nwin: 3\nplayer: z",
+            "mode: pvp\nboard: 3x3\nwin: 3\ncolor: red",
+        ] {
+            assert!(GameRecord::parse(text).is_err(), "accepted {text:?}");
+        }
+    }
+}
@@ -0,0 +1,179 @@
+use crate::board::Board;
+use crate::config::*;
+use crate::player::AiWorker;
+use crate::state::GameState;
+use crate::utils::*;
+use macroquad::prelude::*;
+use tictactoe_core::CellState;
+use tictactoe_core::ai::Difficulty;
+use tictactoe_core::order_chaos::{self, OrderChaos, OrderChaosOutcome, Role, SIZE, WIN_LENGTH};
+use tictactoe_core::rng::Rng;
+use tictactoe_core::wild::WildMove;
+
+static mut ROLE: Role = Role::Order;
+
+struct OrderChaosGame {
+    game: OrderChaos,
+    board: Board,
+    ai: Option<Difficulty>,
+    human: Role, // the human's side against the AI
+    ai_worker: AiWorker<WildMove>,
+}
+
+impl OrderChaosGame {
+    fn new(ai: Option<Difficulty>, human: Role) -> Self {
+        Self {
+            game: OrderChaos::new(),
+            board: Board::new(SIZE, WIN_LENGTH),
+            ai,
+            human,
+            ai_worker: AiWorker::new(Rng::from_time()),
+        }
+    }
+
+    fn is_ai_turn(&self) -> bool {
+        self.ai.is_some() && !self.game.is_over() && self.game.turn() != self.human
+    }
+
+    /// Whether `role` is the human in a game against the AI.
+    fn is_human_vs_ai(&self, role: Role) -> bool {
+        self.ai.is_some() && role == self.human
+    }
+
+    /// Plays a legal move in its symbol's color, since both roles place
+    /// either symbol.
+    fn play(&mut self, mv: WildMove) {
+        if self.game.play(mv).is_err() {
+            return;
+        }
+
+        let (row, col) = mv.cell;
+        self.board.set_cell(row, col, mv.symbol);
+        self.board.check_winner();
+
+        let theme = crate::theme::get_current_theme();
+        let color = if mv.symbol == CellState::X {
+            theme.primary
+        } else {
+            theme.secondary
+        };
+        crate::config::play_move();
+        crate::particles::spawn_move(self.board.get_cell_center(row, col), color);
+
+        if let Some(OrderChaosOutcome::Line(line)) = self.game.outcome() {
+            crate::config::play_win();
+            for &(row, col) in line {
+                crate::particles::spawn_win(self.board.get_cell_center(row, col), color);
+            }
+        }
+    }
+}
+
+static mut ORDER_CHAOS: Option<OrderChaosGame> = None;
+
+/// The side the human takes against the AI in the next game.
+pub fn role() -> Role {
+    unsafe { ROLE }
+}
+
+pub fn set_role(role: Role) {
+    unsafe {
+        ROLE = role;
+    }
+}
+
+/// Starts a new game, against the AI at `ai` or between two people.
+pub fn start(ai: Option<Difficulty>, human: Role) {
+    unsafe {
+        ORDER_CHAOS = Some(OrderChaosGame::new(ai, human));
+    }
+}
+
+#[allow(static_mut_refs)]
+pub fn update(scale: &ScreenScale) -> Option<GameState> {
+    let Some(oc) = (unsafe { ORDER_CHAOS.as_mut() }) else {
+        return Some(GameState::Menu);
+    };
+
+    let mouse = mouse_to_virtual(scale);
+    let font = crate::config::get_font();
+    let theme = crate::theme::get_current_theme();
+
+    // ---- Draw UI ----
+    let mode_text = match oc.ai {
+        Some(difficulty) => format!("Order & Chaos vs AI ({})", difficulty.name()),
+        None => "Order & Chaos: Player vs Player".to_string(),
+    };
+    draw_label(&mode_text, 20.0, 40.0, 24, theme.text);
+    draw_label(
+        "ESC: Menu | R: Reset | T: Theme",
+        20.0,
+        70.0,
+        18,
+        theme.muted,
+    );
+
+    match oc.game.winner() {
+        Some(winner) => {
+            // Colors belong to the symbols, which both sides use
+            let msg = if oc.is_human_vs_ai(winner) {
+                "YOU WIN!".to_string()
+            } else if oc.ai.is_some() {
+                "AI WINS!".to_string()
+            } else {
+                format!("{} WINS!", winner.name().to_uppercase())
+            };
+            draw_end_banner(&msg, theme.text);
+        }
+        None => {
+            let turn = oc.game.turn();
+            let turn_text = if oc.ai_worker.is_thinking() {
+                "AI is thinking...".to_string()
+            } else if oc.is_human_vs_ai(turn) {
+                format!("Your turn ({})", turn.name())
+            } else {
+                format!("Turn: {}", turn.name())
+            };
+            let turn_dim = measure_text(&turn_text, font, 24, 1.0);
+            draw_label(
+                &turn_text,
+                VIRTUAL_WIDTH - turn_dim.width - 20.0,
+                40.0,
+                24,
+                theme.text,
+            );
+            draw_label(
+                "Left click: place X | Right click or Shift+click: place O",
+                20.0,
+                92.0,
+                16,
+                theme.muted,
+            );
+        }
+    }
+
+    // ---- Draw Board ----
+    oc.board.draw();
+
+    // ---- Moves ----
+    if oc.is_ai_turn() {
+        if let Some(mv) = oc.ai_worker.drive(
+            &oc.game,
+            oc.ai.unwrap_or(Difficulty::Perfect),
+            order_chaos::find_best_move,
+        ) {
+            oc.play(mv);
+        }
+    } else if !oc.game.is_over()
+        && let Some(symbol) = pick_symbol()
+        && let Some(cell) = oc.board.get_cell_at(mouse)
+        && oc.board.state(cell.0, cell.1) == CellState::Empty
+    {
+        oc.play(WildMove { cell, symbol });
+    }
+
+    // ---- Controls ----
+    variant_controls(unsafe { &mut ORDER_CHAOS }, |oc| {
+        OrderChaosGame::new(oc.ai, oc.human)
+    })
+}
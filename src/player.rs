@@ -0,0 +1,285 @@
+use crate::board::Board;
+use crate::net::Message;
+use macroquad::prelude::*;
+use std::collections::VecDeque;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::time::Duration;
+use tictactoe_core::ai::{Difficulty, Engine};
+use tictactoe_core::rng::Rng;
+use tictactoe_core::{CellState, Move};
+
+/// One side of a game. Every frame the game asks the player whose turn it is
+/// for a move; a player that hasn't decided yet returns `None` and is asked
+/// again next frame. The game checks the move is legal before playing it.
+pub trait Player {
+    fn poll_move(&mut self, board: &Board, symbol: CellState, mouse: Vec2) -> Option<Move>;
+
+    /// Someone sitting at this screen. Undo and redo skip over everyone
+    /// else's moves, so taking back a move also takes back the reply.
+    fn is_local_human(&self) -> bool {
+        false
+    }
+
+    /// Still working out a move in the background.
+    fn is_thinking(&self) -> bool {
+        false
+    }
+
+    /// The other side just played `mv`.
+    fn opponent_moved(&mut self, _mv: Move) {}
+
+    /// Drops a move in progress, because the position it was for is gone.
+    fn cancel(&mut self) {}
+
+    /// Extra drawing on top of the board while it's this player's turn.
+    fn draw(&self, _board: &Board, _symbol: CellState) {}
+}
+
+/// Clicks a cell with the mouse.
+pub struct MouseHuman;
+
+impl Player for MouseHuman {
+    fn poll_move(&mut self, board: &Board, _symbol: CellState, mouse: Vec2) -> Option<Move> {
+        if is_mouse_button_pressed(MouseButton::Left) {
+            board.get_cell_at(mouse)
+        } else {
+            None
+        }
+    }
+
+    fn is_local_human(&self) -> bool {
+        true
+    }
+}
+
+/// Moves a cursor with the arrow keys and places with Enter or Space.
+pub struct KeyboardHuman {
+    cursor: Move,
+}
+
+impl KeyboardHuman {
+    pub fn new(board_size: usize) -> Self {
+        Self {
+            cursor: (board_size / 2, board_size / 2),
+        }
+    }
+}
+
+impl Player for KeyboardHuman {
+    fn poll_move(&mut self, board: &Board, _symbol: CellState, _mouse: Vec2) -> Option<Move> {
+        let (row, col) = &mut self.cursor;
+        if is_key_pressed(KeyCode::Up) {
+            *row = row.saturating_sub(1);
+        }
+        if is_key_pressed(KeyCode::Down) {
+            *row = (*row + 1).min(board.rows() - 1);
+        }
+        if is_key_pressed(KeyCode::Left) {
+            *col = col.saturating_sub(1);
+        }
+        if is_key_pressed(KeyCode::Right) {
+            *col = (*col + 1).min(board.cols() - 1);
+        }
+
+        if is_key_pressed(KeyCode::Enter) || is_key_pressed(KeyCode::Space) {
+            Some(self.cursor)
+        } else {
+            None
+        }
+    }
+
+    fn is_local_human(&self) -> bool {
+        true
+    }
+
+    fn draw(&self, board: &Board, symbol: CellState) {
+        let theme = crate::theme::get_current_theme();
+        let color = if symbol == CellState::O {
+            theme.secondary
+        } else {
+            theme.primary
+        };
+        let (row, col) = self.cursor;
+        let inset = board.cell_size * 0.08;
+        draw_rectangle_lines(
+            board.x + col as f32 * board.cell_size + inset,
+            board.y + row as f32 * board.cell_size + inset,
+            board.cell_size - inset * 2.0,
+            board.cell_size - inset * 2.0,
+            3.0,
+            color,
+        );
+    }
+}
+
+/// Plays any free cell.
+pub struct RandomPlayer {
+    rng: Rng,
+}
+
+impl RandomPlayer {
+    pub fn new(rng: Rng) -> Self {
+        Self { rng }
+    }
+}
+
+impl Player for RandomPlayer {
+    fn poll_move(&mut self, board: &Board, _symbol: CellState, _mouse: Vec2) -> Option<Move> {
+        let moves = board.position.empty_cells();
+        (!moves.is_empty()).then(|| moves[self.rng.gen_index(moves.len())])
+    }
+}
+
+/// A search running on a worker thread, so the window keeps drawing for the
+/// whole think time. The RNG goes along with each search and comes back
+/// advanced, so a seeded game stays reproducible.
+pub struct AiWorker<M> {
+    rng: Rng,
+    // The move (and the advanced RNG) arrives here from the search thread
+    search: Option<Receiver<(Option<M>, Rng)>>,
+}
+
+impl<M: Send + 'static> AiWorker<M> {
+    pub fn new(rng: Rng) -> Self {
+        Self { rng, search: None }
+    }
+
+    /// Starts `find_move` on a worker thread, dropping any search still
+    /// running.
+    pub fn start(&mut self, find_move: impl FnOnce(&mut Rng) -> Option<M> + Send + 'static) {
+        let mut rng = self.rng;
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            let best_move = find_move(&mut rng);
+            // Nobody is listening any more if the search was cancelled
+            let _ = tx.send((best_move, rng));
+        });
+        self.search = Some(rx);
+    }
+
+    /// The finished search's move, if there is one yet.
+    pub fn poll(&mut self) -> Option<M> {
+        match self.search.as_ref()?.try_recv() {
+            Ok((best_move, rng)) => {
+                self.search = None;
+                self.rng = rng;
+                best_move
+            }
+            Err(TryRecvError::Empty) => None,
+            // The search thread died; the next `start` tries again
+            Err(TryRecvError::Disconnected) => {
+                self.search = None;
+                None
+            }
+        }
+    }
+
+    pub fn is_thinking(&self) -> bool {
+        self.search.is_some()
+    }
+
+    /// Plays a variant's AI turn: searches a copy of `game` unless a search
+    /// is already going, and hands back the move once it's found.
+    pub fn drive<G: Clone + Send + 'static>(
+        &mut self,
+        game: &G,
+        difficulty: Difficulty,
+        find_best_move: fn(&G, Difficulty, Duration, &mut Rng) -> Option<M>,
+    ) -> Option<M> {
+        if !self.is_thinking() {
+            let game = game.clone();
+            let think_time = crate::game::think_time();
+            self.start(move |rng| find_best_move(&game, difficulty, think_time, rng));
+        }
+        self.poll()
+    }
+
+    /// Drops a search in progress, whose thread finishes unheard.
+    pub fn cancel(&mut self) {
+        self.search = None;
+    }
+}
+
+/// The minimax or MCTS engine from `tictactoe_core::ai`, searching on an
+/// `AiWorker`.
+pub struct AiPlayer {
+    difficulty: Difficulty,
+    engine: Engine,
+    think_time: Duration,
+    worker: AiWorker<Move>,
+}
+
+impl AiPlayer {
+    pub fn new(difficulty: Difficulty, engine: Engine, think_time: Duration, rng: Rng) -> Self {
+        Self {
+            difficulty,
+            engine,
+            think_time,
+            worker: AiWorker::new(rng),
+        }
+    }
+}
+
+impl Player for AiPlayer {
+    fn poll_move(&mut self, board: &Board, symbol: CellState, _mouse: Vec2) -> Option<Move> {
+        if !self.worker.is_thinking() {
+            let position = board.position.clone();
+            let (difficulty, engine, think_time) = (self.difficulty, self.engine, self.think_time);
+            self.worker.start(move |rng| {
+                tictactoe_core::ai::find_best_move(
+                    &position, symbol, difficulty, engine, think_time, rng,
+                )
+            });
+            return None;
+        }
+        self.worker.poll()
+    }
+
+    fn is_thinking(&self) -> bool {
+        self.worker.is_thinking()
+    }
+
+    fn cancel(&mut self) {
+        self.worker.cancel();
+    }
+}
+
+// Moves from the network peer, already checked by `game::handle_network`
+static mut REMOTE_MOVES: VecDeque<Move> = VecDeque::new();
+
+/// Hands a move that arrived over the network to the `NetworkPlayer`.
+#[allow(static_mut_refs)]
+pub fn deliver_remote_move(mv: Move) {
+    unsafe {
+        REMOTE_MOVES.push_back(mv);
+    }
+}
+
+/// A move from the peer that hasn't been played yet. The peer has to wait
+/// for its turn again before sending another.
+#[allow(static_mut_refs)]
+pub fn remote_move_pending() -> bool {
+    unsafe { !REMOTE_MOVES.is_empty() }
+}
+
+/// Drops moves that belong to a game that has ended.
+#[allow(static_mut_refs)]
+pub fn clear_remote_moves() {
+    unsafe {
+        REMOTE_MOVES.clear();
+    }
+}
+
+/// The opponent on the other end of the connection in `crate::net`.
+pub struct NetworkPlayer;
+
+impl Player for NetworkPlayer {
+    #[allow(static_mut_refs)]
+    fn poll_move(&mut self, _board: &Board, _symbol: CellState, _mouse: Vec2) -> Option<Move> {
+        unsafe { REMOTE_MOVES.pop_front() }
+    }
+
+    fn opponent_moved(&mut self, (row, col): Move) {
+        crate::net::send(&Message::Move(row, col));
+    }
+}
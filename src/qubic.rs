@@ -0,0 +1,277 @@
+use crate::board::Board;
+use crate::config::*;
+use crate::player::AiWorker;
+use crate::state::GameState;
+use crate::utils::*;
+use macroquad::prelude::*;
+use tictactoe_core::ai::Difficulty;
+use tictactoe_core::qubic::{self, Qubic, SIZE};
+use tictactoe_core::rng::Rng;
+use tictactoe_core::{CellState, Move, Outcome};
+
+// The four layers sit side by side across the screen, bottom layer first
+const LAYER_SIZE: f32 = 170.0;
+const GAP: f32 = 20.0;
+const LAYERS_X: f32 = (VIRTUAL_WIDTH - LAYER_SIZE * SIZE as f32 - GAP * (SIZE - 1) as f32) / 2.0;
+const LAYERS_Y: f32 = 245.0;
+
+struct QubicGame {
+    game: Qubic,
+    layers: Vec<Board>, // one view per layer
+    ai: Option<Difficulty>,
+    player_symbol: CellState, // the human's side against the AI
+    ai_worker: AiWorker<Move>,
+    win_anim_timer: f32,
+}
+
+impl QubicGame {
+    fn new(ai: Option<Difficulty>, player_symbol: CellState) -> Self {
+        let layers = (0..SIZE)
+            .map(|layer| {
+                let mut board = Board::new(SIZE, SIZE);
+                board.set_layout(layer_x(layer), LAYERS_Y, LAYER_SIZE);
+                board
+            })
+            .collect();
+
+        Self {
+            game: Qubic::new(),
+            layers,
+            ai,
+            player_symbol,
+            ai_worker: AiWorker::new(Rng::from_time()),
+            win_anim_timer: 0.0,
+        }
+    }
+
+    fn is_ai_turn(&self) -> bool {
+        self.ai.is_some() && !self.game.is_over() && self.game.turn() != self.player_symbol
+    }
+
+    /// Center of a cell, in whichever layer it's in.
+    fn cell_center(&self, mv: Move) -> Vec2 {
+        let (layer, row, col) = qubic::split(mv);
+        self.layers[layer].get_cell_center(row, col)
+    }
+
+    /// Plays a legal move on its layer's board. A win line goes to each layer
+    /// it passes through, so every layer shows its part.
+    fn play(&mut self, mv: Move) {
+        let mover = self.game.turn();
+        if self.game.play(mv).is_err() {
+            return;
+        }
+
+        let (layer, row, col) = qubic::split(mv);
+        self.layers[layer].set_cell(row, col, mover);
+
+        let theme = crate::theme::get_current_theme();
+        let color = if mover == CellState::X {
+            theme.primary
+        } else {
+            theme.secondary
+        };
+        crate::config::play_move();
+        crate::particles::spawn_move(self.cell_center(mv), color);
+
+        if let Some(Outcome::Win(_, line)) = self.game.outcome() {
+            crate::config::play_win();
+            for (index, board) in self.layers.iter_mut().enumerate() {
+                let cells: Vec<(usize, usize)> = line
+                    .iter()
+                    .map(|&mv| qubic::split(mv))
+                    .filter(|&(layer, _, _)| layer == index)
+                    .map(|(_, row, col)| (row, col))
+                    .collect();
+                board.winning_cells = (!cells.is_empty()).then_some(cells);
+            }
+            for &mv in line {
+                crate::particles::spawn_win(self.cell_center(mv), color);
+            }
+        }
+    }
+}
+
+fn layer_x(layer: usize) -> f32 {
+    LAYERS_X + layer as f32 * (LAYER_SIZE + GAP)
+}
+
+static mut QUBIC: Option<QubicGame> = None;
+
+/// Starts a new game, against the AI at `ai` or between two people.
+pub fn start(ai: Option<Difficulty>, player_symbol: CellState) {
+    unsafe {
+        QUBIC = Some(QubicGame::new(ai, player_symbol));
+    }
+}
+
+#[allow(static_mut_refs)]
+pub fn update(scale: &ScreenScale) -> Option<GameState> {
+    let Some(cube) = (unsafe { QUBIC.as_mut() }) else {
+        return Some(GameState::Menu);
+    };
+
+    let mouse = mouse_to_virtual(scale);
+    let font = crate::config::get_font();
+    let theme = crate::theme::get_current_theme();
+    let side_color = |side: CellState| {
+        if side == CellState::X {
+            theme.primary
+        } else {
+            theme.secondary
+        }
+    };
+
+    // ---- Draw UI ----
+    let mode_text = match cube.ai {
+        Some(difficulty) => format!("Qubic vs AI ({})", difficulty.name()),
+        None => "Qubic: Player vs Player".to_string(),
+    };
+    draw_label(&mode_text, 20.0, 40.0, 24, theme.text);
+    draw_label(
+        "ESC: Menu | R: Reset | T: Theme",
+        20.0,
+        70.0,
+        18,
+        theme.muted,
+    );
+
+    match cube.game.outcome() {
+        Some(outcome) => {
+            let (msg, color) = match outcome {
+                Outcome::Win(winner, _) => {
+                    let msg = match (cube.ai, *winner == cube.player_symbol) {
+                        (Some(_), true) => "YOU WIN!",
+                        (Some(_), false) => "AI WINS!",
+                        (None, _) if *winner == CellState::X => "PLAYER X WINS!",
+                        (None, _) => "PLAYER O WINS!",
+                    };
+                    let color = if *winner == CellState::X {
+                        theme.primary
+                    } else {
+                        theme.secondary
+                    };
+                    (msg, color)
+                }
+                Outcome::Draw => ("IT'S A DRAW!", theme.muted),
+            };
+            draw_end_banner(msg, color);
+        }
+        None => {
+            let turn_text = if cube.ai_worker.is_thinking() {
+                "AI is thinking...".to_string()
+            } else {
+                format!(
+                    "Turn: {}",
+                    if cube.game.turn() == CellState::X {
+                        "X"
+                    } else {
+                        "O"
+                    }
+                )
+            };
+            let turn_dim = measure_text(&turn_text, font, 24, 1.0);
+            draw_label(
+                &turn_text,
+                VIRTUAL_WIDTH - turn_dim.width - 20.0,
+                40.0,
+                24,
+                theme.text,
+            );
+
+            draw_label(
+                "Four in a row on a layer, down a pillar or diagonally through the layers",
+                20.0,
+                92.0,
+                16,
+                theme.muted,
+            );
+        }
+    }
+
+    // ---- Draw Layers ----
+    for layer in 0..SIZE {
+        let label = format!("Layer {}", layer + 1);
+        let dim = measure_text(&label, font, 20, 1.0);
+        draw_label(
+            &label,
+            layer_x(layer) + LAYER_SIZE / 2.0 - dim.width / 2.0,
+            LAYERS_Y - 16.0,
+            20,
+            theme.muted,
+        );
+    }
+
+    // The hovered cell's pillar shows faintly on the other layers, to help
+    // see lines that cross them
+    let human_to_move = !cube.game.is_over() && !cube.is_ai_turn();
+    let hovered = Board::get_cell_among(&cube.layers, mouse).filter(|_| human_to_move);
+    for (index, board) in cube.layers.iter_mut().enumerate() {
+        board.preview = hovered
+            .filter(|&(layer, _)| layer == index)
+            .map(|(_, cell)| (cell, cube.game.turn()));
+    }
+    if let Some((layer, (row, col))) = hovered {
+        let mut glow = side_color(cube.game.turn());
+        glow.a = 0.1;
+        for other in (0..SIZE).filter(|&other| other != layer) {
+            let board = &cube.layers[other];
+            draw_rectangle(
+                board.x + col as f32 * board.cell_size,
+                board.y + row as f32 * board.cell_size,
+                board.cell_size,
+                board.cell_size,
+                glow,
+            );
+        }
+    }
+
+    for board in cube.layers.iter_mut() {
+        board.draw();
+    }
+
+    // A win line through the layers, drawn out from layer to layer like
+    // `Board`'s; one on a single layer is drawn by its board
+    if let Some(Outcome::Win(winner, line)) = cube.game.outcome()
+        && line
+            .iter()
+            .any(|&mv| qubic::split(mv).0 != qubic::split(line[0]).0)
+    {
+        cube.win_anim_timer = (cube.win_anim_timer + get_frame_time() * 2.0).min(1.0);
+        let color = side_color(*winner);
+        let mut glow = color;
+        glow.a = 0.3;
+
+        let centers: Vec<Vec2> = line.iter().map(|&mv| cube.cell_center(mv)).collect();
+        let drawn = cube.win_anim_timer * (centers.len() - 1) as f32;
+        for (i, pair) in centers.windows(2).enumerate() {
+            let t = (drawn - i as f32).clamp(0.0, 1.0);
+            if t <= 0.0 {
+                break;
+            }
+            let (start, end) = (pair[0], pair[0] + (pair[1] - pair[0]) * t);
+            draw_line(start.x, start.y, end.x, end.y, 6.0, color);
+            draw_line(start.x, start.y, end.x, end.y, 12.0, glow);
+        }
+    }
+
+    // ---- Moves ----
+    if cube.is_ai_turn() {
+        if let Some(mv) = cube.ai_worker.drive(
+            &cube.game,
+            cube.ai.unwrap_or(Difficulty::Perfect),
+            qubic::find_best_move,
+        ) {
+            cube.play(mv);
+        }
+    } else if let Some((layer, (row, col))) = hovered
+        && is_mouse_button_pressed(MouseButton::Left)
+    {
+        cube.play((row, layer * SIZE + col));
+    }
+
+    // ---- Controls ----
+    variant_controls(unsafe { &mut QUBIC }, |cube| {
+        QubicGame::new(cube.ai, cube.player_symbol)
+    })
+}
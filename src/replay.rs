@@ -0,0 +1,270 @@
+use crate::board::Board;
+use crate::config::*;
+use crate::notation::GameRecord;
+use crate::state::GameState;
+use crate::utils::*;
+use macroquad::prelude::*;
+use tictactoe_core::CellState;
+
+const MIN_SPEED: f32 = 0.5; // moves per second
+const MAX_SPEED: f32 = 8.0;
+
+// Scrub bar along the bottom edge, below the board
+const BAR_X: f32 = 100.0;
+const BAR_Y: f32 = 575.0;
+const BAR_WIDTH: f32 = VIRTUAL_WIDTH - 200.0;
+const BAR_GRAB_HEIGHT: f32 = 30.0;
+
+struct Replay {
+    board: Board,
+    moves: Vec<(usize, usize)>,
+    ply: usize, // number of moves currently on the board
+    playing: bool,
+    speed: f32,
+    timer: f32,
+    dragging: bool,
+    return_to: GameState,
+}
+
+impl Replay {
+    fn new(record: &GameRecord, return_to: GameState) -> Self {
+        // Lines that wrap need the topology to be found
+        let mut board = Board::new(record.size, record.win_length);
+        board.position.set_topology(record.topology);
+        Self {
+            board,
+            moves: record.moves.clone(),
+            ply: 0,
+            playing: true,
+            speed: 2.0,
+            timer: 0.0,
+            dragging: false,
+            return_to,
+        }
+    }
+
+    fn step_forward(&mut self) {
+        if self.ply < self.moves.len() {
+            let (row, col) = self.moves[self.ply];
+            self.board.set_cell(row, col, symbol_for_ply(self.ply));
+            self.ply += 1;
+            self.board.check_winner();
+            crate::config::play_move();
+        }
+    }
+
+    fn step_back(&mut self) {
+        if self.ply > 0 {
+            self.ply -= 1;
+            let (row, col) = self.moves[self.ply];
+            self.board.clear_cell(row, col);
+        }
+    }
+
+    /// Moves to any ply. Single steps animate, bigger jumps redraw the
+    /// position with every piece already in place.
+    fn seek(&mut self, ply: usize) {
+        let ply = ply.min(self.moves.len());
+        if ply == self.ply + 1 {
+            self.step_forward();
+        } else if ply + 1 == self.ply {
+            self.step_back();
+        } else if ply != self.ply {
+            self.board.reset();
+            for (i, &(row, col)) in self.moves[..ply].iter().enumerate() {
+                self.board.set_cell(row, col, symbol_for_ply(i));
+                self.board.anim_timers[row][col] = 1.0;
+            }
+            self.ply = ply;
+            self.board.check_winner();
+        }
+    }
+}
+
+static mut REPLAY: Option<Replay> = None;
+
+/// Opens the replay viewer for a game. ESC goes back to `return_to`.
+pub fn start(record: &GameRecord, return_to: GameState) {
+    unsafe {
+        REPLAY = Some(Replay::new(record, return_to));
+    }
+}
+
+/// Opens the replay viewer for a saved game file.
+pub fn load(path: &str, return_to: GameState) -> Result<(), String> {
+    let record = crate::notation::load(path)?;
+    start(&record, return_to);
+    Ok(())
+}
+
+#[allow(static_mut_refs)]
+pub fn update(scale: &ScreenScale) -> Option<GameState> {
+    let Some(replay) = (unsafe { REPLAY.as_mut() }) else {
+        return Some(GameState::Menu);
+    };
+
+    let mouse = mouse_to_virtual(scale);
+    let font = crate::config::get_font();
+    let theme = crate::theme::get_current_theme();
+    let dt = get_frame_time();
+
+    // ---- Draw UI ----
+    draw_text_ex(
+        "Replay",
+        20.0,
+        40.0,
+        TextParams {
+            font,
+            font_size: 24,
+            color: theme.text,
+            ..Default::default()
+        },
+    );
+
+    draw_text_ex(
+        "ESC: Back | Left/Right: Step | Space: Play/Pause | Up/Down: Speed",
+        20.0,
+        70.0,
+        TextParams {
+            font,
+            font_size: 18,
+            color: theme.muted,
+            ..Default::default()
+        },
+    );
+
+    let progress_text = format!("Move {} / {}", replay.ply, replay.moves.len());
+    let progress_dim = measure_text(&progress_text, font, 24, 1.0);
+    draw_text_ex(
+        &progress_text,
+        VIRTUAL_WIDTH - progress_dim.width - 20.0,
+        40.0,
+        TextParams {
+            font,
+            font_size: 24,
+            color: theme.text,
+            ..Default::default()
+        },
+    );
+
+    let speed_text = format!(
+        "{} {:.1}x",
+        if replay.playing { "Playing" } else { "Paused" },
+        replay.speed
+    );
+    let speed_dim = measure_text(&speed_text, font, 18, 1.0);
+    draw_text_ex(
+        &speed_text,
+        VIRTUAL_WIDTH - speed_dim.width - 20.0,
+        70.0,
+        TextParams {
+            font,
+            font_size: 18,
+            color: theme.muted,
+            ..Default::default()
+        },
+    );
+
+    // ---- Draw Board ----
+    replay.board.draw();
+
+    // ---- Scrub Bar ----
+    draw_line(BAR_X, BAR_Y, BAR_X + BAR_WIDTH, BAR_Y, 4.0, theme.muted);
+    let total = replay.moves.len().max(1) as f32;
+    for i in 0..=replay.moves.len() {
+        let tick_x = BAR_X + BAR_WIDTH * i as f32 / total;
+        draw_line(tick_x, BAR_Y - 5.0, tick_x, BAR_Y + 5.0, 2.0, theme.muted);
+    }
+    let knob_x = BAR_X + BAR_WIDTH * replay.ply as f32 / total;
+    let knob_color = if replay.ply > 0 && symbol_for_ply(replay.ply - 1) == CellState::O {
+        theme.secondary
+    } else {
+        theme.primary
+    };
+    draw_line(BAR_X, BAR_Y, knob_x, BAR_Y, 4.0, knob_color);
+    draw_circle(knob_x, BAR_Y, 9.0, knob_color);
+
+    let bar_rect = Rect::new(
+        BAR_X - 10.0,
+        BAR_Y - BAR_GRAB_HEIGHT / 2.0,
+        BAR_WIDTH + 20.0,
+        BAR_GRAB_HEIGHT,
+    );
+    if is_mouse_button_pressed(MouseButton::Left) && bar_rect.contains(mouse) {
+        replay.dragging = true;
+        replay.playing = false;
+    }
+    if !is_mouse_button_down(MouseButton::Left) {
+        replay.dragging = false;
+    }
+    if replay.dragging {
+        let t = ((mouse.x - BAR_X) / BAR_WIDTH).clamp(0.0, 1.0);
+        replay.seek((t * total).round() as usize);
+    }
+
+    // ---- Playback ----
+    if replay.playing {
+        replay.timer += dt;
+        if replay.timer >= 1.0 / replay.speed {
+            replay.timer = 0.0;
+            replay.step_forward();
+        }
+        if replay.ply == replay.moves.len() {
+            replay.playing = false;
+        }
+    }
+
+    if is_key_pressed(KeyCode::Right) {
+        replay.playing = false;
+        replay.step_forward();
+    }
+    if is_key_pressed(KeyCode::Left) {
+        replay.playing = false;
+        replay.step_back();
+    }
+    if is_key_pressed(KeyCode::Home) {
+        replay.playing = false;
+        replay.seek(0);
+    }
+    if is_key_pressed(KeyCode::End) {
+        replay.playing = false;
+        replay.seek(replay.moves.len());
+    }
+    if is_key_pressed(KeyCode::Space) {
+        // Playing from the last move starts over from the beginning
+        if !replay.playing && replay.ply == replay.moves.len() {
+            replay.seek(0);
+        }
+        replay.playing = !replay.playing;
+        replay.timer = 0.0;
+    }
+    if is_key_pressed(KeyCode::Up) {
+        replay.speed = (replay.speed * 2.0).min(MAX_SPEED);
+    }
+    if is_key_pressed(KeyCode::Down) {
+        replay.speed = (replay.speed / 2.0).max(MIN_SPEED);
+    }
+
+    // Theme switching
+    if is_key_pressed(KeyCode::T) {
+        crate::theme::cycle_theme();
+    }
+
+    if is_key_pressed(KeyCode::Escape) {
+        let return_to = replay.return_to;
+        unsafe {
+            REPLAY = None;
+        }
+        return Some(return_to);
+    }
+
+    None
+}
+
+fn symbol_for_ply(ply: usize) -> CellState {
+    if ply.is_multiple_of(2) {
+        CellState::X
+    } else {
+        CellState::O
+    }
+}
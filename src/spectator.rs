@@ -0,0 +1,192 @@
+use macroquad::prelude::*;
+use tictactoe_core::CellState;
+use tictactoe_core::ai::{Difficulty, Engine};
+
+const MAX_MOVE_DELAY: f32 = 2.0; // seconds
+const RESTART_DELAY: f32 = 2.0;
+
+// Speed slider in the empty space left of the board
+const SLIDER_X: f32 = 20.0;
+const SLIDER_Y: f32 = 250.0;
+const SLIDER_WIDTH: f32 = 130.0;
+const SLIDER_GRAB_HEIGHT: f32 = 30.0;
+
+struct Spectator {
+    paused: bool,
+    step: bool,      // play one move, then stay paused
+    move_delay: f32, // pause between moves, on top of thinking time
+    since_move: f32,
+    since_end: f32,
+    auto_restart: bool,
+    dragging: bool,
+    results: [u32; 3], // X wins, O wins, draws
+}
+
+/// Difficulty and engine for X and O.
+pub type Sides = [(Difficulty, Engine); 2];
+
+static mut SIDES: Sides = [
+    (Difficulty::Perfect, Engine::Minimax),
+    (Difficulty::Perfect, Engine::Mcts),
+];
+
+static mut SPECTATOR: Spectator = Spectator {
+    paused: false,
+    step: false,
+    move_delay: 0.6,
+    since_move: 0.0,
+    since_end: 0.0,
+    auto_restart: true,
+    dragging: false,
+    results: [0; 3],
+};
+
+pub fn sides() -> Sides {
+    unsafe { SIDES }
+}
+
+/// Sets up a new match. The running score starts again from zero.
+pub fn set_sides(sides: Sides) {
+    unsafe {
+        SIDES = sides;
+        SPECTATOR.paused = false;
+        SPECTATOR.step = false;
+        SPECTATOR.results = [0; 3];
+    }
+}
+
+/// Whether the side to move may be asked for its move this frame.
+pub fn may_move() -> bool {
+    unsafe { SPECTATOR.step || (!SPECTATOR.paused && SPECTATOR.since_move >= SPECTATOR.move_delay) }
+}
+
+/// A move was just played.
+pub fn moved() {
+    unsafe {
+        SPECTATOR.since_move = 0.0;
+        SPECTATOR.step = false;
+    }
+}
+
+pub fn record_result(winner: Option<CellState>) {
+    let index = match winner {
+        Some(CellState::X) => 0,
+        Some(CellState::O) => 1,
+        _ => 2,
+    };
+    unsafe {
+        SPECTATOR.results[index] += 1;
+        SPECTATOR.since_end = 0.0;
+    }
+}
+
+/// Draws the side panel and handles pause, step, speed and auto-restart.
+/// Returns true when the finished game should make way for the next one.
+#[allow(static_mut_refs)]
+pub fn update(game_over: bool, mouse: Vec2) -> bool {
+    let spectator = unsafe { &mut SPECTATOR };
+    let font = crate::config::get_font();
+    let theme = crate::theme::get_current_theme();
+    let dt = get_frame_time();
+
+    if !spectator.paused {
+        spectator.since_move += dt;
+        if game_over {
+            spectator.since_end += dt;
+        }
+    }
+
+    let text = |text: &str, y: f32, color: Color| {
+        draw_text_ex(
+            text,
+            SLIDER_X,
+            y,
+            TextParams {
+                font,
+                font_size: 18,
+                color,
+                ..Default::default()
+            },
+        );
+    };
+
+    // ---- Speed Slider ----
+    // Right is faster, i.e. a shorter pause between moves
+    text("Speed", SLIDER_Y - 20.0, theme.text);
+    let t = 1.0 - spectator.move_delay / MAX_MOVE_DELAY;
+    let knob_x = SLIDER_X + SLIDER_WIDTH * t;
+    draw_line(
+        SLIDER_X,
+        SLIDER_Y,
+        SLIDER_X + SLIDER_WIDTH,
+        SLIDER_Y,
+        4.0,
+        theme.muted,
+    );
+    draw_line(SLIDER_X, SLIDER_Y, knob_x, SLIDER_Y, 4.0, theme.primary);
+    draw_circle(knob_x, SLIDER_Y, 9.0, theme.primary);
+    text(
+        &format!("{:.1} s / move", spectator.move_delay),
+        SLIDER_Y + 30.0,
+        theme.muted,
+    );
+
+    let slider_rect = Rect::new(
+        SLIDER_X - 10.0,
+        SLIDER_Y - SLIDER_GRAB_HEIGHT / 2.0,
+        SLIDER_WIDTH + 20.0,
+        SLIDER_GRAB_HEIGHT,
+    );
+    if is_mouse_button_pressed(MouseButton::Left) && slider_rect.contains(mouse) {
+        spectator.dragging = true;
+    }
+    if !is_mouse_button_down(MouseButton::Left) {
+        spectator.dragging = false;
+    }
+    if spectator.dragging {
+        let t = ((mouse.x - SLIDER_X) / SLIDER_WIDTH).clamp(0.0, 1.0);
+        // Snap to tenths so the label reads cleanly
+        spectator.move_delay = ((1.0 - t) * MAX_MOVE_DELAY * 10.0).round() / 10.0;
+    }
+
+    // ---- State and Score ----
+    let state = if spectator.paused {
+        "Paused"
+    } else {
+        "Running"
+    };
+    text(state, SLIDER_Y + 70.0, theme.text);
+    let restart = if spectator.auto_restart {
+        "Auto-restart: on"
+    } else {
+        "Auto-restart: off"
+    };
+    text(restart, SLIDER_Y + 95.0, theme.muted);
+
+    let [x_wins, o_wins, draws] = spectator.results;
+    text(
+        &format!("X wins: {x_wins}"),
+        SLIDER_Y + 135.0,
+        theme.primary,
+    );
+    text(
+        &format!("O wins: {o_wins}"),
+        SLIDER_Y + 160.0,
+        theme.secondary,
+    );
+    text(&format!("Draws: {draws}"), SLIDER_Y + 185.0, theme.muted);
+
+    // ---- Controls ----
+    if is_key_pressed(KeyCode::Space) {
+        spectator.paused = !spectator.paused;
+    }
+    if (is_key_pressed(KeyCode::N) || is_key_pressed(KeyCode::Right)) && !game_over {
+        spectator.paused = true;
+        spectator.step = true;
+    }
+    if is_key_pressed(KeyCode::A) {
+        spectator.auto_restart = !spectator.auto_restart;
+    }
+
+    game_over && spectator.auto_restart && !spectator.paused && spectator.since_end >= RESTART_DELAY
+}
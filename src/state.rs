@@ -1,7 +1,28 @@
+use tictactoe_core::ai::Difficulty;
+
 #[derive(Copy, Clone, PartialEq)]
 pub enum GameState {
     Menu,
     ChooseSymbol,
+    ChooseBoard,
+    Stats,
     PvP,
-    PvAI,
+    PvAI(Difficulty),
+    Replay,
+    Analysis,
+    OnlineLobby,
+    Online,
+    ChooseAiVsAi,
+    AiVsAi,
+    ChooseVariant,
+    Ultimate,
+    Wild,
+    ThreePiece,
+    ChooseConnectFour,
+    ConnectFour,
+    ChooseGomoku,
+    Gomoku,
+    Qubic,
+    ChooseOrderChaos,
+    OrderChaos,
 }
@@ -0,0 +1,345 @@
+use crate::state::GameState;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use tictactoe_core::ai::Difficulty;
+use tictactoe_core::{CellState, Rule, Topology};
+
+// Bump when the file layout changes, and teach `parse` to upgrade the old one.
+// Files from a newer version are still read, and the sections and fields we
+// don't know are written back untouched along with that version's number.
+const STATS_VERSION: u32 = 1;
+
+const STATS_FILE: &str = "stats.txt";
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Outcome {
+    Win,
+    Loss,
+    Draw,
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
+pub struct ModeStats {
+    pub wins: u32,
+    pub losses: u32,
+    pub draws: u32,
+    pub streak: i32, // positive for a run of wins, negative for losses
+    pub best_streak: u32,
+    pub total_moves: u32,
+}
+
+impl ModeStats {
+    pub fn games(&self) -> u32 {
+        self.wins + self.losses + self.draws
+    }
+
+    pub fn average_length(&self) -> Option<f32> {
+        match self.games() {
+            0 => None,
+            games => Some(self.total_moves as f32 / games as f32),
+        }
+    }
+
+    /// Reads one `key: value` line of the file. False for keys this build
+    /// doesn't know.
+    fn set(&mut self, key: &str, value: &str) -> bool {
+        match key {
+            "wins" => self.wins = value.parse().unwrap_or(0),
+            "losses" => self.losses = value.parse().unwrap_or(0),
+            "draws" => self.draws = value.parse().unwrap_or(0),
+            "streak" => self.streak = value.parse().unwrap_or(0),
+            "best_streak" => self.best_streak = value.parse().unwrap_or(0),
+            "total_moves" => self.total_moves = value.parse().unwrap_or(0),
+            _ => return false,
+        }
+        true
+    }
+
+    fn record(&mut self, outcome: Outcome, moves: usize) {
+        self.total_moves += moves as u32;
+        match outcome {
+            Outcome::Win => {
+                self.wins += 1;
+                self.streak = self.streak.max(0) + 1;
+                self.best_streak = self.best_streak.max(self.streak as u32);
+            }
+            Outcome::Loss => {
+                self.losses += 1;
+                self.streak = self.streak.min(0) - 1;
+            }
+            Outcome::Draw => {
+                self.draws += 1;
+                self.streak = 0;
+            }
+        }
+    }
+}
+
+pub struct Stats {
+    pub modes: BTreeMap<String, ModeStats>,
+    version: u32,
+    // Lines this build doesn't understand, by section ("" before the first)
+    unknown: BTreeMap<String, Vec<String>>,
+}
+
+impl Default for Stats {
+    fn default() -> Self {
+        Self {
+            modes: BTreeMap::new(),
+            version: STATS_VERSION,
+            unknown: BTreeMap::new(),
+        }
+    }
+}
+
+impl Stats {
+    pub fn to_text(&self) -> String {
+        let mut text = format!("version: {}\n", self.version.max(STATS_VERSION));
+        for line in self.unknown.get("").into_iter().flatten() {
+            text += &format!("{line}\n");
+        }
+
+        let mut sections: Vec<&String> = self.modes.keys().collect();
+        sections.extend(self.unknown.keys().filter(|key| !key.is_empty()));
+        sections.sort();
+        sections.dedup();
+        for key in sections {
+            text += &format!("\n[{key}]\n");
+            if let Some(stats) = self.modes.get(key) {
+                text += &format!(
+                    "wins: {}\nlosses: {}\ndraws: {}\nstreak: {}\nbest_streak: {}\ntotal_moves: {}\n",
+                    stats.wins,
+                    stats.losses,
+                    stats.draws,
+                    stats.streak,
+                    stats.best_streak,
+                    stats.total_moves,
+                );
+            }
+            for line in self.unknown.get(key).into_iter().flatten() {
+                text += &format!("{line}\n");
+            }
+        }
+        text
+    }
+
+    pub fn parse(text: &str) -> Self {
+        let mut stats = Stats::default();
+        let mut section = String::new();
+
+        for line in text.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if let Some(key) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                section = key.to_string();
+                continue;
+            }
+
+            if let Some((key, value)) = line.split_once(':') {
+                let (key, value) = (key.trim(), value.trim());
+                // Only one version exists so far, so there is nothing to migrate yet
+                if section.is_empty() && key == "version" {
+                    stats.version = value.parse().unwrap_or(STATS_VERSION);
+                    continue;
+                }
+                let mut entry = stats.get(&section);
+                if !section.is_empty() && entry.set(key, value) {
+                    stats.modes.insert(section.clone(), entry);
+                    continue;
+                }
+            }
+            stats
+                .unknown
+                .entry(section.clone())
+                .or_default()
+                .push(line.to_string());
+        }
+
+        stats
+    }
+
+    pub fn get(&self, key: &str) -> ModeStats {
+        self.modes.get(key).copied().unwrap_or_default()
+    }
+}
+
+static mut STATS: Option<Stats> = None;
+
+/// Stats loaded from disk on first use.
+#[allow(static_mut_refs)]
+pub fn get_stats() -> &'static Stats {
+    unsafe { STATS.get_or_insert_with(load) }
+}
+
+/// Adds a finished game to the stats and writes them back to disk.
+#[allow(static_mut_refs)]
+pub fn record_game(key: &str, outcome: Outcome, moves: usize) {
+    let stats = unsafe { STATS.get_or_insert_with(load) };
+    stats
+        .modes
+        .entry(key.to_string())
+        .or_default()
+        .record(outcome, moves);
+    save(stats);
+}
+
+/// Stats key for a mode. PvAI games are split by difficulty and by the side
+/// the human played, and every mode by rule and topology. Standard rules on
+/// a flat board add nothing, so keys from before those existed still match.
+pub fn mode_key(
+    mode: GameState,
+    player_symbol: CellState,
+    rule: Rule,
+    topology: Topology,
+) -> String {
+    let mut key = match mode {
+        GameState::PvAI(difficulty) => {
+            let symbol = if player_symbol == CellState::O {
+                "o"
+            } else {
+                "x"
+            };
+            format!("pvai-{}-{symbol}", difficulty.name().to_lowercase())
+        }
+        GameState::Online => "online".to_string(),
+        _ => "pvp".to_string(),
+    };
+    if rule != Rule::Standard {
+        key += &format!("-{}", rule.name().to_lowercase());
+    }
+    if topology != Topology::Flat {
+        key += &format!("-{}", topology.name().to_lowercase());
+    }
+    key
+}
+
+/// Every key shown on the stats screen for `rule` and `topology`, with a
+/// readable label.
+pub fn all_mode_keys(rule: Rule, topology: Topology) -> Vec<(String, String)> {
+    let key = |mode, symbol| mode_key(mode, symbol, rule, topology);
+    let mut keys = vec![(key(GameState::PvP, CellState::X), "PvP (X / O)".to_string())];
+    for difficulty in Difficulty::ALL {
+        for symbol in [CellState::X, CellState::O] {
+            let label = format!(
+                "AI {} as {}",
+                difficulty.name(),
+                if symbol == CellState::X { "X" } else { "O" }
+            );
+            keys.push((key(GameState::PvAI(difficulty), symbol), label));
+        }
+    }
+    keys.push((key(GameState::Online, CellState::X), "Online".to_string()));
+    keys
+}
+
+/// Per-user config directory for the game, e.g. `~/.config/tictactoe`.
+fn config_dir() -> Option<PathBuf> {
+    let home = std::env::var_os("HOME").map(PathBuf::from);
+    let base = if cfg!(windows) {
+        std::env::var_os("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        home.map(|home| home.join("Library/Application Support"))
+    } else {
+        std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| home.map(|home| home.join(".config")))
+    };
+    base.map(|dir| dir.join("tictactoe"))
+}
+
+fn load() -> Stats {
+    config_dir()
+        .and_then(|dir| std::fs::read_to_string(dir.join(STATS_FILE)).ok())
+        .map(|text| Stats::parse(&text))
+        .unwrap_or_default()
+}
+
+fn save(stats: &Stats) {
+    // Stats are a nice-to-have; failing to write them shouldn't stop the game
+    if let Some(dir) = config_dir()
+        && std::fs::create_dir_all(&dir).is_ok()
+    {
+        let _ = std::fs::write(dir.join(STATS_FILE), stats.to_text());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips() {
+        let mut stats = Stats::default();
+        stats
+            .modes
+            .entry("pvp".to_string())
+            .or_default()
+            .record(Outcome::Draw, 9);
+        let hard = stats
+            .modes
+            .entry("pvai-hard-o-misere".to_string())
+            .or_default();
+        hard.record(Outcome::Win, 7);
+        hard.record(Outcome::Win, 5);
+        hard.record(Outcome::Loss, 6);
+
+        let text = stats.to_text();
+        assert!(text.starts_with(&format!("version: {STATS_VERSION}\n")));
+        let parsed = Stats::parse(&text);
+        assert_eq!(parsed.modes, stats.modes);
+        assert_eq!(parsed.to_text(), text);
+    }
+
+    #[test]
+    fn keeps_what_a_newer_version_wrote() {
+        let newer = STATS_VERSION + 1;
+        let text = format!(
+            "version: {newer}\nplayer: Ada\n\n[achievements]\nfirst_win: yes\n\n[pvp]\nwins: 2\nlosses: 1\ndraws: 0\nstreak: 1\nbest_streak: 1\ntotal_moves: 20\nlongest_game: 9\n"
+        );
+        let mut stats = Stats::parse(&text);
+        assert_eq!(stats.get("pvp").wins, 2);
+        assert!(!stats.modes.contains_key("achievements"));
+        assert_eq!(stats.to_text(), text);
+
+        stats.modes.get_mut("pvp").unwrap().record(Outcome::Win, 5);
+        let saved = stats.to_text();
+        assert!(saved.starts_with(&format!("version: {newer}\nplayer: Ada\n")));
+        assert!(saved.contains("wins: 3\n"));
+        assert!(saved.contains("longest_game: 9\n"));
+        assert!(saved.contains("\n[achievements]\nfirst_win: yes\n"));
+    }
+
+    #[test]
+    fn mode_keys_split_by_rule_and_topology() {
+        let pvai = GameState::PvAI(Difficulty::Hard);
+        // Standard rules on a flat board keep the keys from before variants
+        assert_eq!(
+            mode_key(GameState::PvP, CellState::X, Rule::Standard, Topology::Flat),
+            "pvp"
+        );
+        assert_eq!(
+            mode_key(pvai, CellState::O, Rule::Standard, Topology::Flat),
+            "pvai-hard-o"
+        );
+        assert_eq!(
+            mode_key(pvai, CellState::X, Rule::Misere, Topology::Torus),
+            "pvai-hard-x-misere-torus"
+        );
+        assert_eq!(
+            mode_key(
+                GameState::Online,
+                CellState::O,
+                Rule::Standard,
+                Topology::Cylinder
+            ),
+            "online-cylinder"
+        );
+
+        let keys = all_mode_keys(Rule::Misere, Topology::Flat);
+        assert_eq!(keys.len(), 2 + 2 * Difficulty::ALL.len());
+        assert!(keys.iter().all(|(key, _)| key.ends_with("-misere")));
+    }
+}
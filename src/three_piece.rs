@@ -0,0 +1,168 @@
+use crate::board::Board;
+use crate::config::*;
+use crate::player::AiWorker;
+use crate::state::GameState;
+use crate::utils::*;
+use macroquad::prelude::*;
+use tictactoe_core::ai::Difficulty;
+use tictactoe_core::rng::Rng;
+use tictactoe_core::three_piece::{self, MOVE_LIMIT, REPETITIONS, SIZE, ThreePiece};
+use tictactoe_core::{CellState, Move, Outcome};
+
+struct ThreePieceGame {
+    game: ThreePiece,
+    board: Board,
+    ai: Option<Difficulty>,
+    player_symbol: CellState, // the human's side against the AI
+    ai_worker: AiWorker<Move>,
+}
+
+impl ThreePieceGame {
+    fn new(ai: Option<Difficulty>, player_symbol: CellState) -> Self {
+        Self {
+            game: ThreePiece::new(),
+            board: Board::new(SIZE, SIZE),
+            ai,
+            player_symbol,
+            ai_worker: AiWorker::new(Rng::from_time()),
+        }
+    }
+
+    fn is_ai_turn(&self) -> bool {
+        self.ai.is_some() && !self.game.is_over() && self.game.turn() != self.player_symbol
+    }
+
+    /// Plays a legal move, taking the mover's oldest piece off first if it
+    /// has to go.
+    fn play(&mut self, (row, col): Move) {
+        let mover = self.game.turn();
+        let vanishing = self.game.next_to_vanish();
+        if self.game.play((row, col)).is_err() {
+            return;
+        }
+
+        if let Some((old_row, old_col)) = vanishing {
+            self.board.clear_cell(old_row, old_col);
+        }
+        self.board.set_cell(row, col, mover);
+        self.board.check_winner();
+        self.board.vanishing = self.game.next_to_vanish();
+
+        let theme = crate::theme::get_current_theme();
+        let color = if mover == CellState::X {
+            theme.primary
+        } else {
+            theme.secondary
+        };
+        crate::config::play_move();
+        crate::particles::spawn_move(self.board.get_cell_center(row, col), color);
+
+        if let Some(Outcome::Win(_, line)) = self.game.outcome() {
+            crate::config::play_win();
+            for &(row, col) in line {
+                crate::particles::spawn_win(self.board.get_cell_center(row, col), color);
+            }
+        }
+    }
+}
+
+static mut THREE_PIECE: Option<ThreePieceGame> = None;
+
+/// Starts a new game, against the AI at `ai` or between two people.
+pub fn start(ai: Option<Difficulty>, player_symbol: CellState) {
+    unsafe {
+        THREE_PIECE = Some(ThreePieceGame::new(ai, player_symbol));
+    }
+}
+
+#[allow(static_mut_refs)]
+pub fn update(scale: &ScreenScale) -> Option<GameState> {
+    let Some(three) = (unsafe { THREE_PIECE.as_mut() }) else {
+        return Some(GameState::Menu);
+    };
+
+    let mouse = mouse_to_virtual(scale);
+    let font = crate::config::get_font();
+    let theme = crate::theme::get_current_theme();
+
+    // ---- Draw UI ----
+    let mode_text = match three.ai {
+        Some(difficulty) => format!("Three Pieces vs AI ({})", difficulty.name()),
+        None => "Three Pieces: Player vs Player".to_string(),
+    };
+    draw_label(&mode_text, 20.0, 40.0, 24, theme.text);
+    draw_label(
+        "ESC: Menu | R: Reset | T: Theme",
+        20.0,
+        70.0,
+        18,
+        theme.muted,
+    );
+
+    match three.game.outcome() {
+        Some(outcome) => {
+            let (msg, color) = match outcome {
+                Outcome::Win(CellState::X, _) => ("PLAYER X WINS!", theme.primary),
+                Outcome::Win(_, _) => ("PLAYER O WINS!", theme.secondary),
+                Outcome::Draw if three.game.repetitions() >= REPETITIONS => {
+                    ("DRAW BY REPETITION!", theme.muted)
+                }
+                Outcome::Draw => ("DRAW - MOVE LIMIT!", theme.muted),
+            };
+            draw_end_banner(msg, color);
+        }
+        None => {
+            let turn_text = if three.ai_worker.is_thinking() {
+                "AI is thinking...".to_string()
+            } else {
+                format!(
+                    "Turn: {}",
+                    if three.game.turn() == CellState::X {
+                        "X"
+                    } else {
+                        "O"
+                    }
+                )
+            };
+            let turn_dim = measure_text(&turn_text, font, 24, 1.0);
+            draw_label(
+                &turn_text,
+                VIRTUAL_WIDTH - turn_dim.width - 20.0,
+                40.0,
+                24,
+                theme.text,
+            );
+
+            let rules_text = format!(
+                "A fourth piece takes your oldest (flickering) off | Move {} of {MOVE_LIMIT}",
+                three.game.moves().len() + 1
+            );
+            draw_label(&rules_text, 20.0, 92.0, 16, theme.muted);
+        }
+    }
+
+    // ---- Draw Board ----
+    three.board.draw();
+
+    // ---- Moves ----
+    if three.is_ai_turn() {
+        if let Some(mv) = three.ai_worker.drive(
+            &three.game,
+            three.ai.unwrap_or(Difficulty::Perfect),
+            three_piece::find_best_move,
+        ) {
+            three.play(mv);
+        }
+    } else if !three.game.is_over()
+        && is_mouse_button_pressed(MouseButton::Left)
+        && let Some((row, col)) = three.board.get_cell_at(mouse)
+        && three.board.state(row, col) == CellState::Empty
+    {
+        three.play((row, col));
+    }
+
+    // ---- Controls ----
+    variant_controls(unsafe { &mut THREE_PIECE }, |three| {
+        ThreePieceGame::new(three.ai, three.player_symbol)
+    })
+}
@@ -0,0 +1,298 @@
+use crate::board::Board;
+use crate::config::*;
+use crate::player::AiWorker;
+use crate::state::GameState;
+use crate::utils::*;
+use macroquad::prelude::*;
+use tictactoe_core::ai::Difficulty;
+use tictactoe_core::rng::Rng;
+use tictactoe_core::ultimate::{self, SUB_SIZE, Ultimate};
+use tictactoe_core::{CellState, Move, Outcome};
+
+// The big board takes the place of the normal one; small boards sit in it
+// with a gap between them
+const META_X: f32 = 175.0;
+const META_Y: f32 = 105.0;
+const META_SIZE: f32 = 450.0;
+const GAP: f32 = 12.0;
+const SUB_PIXEL_SIZE: f32 = (META_SIZE - GAP * (SUB_SIZE - 1) as f32) / SUB_SIZE as f32;
+
+struct UltimateGame {
+    game: Ultimate,
+    boards: Vec<Board>, // one view per small board
+    ai: Option<Difficulty>,
+    player_symbol: CellState, // the human's side against the AI
+    ai_worker: AiWorker<Move>,
+    win_anim_timer: f32,
+}
+
+impl UltimateGame {
+    fn new(ai: Option<Difficulty>, player_symbol: CellState) -> Self {
+        let boards = (0..SUB_SIZE * SUB_SIZE)
+            .map(|index| {
+                let mut board = Board::new(SUB_SIZE, SUB_SIZE);
+                let origin = sub_origin(index);
+                board.set_layout(origin.x, origin.y, SUB_PIXEL_SIZE);
+                board
+            })
+            .collect();
+
+        Self {
+            game: Ultimate::new(),
+            boards,
+            ai,
+            player_symbol,
+            ai_worker: AiWorker::new(Rng::from_time()),
+            win_anim_timer: 0.0,
+        }
+    }
+
+    fn is_ai_turn(&self) -> bool {
+        self.ai.is_some() && !self.game.is_over() && self.game.turn() != self.player_symbol
+    }
+
+    /// Plays a legal move and marks it on its small board, bursting over the
+    /// small board it claims or the big board's winning line.
+    fn play(&mut self, (row, col): Move) {
+        let mover = self.game.turn();
+        if self.game.play((row, col)).is_err() {
+            return;
+        }
+
+        let index = Ultimate::board_of((row, col));
+        let board = &mut self.boards[index];
+        let (sub_row, sub_col) = (row % SUB_SIZE, col % SUB_SIZE);
+        board.set_cell(sub_row, sub_col, mover);
+        let claimed = board.check_winner().is_some();
+
+        let theme = crate::theme::get_current_theme();
+        let color = if mover == CellState::X {
+            theme.primary
+        } else {
+            theme.secondary
+        };
+        crate::config::play_move();
+        crate::particles::spawn_move(board.get_cell_center(sub_row, sub_col), color);
+
+        if let Some(Outcome::Win(_, line)) = self.game.outcome() {
+            crate::config::play_win();
+            for &(meta_row, meta_col) in line {
+                crate::particles::spawn_win(sub_center(meta_row * SUB_SIZE + meta_col), color);
+            }
+        } else if claimed {
+            crate::particles::spawn_win(sub_center(index), color);
+        }
+    }
+}
+
+static mut ULTIMATE: Option<UltimateGame> = None;
+
+/// Starts a new game, against the AI at `ai` or between two people.
+pub fn start(ai: Option<Difficulty>, player_symbol: CellState) {
+    unsafe {
+        ULTIMATE = Some(UltimateGame::new(ai, player_symbol));
+    }
+}
+
+#[allow(static_mut_refs)]
+pub fn update(scale: &ScreenScale) -> Option<GameState> {
+    let Some(ultimate) = (unsafe { ULTIMATE.as_mut() }) else {
+        return Some(GameState::Menu);
+    };
+
+    let mouse = mouse_to_virtual(scale);
+    let font = crate::config::get_font();
+    let theme = crate::theme::get_current_theme();
+
+    // ---- Draw UI ----
+    let mode_text = match ultimate.ai {
+        Some(difficulty) => format!("Ultimate vs AI ({})", difficulty.name()),
+        None => "Ultimate: Player vs Player".to_string(),
+    };
+    draw_label(&mode_text, 20.0, 40.0, 24, theme.text);
+    draw_label(
+        "ESC: Menu | R: Reset | T: Theme",
+        20.0,
+        70.0,
+        18,
+        theme.muted,
+    );
+
+    let side_color = |side: CellState| {
+        if side == CellState::X {
+            theme.primary
+        } else {
+            theme.secondary
+        }
+    };
+
+    match ultimate.game.outcome() {
+        Some(outcome) => {
+            let (msg, color) = match outcome {
+                Outcome::Win(CellState::X, _) => ("PLAYER X WINS!", theme.primary),
+                Outcome::Win(_, _) => ("PLAYER O WINS!", theme.secondary),
+                Outcome::Draw => ("IT'S A DRAW!", theme.muted),
+            };
+            draw_end_banner(msg, color);
+        }
+        None => {
+            let turn_text = if ultimate.ai_worker.is_thinking() {
+                "AI is thinking...".to_string()
+            } else {
+                format!(
+                    "Turn: {}",
+                    if ultimate.game.turn() == CellState::X {
+                        "X"
+                    } else {
+                        "O"
+                    }
+                )
+            };
+            let turn_dim = measure_text(&turn_text, font, 24, 1.0);
+            draw_label(
+                &turn_text,
+                VIRTUAL_WIDTH - turn_dim.width - 20.0,
+                40.0,
+                24,
+                theme.text,
+            );
+
+            let open = (0..SUB_SIZE * SUB_SIZE)
+                .filter(|&index| ultimate.game.is_open_target(index))
+                .count();
+            let target_text = if open == 1 {
+                "Play in the highlighted board"
+            } else {
+                "Play in any highlighted board"
+            };
+            draw_label(target_text, 20.0, 92.0, 16, theme.muted);
+        }
+    }
+
+    // ---- Draw Boards ----
+    // Boards the side to move may play in glow in its color
+    let pulse = 0.5 + 0.5 * (get_time() as f32 * 3.0).sin();
+    for index in (0..SUB_SIZE * SUB_SIZE).filter(|&i| ultimate.game.is_open_target(i)) {
+        let origin = sub_origin(index);
+        let mut glow = side_color(ultimate.game.turn());
+        glow.a = 0.08 + 0.08 * pulse;
+        draw_rectangle(
+            origin.x - GAP / 3.0,
+            origin.y - GAP / 3.0,
+            SUB_PIXEL_SIZE + GAP * 2.0 / 3.0,
+            SUB_PIXEL_SIZE + GAP * 2.0 / 3.0,
+            glow,
+        );
+    }
+
+    // The big board's grid, between the small boards
+    for i in 1..SUB_SIZE {
+        let offset = i as f32 * (SUB_PIXEL_SIZE + GAP) - GAP / 2.0;
+        draw_line(
+            META_X + offset,
+            META_Y,
+            META_X + offset,
+            META_Y + META_SIZE,
+            6.0,
+            theme.text,
+        );
+        draw_line(
+            META_X,
+            META_Y + offset,
+            META_X + META_SIZE,
+            META_Y + offset,
+            6.0,
+            theme.text,
+        );
+    }
+
+    for board in ultimate.boards.iter_mut() {
+        board.draw();
+    }
+
+    // Claimed boards fade under one big piece; full ones just fade
+    for index in 0..SUB_SIZE * SUB_SIZE {
+        if !ultimate.game.is_closed(index) {
+            continue;
+        }
+        let origin = sub_origin(index);
+        let mut veil = theme.bg;
+        veil.a = 0.6;
+        draw_rectangle(origin.x, origin.y, SUB_PIXEL_SIZE, SUB_PIXEL_SIZE, veil);
+
+        let owner = ultimate.game.meta().get(index / SUB_SIZE, index % SUB_SIZE);
+        if owner != CellState::Empty {
+            let symbol = if owner == CellState::X { "X" } else { "O" };
+            let font_size = (SUB_PIXEL_SIZE * 0.9) as u16;
+            let inter = crate::config::get_inter_font();
+            let dim = measure_text(symbol, inter, font_size, 1.0);
+            let center = sub_center(index);
+            draw_text_ex(
+                symbol,
+                center.x - dim.width / 2.0,
+                center.y + dim.height / 2.0,
+                TextParams {
+                    font: inter,
+                    font_size,
+                    color: side_color(owner),
+                    ..Default::default()
+                },
+            );
+        }
+    }
+
+    // Win line across the big board, drawn out like `Board`'s
+    if let Some(Outcome::Win(winner, line)) = ultimate.game.outcome() {
+        ultimate.win_anim_timer = (ultimate.win_anim_timer + get_frame_time() * 2.0).min(1.0);
+        let first = line[0];
+        let last = line[line.len() - 1];
+        let start = sub_center(first.0 * SUB_SIZE + first.1);
+        let end = sub_center(last.0 * SUB_SIZE + last.1);
+        let current_end = start + (end - start) * ultimate.win_anim_timer;
+
+        let color = side_color(*winner);
+        let mut glow = color;
+        glow.a = 0.3;
+        draw_line(start.x, start.y, current_end.x, current_end.y, 12.0, color);
+        draw_line(start.x, start.y, current_end.x, current_end.y, 24.0, glow);
+    }
+
+    // ---- Moves ----
+    if ultimate.is_ai_turn() {
+        if let Some(mv) = ultimate.ai_worker.drive(
+            &ultimate.game,
+            ultimate.ai.unwrap_or(Difficulty::Perfect),
+            ultimate::find_best_move,
+        ) {
+            ultimate.play(mv);
+        }
+    } else if !ultimate.game.is_over() && is_mouse_button_pressed(MouseButton::Left) {
+        let clicked = (0..SUB_SIZE * SUB_SIZE)
+            .filter(|&index| ultimate.game.is_open_target(index))
+            .find_map(|index| {
+                let (row, col) = ultimate.boards[index].get_cell_at(mouse)?;
+                let (top, left) = (index / SUB_SIZE * SUB_SIZE, index % SUB_SIZE * SUB_SIZE);
+                Some((top + row, left + col))
+            });
+        if let Some(mv) = clicked {
+            ultimate.play(mv);
+        }
+    }
+
+    // ---- Controls ----
+    variant_controls(unsafe { &mut ULTIMATE }, |ultimate| {
+        UltimateGame::new(ultimate.ai, ultimate.player_symbol)
+    })
+}
+
+/// Top left corner of small board `index`.
+fn sub_origin(index: usize) -> Vec2 {
+    vec2(
+        META_X + (index % SUB_SIZE) as f32 * (SUB_PIXEL_SIZE + GAP),
+        META_Y + (index / SUB_SIZE) as f32 * (SUB_PIXEL_SIZE + GAP),
+    )
+}
+
+fn sub_center(index: usize) -> Vec2 {
+    sub_origin(index) + Vec2::splat(SUB_PIXEL_SIZE / 2.0)
+}
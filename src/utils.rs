@@ -1,5 +1,7 @@
 use crate::config::*;
+use crate::state::GameState;
 use macroquad::prelude::*;
+use tictactoe_core::CellState;
 
 pub struct ScreenScale {
     pub scale: f32,
@@ -29,3 +31,93 @@ pub fn mouse_to_virtual(scale: &ScreenScale) -> Vec2 {
         (my - scale.offset_y) / scale.scale,
     )
 }
+
+/// Limits drawing to `area`, in virtual coordinates, until called again with
+/// `None`.
+pub fn clip_to(scale: &ScreenScale, area: Option<Rect>) {
+    // Scissor rects are in physical pixels, not the logical ones macroquad
+    // reports screen sizes in
+    let dpi = screen_dpi_scale();
+    let clip = area.map(|area| {
+        (
+            ((area.x * scale.scale + scale.offset_x) * dpi) as i32,
+            ((area.y * scale.scale + scale.offset_y) * dpi) as i32,
+            (area.w * scale.scale * dpi) as i32,
+            (area.h * scale.scale * dpi) as i32,
+        )
+    });
+    unsafe {
+        get_internal_gl().quad_gl.scissor(clip);
+    }
+}
+
+/// Draws `text` in the game font with its baseline at `y`.
+pub fn draw_label(text: &str, x: f32, y: f32, font_size: u16, color: Color) {
+    draw_text_ex(
+        text,
+        x,
+        y,
+        TextParams {
+            font: get_font(),
+            font_size,
+            color,
+            ..Default::default()
+        },
+    );
+}
+
+/// The game-over message centered above the board, with the restart hint
+/// under it.
+pub fn draw_end_banner(msg: &str, color: Color) {
+    let font = get_font();
+    let dim = measure_text(msg, font, 40, 1.0);
+    draw_label(msg, VIRTUAL_WIDTH / 2.0 - dim.width / 2.0, 110.0, 40, color);
+
+    let restart_msg = "PRESS R TO RESTART";
+    let r_dim = measure_text(restart_msg, font, 24, 1.0);
+    draw_label(
+        restart_msg,
+        VIRTUAL_WIDTH / 2.0 - r_dim.width / 2.0,
+        150.0,
+        24,
+        crate::theme::get_current_theme().text,
+    );
+}
+
+/// The symbol clicked this frame where either side may play X or O: left
+/// click for X, right click or Shift+click for O.
+pub fn pick_symbol() -> Option<CellState> {
+    let shift_down = is_key_down(KeyCode::LeftShift) || is_key_down(KeyCode::RightShift);
+    if is_mouse_button_pressed(MouseButton::Right) {
+        Some(CellState::O)
+    } else if is_mouse_button_pressed(MouseButton::Left) {
+        Some(if shift_down {
+            CellState::O
+        } else {
+            CellState::X
+        })
+    } else {
+        None
+    }
+}
+
+/// The keys every variant screen shares: T cycles the theme, R swaps the
+/// game in `screen` for `restart`'s, and ESC closes it for the menu.
+pub fn variant_controls<T>(
+    screen: &mut Option<T>,
+    restart: impl FnOnce(&T) -> T,
+) -> Option<GameState> {
+    if is_key_pressed(KeyCode::T) {
+        crate::theme::cycle_theme();
+    }
+    if is_key_pressed(KeyCode::R)
+        && let Some(game) = screen
+    {
+        *game = restart(game);
+    }
+    if is_key_pressed(KeyCode::Escape) {
+        *screen = None;
+        return Some(GameState::Menu);
+    }
+    None
+}
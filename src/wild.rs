@@ -0,0 +1,179 @@
+use crate::board::Board;
+use crate::config::*;
+use crate::player::AiWorker;
+use crate::state::GameState;
+use crate::utils::*;
+use macroquad::prelude::*;
+use tictactoe_core::CellState;
+use tictactoe_core::ai::Difficulty;
+use tictactoe_core::rng::Rng;
+use tictactoe_core::wild::{self, Wild, WildMove, WildOutcome};
+
+struct WildGame {
+    game: Wild,
+    board: Board,
+    ai: Option<Difficulty>,
+    human: usize, // the human's player number against the AI; 0 moves first
+    ai_worker: AiWorker<WildMove>,
+}
+
+impl WildGame {
+    fn new(ai: Option<Difficulty>, human: usize) -> Self {
+        let (size, win_length) = crate::game::board_config();
+        Self {
+            game: Wild::new(size, win_length),
+            board: Board::new(size, win_length),
+            ai,
+            human,
+            ai_worker: AiWorker::new(Rng::from_time()),
+        }
+    }
+
+    fn is_ai_turn(&self) -> bool {
+        self.ai.is_some() && !self.game.is_over() && self.game.turn() != self.human
+    }
+
+    /// Whether `player` is the human in a game against the AI.
+    fn is_human_vs_ai(&self, player: usize) -> bool {
+        self.ai.is_some() && player == self.human
+    }
+
+    fn player_name(&self, player: usize) -> &'static str {
+        match (self.ai, player == self.human) {
+            (Some(_), true) => "You",
+            (Some(_), false) => "AI",
+            (None, _) if player == 0 => "Player 1",
+            (None, _) => "Player 2",
+        }
+    }
+
+    /// Plays a legal move. Its particles take the color of the symbol placed,
+    /// since neither player owns one.
+    fn play(&mut self, mv: WildMove) {
+        if self.game.play(mv).is_err() {
+            return;
+        }
+
+        let (row, col) = mv.cell;
+        self.board.set_cell(row, col, mv.symbol);
+        self.board.check_winner();
+
+        let theme = crate::theme::get_current_theme();
+        let color = if mv.symbol == CellState::X {
+            theme.primary
+        } else {
+            theme.secondary
+        };
+        crate::config::play_move();
+        crate::particles::spawn_move(self.board.get_cell_center(row, col), color);
+
+        if let Some(WildOutcome::Win(_, line)) = self.game.outcome() {
+            crate::config::play_win();
+            for &(row, col) in line {
+                crate::particles::spawn_win(self.board.get_cell_center(row, col), color);
+            }
+        }
+    }
+}
+
+static mut WILD: Option<WildGame> = None;
+
+/// Starts a new game, against the AI at `ai` or between two people. `human`
+/// is 0 to move first against the AI, 1 to move second.
+pub fn start(ai: Option<Difficulty>, human: usize) {
+    unsafe {
+        WILD = Some(WildGame::new(ai, human));
+    }
+}
+
+#[allow(static_mut_refs)]
+pub fn update(scale: &ScreenScale) -> Option<GameState> {
+    let Some(wild) = (unsafe { WILD.as_mut() }) else {
+        return Some(GameState::Menu);
+    };
+
+    let mouse = mouse_to_virtual(scale);
+    let font = crate::config::get_font();
+    let theme = crate::theme::get_current_theme();
+
+    // ---- Draw UI ----
+    let mode_text = match wild.ai {
+        Some(difficulty) => format!("Wild vs AI ({})", difficulty.name()),
+        None => "Wild: Player vs Player".to_string(),
+    };
+    draw_label(&mode_text, 20.0, 40.0, 24, theme.text);
+    draw_label(
+        "ESC: Menu | R: Reset | T: Theme",
+        20.0,
+        70.0,
+        18,
+        theme.muted,
+    );
+
+    match wild.game.outcome() {
+        Some(outcome) => {
+            let (msg, color) = match outcome {
+                // Colors belong to the symbols, which both players use
+                WildOutcome::Win(winner, _) if wild.is_human_vs_ai(*winner) => {
+                    ("YOU WIN!".to_string(), theme.text)
+                }
+                WildOutcome::Win(winner, _) => (
+                    format!("{} WINS!", wild.player_name(*winner).to_uppercase()),
+                    theme.text,
+                ),
+                WildOutcome::Draw => ("IT'S A DRAW!".to_string(), theme.muted),
+            };
+            draw_end_banner(&msg, color);
+        }
+        None => {
+            let turn = wild.game.turn();
+            let turn_text = if wild.ai_worker.is_thinking() {
+                "AI is thinking...".to_string()
+            } else if wild.is_human_vs_ai(turn) {
+                "Your turn".to_string()
+            } else {
+                format!("Turn: {}", wild.player_name(turn))
+            };
+            let turn_dim = measure_text(&turn_text, font, 24, 1.0);
+            draw_label(
+                &turn_text,
+                VIRTUAL_WIDTH - turn_dim.width - 20.0,
+                40.0,
+                24,
+                theme.text,
+            );
+            draw_label(
+                "Left click: place X | Right click or Shift+click: place O",
+                20.0,
+                92.0,
+                16,
+                theme.muted,
+            );
+        }
+    }
+
+    // ---- Draw Board ----
+    wild.board.draw();
+
+    // ---- Moves ----
+    if wild.is_ai_turn() {
+        if let Some(mv) = wild.ai_worker.drive(
+            &wild.game,
+            wild.ai.unwrap_or(Difficulty::Perfect),
+            wild::find_best_move,
+        ) {
+            wild.play(mv);
+        }
+    } else if !wild.game.is_over()
+        && let Some(symbol) = pick_symbol()
+        && let Some(cell) = wild.board.get_cell_at(mouse)
+        && wild.board.state(cell.0, cell.1) == CellState::Empty
+    {
+        wild.play(WildMove { cell, symbol });
+    }
+
+    // ---- Controls ----
+    variant_controls(unsafe { &mut WILD }, |wild| {
+        WildGame::new(wild.ai, wild.human)
+    })
+}